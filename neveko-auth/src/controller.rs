@@ -1,8 +1,10 @@
 #![allow(non_snake_case)]
 
 use rocket::{
+    delete,
     get,
     http::Status,
+    post,
     response::status::Custom,
     serde::json::Json,
 };
@@ -20,3 +22,46 @@ pub async fn login(aid: String, uid: String, signature: String) -> Custom<Json<A
     let m_auth = auth::verify_login(aid, uid, signature).await;
     Custom(Status::Created, Json(m_auth.unwrap_or_default()))
 }
+
+/// Issue a fresh token for the session behind a valid token
+#[post("/refresh")]
+pub async fn refresh(token: auth::BearerToken) -> Custom<Json<Authorization>> {
+    let m_auth = auth::refresh(&token.get_token());
+    match m_auth {
+        Ok(a) => Custom(Status::Ok, Json(a)),
+        Err(_) => Custom(Status::Unauthorized, Json(Default::default())),
+    }
+}
+
+/// Mint a token for a new session limited to a comma separated list of
+///
+/// `scopes` (contacts, messages, market-admin, wallet)
+#[post("/<scopes>/<read_only>")]
+pub async fn mint_token(
+    scopes: String,
+    read_only: bool,
+    token: auth::AdminToken,
+) -> Custom<Json<Authorization>> {
+    let t_scopes = auth::parse_token_scopes(&scopes);
+    let m_auth = auth::mint(&token.get_token(), &t_scopes, read_only);
+    match m_auth {
+        Ok(a) => Custom(Status::Created, Json(a)),
+        Err(_) => Custom(Status::BadRequest, Json(Default::default())),
+    }
+}
+
+/// List active sessions
+#[get("/")]
+pub async fn get_sessions(_token: auth::AdminToken) -> Custom<Json<Vec<Session>>> {
+    let m_sessions = auth::find_sessions();
+    Custom(Status::Ok, Json(m_sessions.unwrap_or_default()))
+}
+
+/// Revoke a session, its tokens are rejected from then on
+#[delete("/<sid>")]
+pub async fn revoke_session(sid: String, _token: auth::AdminToken) -> Status {
+    match auth::revoke(&sid) {
+        Ok(_) => Status::Ok,
+        Err(_) => Status::InternalServerError,
+    }
+}
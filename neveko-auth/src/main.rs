@@ -14,5 +14,11 @@ async fn rocket() -> _ {
     };
     env_logger::init();
     log::info!("neveko-auth is online");
-    rocket::custom(&config).mount("/", routes![controller::login])
+    rocket::custom(&config)
+        .mount("/", routes![controller::login, controller::refresh])
+        .mount(
+            "/sessions",
+            routes![controller::get_sessions, controller::revoke_session],
+        )
+        .mount("/token", routes![controller::mint_token])
 }
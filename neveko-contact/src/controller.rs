@@ -12,6 +12,7 @@ use rocket::{
 use neveko_core::{
     auth,
     contact,
+    credit,
     models::*,
     proof,
     reqres,
@@ -21,7 +22,7 @@ use neveko_core::{
 #[post("/", data = "<req_contact>")]
 pub async fn add_contact(
     req_contact: Json<Contact>,
-    _token: auth::BearerToken,
+    _token: auth::ContactsToken,
 ) -> Custom<Json<Contact>> {
     let res_contact = contact::create(&req_contact).await;
     let u_contact = res_contact.unwrap_or_default();
@@ -33,7 +34,7 @@ pub async fn add_contact(
 
 /// Return all contacts
 #[get("/")]
-pub async fn get_contacts(_token: auth::BearerToken) -> Custom<Json<Vec<Contact>>> {
+pub async fn get_contacts(_token: auth::ContactsToken) -> Custom<Json<Vec<Contact>>> {
     let contacts = contact::find_all();
     Custom(Status::Ok, Json(contacts.unwrap_or_default()))
 }
@@ -42,7 +43,7 @@ pub async fn get_contacts(_token: auth::BearerToken) -> Custom<Json<Vec<Contact>
 #[delete("/remove/<contact>")]
 pub async fn remove_contact(
     contact: String,
-    _token: auth::BearerToken,
+    _token: auth::ContactsToken,
 ) -> Custom<Json<Vec<Contact>>> {
     let _ = contact::delete(&contact);
     let contacts = contact::find_all();
@@ -54,8 +55,123 @@ pub async fn remove_contact(
 pub async fn prove_payment(
     contact: String,
     proof_req: Json<proof::TxProof>,
-    _token: auth::BearerToken,
+    _token: auth::WalletToken,
 ) -> Custom<Json<reqres::Jwp>> {
     let r_jwp = proof::prove_payment(contact, &proof_req).await;
     Custom(Status::Ok, Json(r_jwp.unwrap()))
 }
+
+/// Block a contact by i2p address and revoke their jwps
+#[post("/block/<contact>")]
+pub async fn block_contact(
+    contact: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<Vec<String>>> {
+    if contact::block(&contact).is_err() {
+        return Custom(Status::InternalServerError, Json(Default::default()));
+    }
+    let blocked = contact::find_blocked();
+    Custom(Status::Ok, Json(blocked.unwrap_or_default()))
+}
+
+/// Unblock a contact by i2p address
+#[post("/unblock/<contact>")]
+pub async fn unblock_contact(
+    contact: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<Vec<String>>> {
+    if contact::unblock(&contact).is_err() {
+        return Custom(Status::InternalServerError, Json(Default::default()));
+    }
+    let blocked = contact::find_blocked();
+    Custom(Status::Ok, Json(blocked.unwrap_or_default()))
+}
+
+/// List the ids and expiry of the jwps issued to a contact
+#[get("/<contact>")]
+pub async fn get_issued_jwps(
+    contact: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<Vec<reqres::IssuedJwp>>> {
+    let issued = proof::find_issued_jwps(&contact);
+    Custom(Status::Ok, Json(issued.unwrap_or_default()))
+}
+
+/// Revoke a jwp issued to a contact
+#[post("/revoke/<contact>/<jid>")]
+pub async fn revoke_jwp(
+    contact: String,
+    jid: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<Vec<reqres::IssuedJwp>>> {
+    if proof::revoke(&contact, &jid).is_err() {
+        return Custom(Status::NotFound, Json(Default::default()));
+    }
+    let issued = proof::find_issued_jwps(&contact);
+    Custom(Status::Ok, Json(issued.unwrap_or_default()))
+}
+
+/// Get the jwp tiers offered to a contact
+#[get("/<contact>")]
+pub async fn get_tiers(
+    contact: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<Vec<reqres::Tier>>> {
+    Custom(Status::Ok, Json(proof::get_tiers(&contact)))
+}
+
+/// Override the jwp tiers for a contact. Set `pay_threshold` to zero for free access.
+#[post("/<contact>", data = "<tiers>")]
+pub async fn set_tiers(
+    contact: String,
+    tiers: Json<Vec<reqres::Tier>>,
+    _token: auth::ContactsToken,
+) -> Custom<Json<Vec<reqres::Tier>>> {
+    if proof::write_tier_overrides(&contact, &tiers).is_err() {
+        return Custom(Status::BadRequest, Json(Default::default()));
+    }
+    Custom(Status::Ok, Json(proof::get_tiers(&contact)))
+}
+
+/// Remove the jwp tier overrides for a contact
+#[delete("/<contact>")]
+pub async fn remove_tiers(
+    contact: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<Vec<reqres::Tier>>> {
+    let _ = proof::delete_tier_overrides(&contact);
+    Custom(Status::Ok, Json(proof::get_tiers(&contact)))
+}
+
+/// Credit balance and usage history a contact holds with us
+#[get("/<contact>")]
+pub async fn get_credit(
+    contact: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<CreditAccount>> {
+    let account = credit::find(&contact);
+    Custom(Status::Ok, Json(account.unwrap_or_default()))
+}
+
+/// Our credit balance and usage history held by a contact
+#[get("/remote/<contact>")]
+pub async fn get_remote_credit(
+    contact: String,
+    _token: auth::ContactsToken,
+) -> Custom<Json<CreditAccount>> {
+    let account = credit::fetch_balance(&contact).await;
+    Custom(Status::Ok, Json(account.unwrap_or_default()))
+}
+
+/// Pay a contact to top up our credit with them
+#[post("/topup/<contact>/<amount>")]
+pub async fn top_up_credit(
+    contact: String,
+    amount: u128,
+    _token: auth::WalletToken,
+) -> Custom<Json<reqres::Credit>> {
+    match credit::send_top_up(&contact, amount).await {
+        Ok(c) => Custom(Status::Ok, Json(c)),
+        Err(_) => Custom(Status::PaymentRequired, Json(Default::default())),
+    }
+}
@@ -18,7 +18,32 @@ async fn rocket() -> _ {
         .mount("/prove", routes![controller::prove_payment])
         .mount(
             "/contact",
-            routes![controller::add_contact, controller::remove_contact],
+            routes![
+                controller::add_contact,
+                controller::block_contact,
+                controller::remove_contact,
+                controller::unblock_contact
+            ],
+        )
+        .mount(
+            "/tier",
+            routes![
+                controller::get_tiers,
+                controller::remove_tiers,
+                controller::set_tiers
+            ],
+        )
+        .mount(
+            "/jwp",
+            routes![controller::get_issued_jwps, controller::revoke_jwp],
+        )
+        .mount(
+            "/credit",
+            routes![
+                controller::get_credit,
+                controller::get_remote_credit,
+                controller::top_up_credit
+            ],
         )
         .mount("/contacts", routes![controller::get_contacts])
 }
@@ -0,0 +1,305 @@
+//! Wallet accounts per purpose with subaddresses labelled by what they are for
+
+use crate::{
+    db::{
+        self,
+        DATABASE_LOCK,
+    },
+    error::NevekoError,
+    monero,
+    reqres,
+};
+use kn0sys_lmdb_rs::MdbError;
+use log::{
+    debug,
+    error,
+    info,
+    warn,
+};
+use std::collections::BTreeMap;
+
+/// Label of the primary address in account 0
+const PRIMARY_LABEL: &str = "primary";
+
+/// Wallet account incoming funds are kept in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountPurpose {
+    /// Invoice subaddresses for JWPs and credit top ups
+    Messaging,
+    /// Order payout subaddresses
+    Market,
+    /// Subaddresses disputed orders are refunded to
+    Refunds,
+}
+
+impl AccountPurpose {
+    pub const ALL: [AccountPurpose; 3] = [
+        AccountPurpose::Messaging,
+        AccountPurpose::Market,
+        AccountPurpose::Refunds,
+    ];
+
+    /// Also the account label
+    pub fn value(&self) -> String {
+        match *self {
+            AccountPurpose::Messaging => String::from("messaging"),
+            AccountPurpose::Market => String::from("market"),
+            AccountPurpose::Refunds => String::from("refunds"),
+        }
+    }
+
+    pub fn from_value(s: &str) -> Option<AccountPurpose> {
+        AccountPurpose::ALL.into_iter().find(|p| p.value() == s)
+    }
+}
+
+/// Subaddress label, `{purpose}:{reference}` where the reference is the
+///
+/// contact destination or order id.
+pub fn get_label(purpose: AccountPurpose, reference: &str) -> String {
+    format!("{}:{}", purpose.value(), reference)
+}
+
+/// Purpose and reference encoded in a label, `None` for labels not set by us
+pub fn parse_label(label: &str) -> Option<(AccountPurpose, String)> {
+    let (purpose, reference) = label.split_once(":")?;
+    Some((AccountPurpose::from_value(purpose)?, String::from(reference)))
+}
+
+/// Index of the purpose's account if it was set up already
+pub fn find_index(purpose: AccountPurpose) -> Option<u32> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::ACCOUNT_DB_KEY, purpose.value());
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec()).ok()?;
+    if r.is_empty() {
+        return None;
+    }
+    bincode::deserialize(&r[..]).ok()
+}
+
+fn write_index(purpose: AccountPurpose, index: u32) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::ACCOUNT_DB_KEY, purpose.value());
+    let v = bincode::serialize(&index).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+fn delete_index(purpose: AccountPurpose) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::ACCOUNT_DB_KEY, purpose.value());
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Whether a cached index still points at the purpose's account, it does not
+///
+/// after the app wallet was restored over an existing database.
+fn is_index_valid(
+    accounts: &[reqres::SubaddressAccount],
+    purpose: AccountPurpose,
+    index: u32,
+) -> bool {
+    accounts
+        .iter()
+        .any(|a| a.account_index == index && a.label == purpose.value())
+}
+
+/// Index of the purpose's account, created with the purpose as its label
+///
+/// the first time it is needed.
+pub async fn get_index(
+    wallet: &monero::WalletSession,
+    purpose: AccountPurpose,
+) -> Result<u32, NevekoError> {
+    if let Some(index) = find_index(purpose) {
+        return Ok(index);
+    }
+    let label = purpose.value();
+    let accounts = monero::get_accounts(wallet).await?.result.subaddress_accounts;
+    let index = match accounts.iter().find(|a| a.label == label) {
+        Some(a) => a.account_index,
+        None => {
+            info!("creating {} account", label);
+            monero::create_account(wallet, &label).await?.result.account_index
+        }
+    };
+    write_index(purpose, index)?;
+    Ok(index)
+}
+
+fn write_label(major: u64, minor: u64, label: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}-{}", crate::SUBADDRESS_LABEL_DB_KEY, major, minor);
+    let v = bincode::serialize(label).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Create a subaddress in the purpose's account labelled with the reference
+pub async fn create_address(
+    wallet: &monero::WalletSession,
+    purpose: AccountPurpose,
+    reference: &String,
+) -> Result<reqres::XmrRpcCreateAddressResult, NevekoError> {
+    let major = get_index(wallet, purpose).await?;
+    let result = monero::create_address(wallet, major).await?.result;
+    let label = get_label(purpose, reference);
+    debug!("labelling subaddress {}/{} {}", major, result.address_index, label);
+    monero::label_address(wallet, major as u64, result.address_index, &label).await?;
+    write_label(major as u64, result.address_index, &label)?;
+    Ok(result)
+}
+
+/// Purpose and reference of a subaddress created by `create_address`
+pub fn find_purpose(major: u64, minor: u64) -> Option<(AccountPurpose, String)> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}-{}", crate::SUBADDRESS_LABEL_DB_KEY, major, minor);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec()).ok()?;
+    if r.is_empty() {
+        return None;
+    }
+    let label: String = bincode::deserialize(&r[..]).ok()?;
+    parse_label(&label)
+}
+
+/// Like `find_purpose` but falls back to the wallet's own labels, which
+///
+/// outlive the cache.
+pub async fn lookup_purpose(
+    wallet: &monero::WalletSession,
+    major: u64,
+    minor: u64,
+) -> Option<(AccountPurpose, String)> {
+    if let Some(purpose) = find_purpose(major, minor) {
+        return Some(purpose);
+    }
+    let r = monero::get_subaddresses(wallet, major as u32, vec![minor as u32])
+        .await
+        .ok()?;
+    let label = String::from(&r.result.addresses.first()?.label);
+    let purpose = parse_label(&label)?;
+    let _ = write_label(major, minor, &label);
+    Some(purpose)
+}
+
+/// Purpose of an account for display, account 0 is the primary account
+pub fn get_account_label(index: u32) -> String {
+    AccountPurpose::ALL
+        .into_iter()
+        .find(|p| find_index(*p) == Some(index))
+        .map(|p| p.value())
+        .unwrap_or(String::from(if index == 0 { PRIMARY_LABEL } else { "" }))
+}
+
+/// Unlocked balance of every account with funds from an `all_accounts`
+///
+/// balance.
+pub fn get_unlocked_balances(balance: &reqres::XmrRpcBalanceResult) -> BTreeMap<u32, u128> {
+    let mut balances: BTreeMap<u32, u128> = BTreeMap::new();
+    for s in &balance.per_subaddress {
+        *balances.entry(s.account_index as u32).or_insert(0) += s.unlocked_balance;
+    }
+    balances
+}
+
+/// Account a payment of `amount` is sent from. A transfer spends from a
+///
+/// single account so the primary account is preferred, then the first
+///
+/// purpose account that covers it on its own.
+pub fn select_source(balances: &BTreeMap<u32, u128>, amount: u128) -> Option<u32> {
+    if balances.get(&0).is_some_and(|b| *b > amount) {
+        return Some(0);
+    }
+    balances.iter().find(|(_, b)| **b > amount).map(|(i, _)| *i)
+}
+
+/// Account to pay `amount` from in the session's wallet
+pub async fn find_source(
+    wallet: &monero::WalletSession,
+    amount: u128,
+) -> Result<Option<u32>, NevekoError> {
+    let balance = monero::get_balance(wallet).await?;
+    Ok(select_source(&get_unlocked_balances(&balance.result), amount))
+}
+
+/// Label the primary address and make sure every purpose has an account,
+///
+/// called on app startup. Cached indices the wallet doesn't have are dropped.
+pub async fn init(wallet: &monero::WalletSession) -> Result<(), NevekoError> {
+    monero::label_address(wallet, 0, 0, &String::from(PRIMARY_LABEL)).await?;
+    let accounts = monero::get_accounts(wallet).await?.result.subaddress_accounts;
+    for purpose in AccountPurpose::ALL {
+        if let Some(index) = find_index(purpose) {
+            if !is_index_valid(&accounts, purpose, index) {
+                warn!("{} account {} is not in the wallet", purpose.value(), index);
+                delete_index(purpose)?;
+            }
+        }
+        let index = get_index(wallet, purpose).await.map_err(|e| {
+            error!("failed to set up {} account", purpose.value());
+            e
+        })?;
+        debug!("{} account: {}", purpose.value(), index);
+    }
+    Ok(())
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_label_test() {
+        let label = get_label(AccountPurpose::Market, "o123");
+        assert_eq!(label, "market:o123");
+        assert_eq!(
+            parse_label(&label),
+            Some((AccountPurpose::Market, String::from("o123")))
+        );
+        let label = get_label(AccountPurpose::Messaging, "abc.b32.i2p");
+        assert_eq!(
+            parse_label(&label),
+            Some((AccountPurpose::Messaging, String::from("abc.b32.i2p")))
+        );
+        assert!(parse_label("primary").is_none());
+        assert!(parse_label("savings:x").is_none());
+    }
+
+    #[test]
+    fn is_index_valid_test() {
+        let accounts = vec![reqres::SubaddressAccount {
+            account_index: 1,
+            label: AccountPurpose::Messaging.value(),
+            ..Default::default()
+        }];
+        assert!(is_index_valid(&accounts, AccountPurpose::Messaging, 1));
+        assert!(!is_index_valid(&accounts, AccountPurpose::Messaging, 2));
+        assert!(!is_index_valid(&accounts, AccountPurpose::Market, 1));
+    }
+
+    #[test]
+    fn select_source_test() {
+        let subaddress = |account_index: u64, unlocked_balance: u128| reqres::SubAddressInfo {
+            account_index,
+            unlocked_balance,
+            ..Default::default()
+        };
+        let balance = reqres::XmrRpcBalanceResult {
+            per_subaddress: vec![subaddress(0, 5), subaddress(1, 20), subaddress(1, 30)],
+            ..Default::default()
+        };
+        let balances = get_unlocked_balances(&balance);
+        assert_eq!(balances.get(&1), Some(&50));
+        assert_eq!(select_source(&balances, 4), Some(0));
+        assert_eq!(select_source(&balances, 40), Some(1));
+        assert_eq!(select_source(&balances, 50), None);
+    }
+}
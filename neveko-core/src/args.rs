@@ -13,6 +13,13 @@ pub struct Args {
         default_value = "prod"
     )]
     pub release_env: String,
+    /// Monero network
+    #[arg(
+        long,
+        value_enum,
+        help = "Monero network (mainnet, stagenet, testnet), defaults to stagenet for dev"
+    )]
+    pub network: Option<crate::utils::Network>,
     /// Monero location
     #[arg(
         long,
@@ -27,6 +34,13 @@ pub struct Args {
         default_value = "http://localhost:18083"
     )]
     pub monero_rpc_host: String,
+    /// Monero RPC workers
+    #[arg(
+        long,
+        help = "Number of monero-wallet-rpc workers on consecutive ports from the rpc host",
+        default_value = "1"
+    )]
+    pub monero_rpc_workers: u16,
     /// Monero blockchain location
     #[arg(
         long,
@@ -71,6 +85,97 @@ pub struct Args {
         default_value = "720"
     )]
     pub confirmation_threshold: u64,
+    /// Market read payment threshold
+    #[arg(
+        long,
+        help = "Set a payment threshold in piconeros for browsing the market",
+        default_value = "1"
+    )]
+    pub market_read_payment_threshold: u128,
+    /// Market read confirmation threshold
+    #[arg(
+        long,
+        help = "Set a confirmation expiration for market browsing payments",
+        default_value = "720"
+    )]
+    pub market_read_confirmation_threshold: u64,
+    /// Market order payment threshold
+    #[arg(
+        long,
+        help = "Set a payment threshold in piconeros for placing orders",
+        default_value = "1"
+    )]
+    pub market_order_payment_threshold: u128,
+    /// Market order confirmation threshold
+    #[arg(
+        long,
+        help = "Set a confirmation expiration for order payments",
+        default_value = "720"
+    )]
+    pub market_order_confirmation_threshold: u64,
+    /// Message credit price
+    #[arg(
+        long,
+        help = "Set the price in piconeros debited from credit for each message",
+        default_value = "1"
+    )]
+    pub message_call_price: u128,
+    /// Market read credit price
+    #[arg(
+        long,
+        help = "Set the price in piconeros debited from credit for each product lookup",
+        default_value = "1"
+    )]
+    pub market_read_call_price: u128,
+    /// Market order credit price
+    #[arg(
+        long,
+        help = "Set the price in piconeros debited from credit for each order request",
+        default_value = "1"
+    )]
+    pub market_order_call_price: u128,
+    /// Proof-of-work admission difficulty
+    #[arg(
+        long,
+        help = "Leading zero bits required for proof-of-work admission, 0 disables it",
+        default_value = "0"
+    )]
+    pub pow_difficulty: u32,
+    /// Proof-of-work admission scopes
+    #[arg(
+        long,
+        help = "Comma separated scopes that accept proof-of-work admission",
+        default_value = "message"
+    )]
+    pub pow_scopes: String,
+    /// Proof-of-work only admission
+    #[arg(
+        long,
+        help = "Only accept proof-of-work, not payment, for proof-of-work scopes",
+        default_value = "false"
+    )]
+    pub pow_only: bool,
+    /// JWP renewal budget
+    #[arg(
+        long,
+        help = "Daily budget in piconeros for renewing JWPs before expiry, 0 disables renewal",
+        default_value = "0"
+    )]
+    pub jwp_renewal_budget: u128,
+    /// Daily spending cap
+    #[arg(
+        long,
+        help = "Daily cap in piconeros on all JWP payments, 0 for no cap",
+        default_value = "0"
+    )]
+    pub daily_spending_cap: u128,
+    /// Per-contact spending cap
+    #[arg(
+        long,
+        help = "Daily cap in piconeros on JWP payments to a single contact, 0 for no cap",
+        default_value = "0"
+    )]
+    pub contact_spending_cap: u128,
     /// Application port
     #[arg(long, help = "Set app port", default_value = "9000")]
     pub port: u16,
@@ -107,6 +212,13 @@ pub struct Args {
         default_value = "false"
     )]
     pub remote_node: bool,
+    /// More remote nodes to fail over to
+    #[arg(
+        long,
+        help = "comma separated remote nodes to fail over to besides --monero-rpc-daemon",
+        default_value = ""
+    )]
+    pub monero_remote_nodes: String,
     /// Dummy flag for normal mode when not using remote node. Future use.
     #[arg(
         long,
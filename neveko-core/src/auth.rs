@@ -20,7 +20,10 @@ use log::{
     info,
 };
 use rocket::{
-    http::Status,
+    http::{
+        Method,
+        Status,
+    },
     outcome::Outcome,
     request,
     request::FromRequest,
@@ -35,18 +38,79 @@ use jwt::*;
 use sha2::Sha384;
 use std::collections::BTreeMap;
 
+/// Areas of the internal micro servers a bearer token may access
+#[derive(Debug, PartialEq)]
+pub enum TokenScope {
+    Contacts,
+    Messages,
+    MarketAdmin,
+    Wallet,
+}
+
+impl TokenScope {
+    pub fn value(&self) -> String {
+        match *self {
+            TokenScope::Contacts => String::from("contacts"),
+            TokenScope::Messages => String::from("messages"),
+            TokenScope::MarketAdmin => String::from("market-admin"),
+            TokenScope::Wallet => String::from("wallet"),
+        }
+    }
+    pub fn from_value(v: &str) -> Option<TokenScope> {
+        match v {
+            "contacts" => Some(TokenScope::Contacts),
+            "messages" => Some(TokenScope::Messages),
+            "market-admin" => Some(TokenScope::MarketAdmin),
+            "wallet" => Some(TokenScope::Wallet),
+            _ => None,
+        }
+    }
+    pub fn all() -> Vec<TokenScope> {
+        vec![
+            TokenScope::Contacts,
+            TokenScope::Messages,
+            TokenScope::MarketAdmin,
+            TokenScope::Wallet,
+        ]
+    }
+}
+
+/// Parse a comma separated list of token scopes, unknown values are dropped
+pub fn parse_token_scopes(s: &str) -> Vec<TokenScope> {
+    s.split(",").filter_map(TokenScope::from_value).collect()
+}
+
+fn join_token_scopes(scopes: &[TokenScope]) -> String {
+    scopes.iter().map(|s| s.value()).collect::<Vec<String>>().join(",")
+}
+
 /// Create authorization data to sign and expiration
+///
+/// Login sessions have every scope with write access.
 pub fn create(address: &String) -> Result<Authorization, MdbError> {
+    create_session(address, &String::new(), &TokenScope::all(), false)
+}
+
+fn create_session(
+    address: &String,
+    uid: &String,
+    scopes: &[TokenScope],
+    read_only: bool,
+) -> Result<Authorization, MdbError> {
     info!("creating auth");
     let aid: String = format!("{}{}", crate::AUTH_DB_KEY, utils::generate_rnd());
     let rnd: String = utils::generate_rnd();
     let created: i64 = chrono::offset::Utc::now().timestamp();
-    let token: String = create_token(String::from(address), created);
+    let s_scopes = join_token_scopes(scopes);
+    let token: String =
+        create_token(String::from(address), &aid, &s_scopes, read_only, created);
     let new_auth = Authorization {
         aid,
         created,
-        uid: String::new(),
+        uid: String::from(uid),
         rnd,
+        scopes: s_scopes,
+        read_only,
         token,
         xmr_address: String::from(address),
     };
@@ -55,9 +119,39 @@ pub fn create(address: &String) -> Result<Authorization, MdbError> {
     let v = bincode::serialize(&new_auth).unwrap_or_default();
     let db = &DATABASE_LOCK;
     db::write_chunks(&db.env, &db.handle, k, &v)?;
+    // in order to list and revoke sessions they must be indexed
+    let list_key = crate::AUTH_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())?;
+    let old: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let auth_list = [String::from(&old), String::from(&new_auth.aid)].join(",");
+    debug!("writing auth index {} for id: {}", auth_list, list_key);
+    let s_auth_list = bincode::serialize(&auth_list).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_auth_list)?;
     Ok(new_auth)
 }
 
+/// Records written before token scopes are migrated to login sessions
+///
+/// with every scope. Their tokens lack the session claims `refresh` needs,
+///
+/// so a re-login is required for a new token.
+fn deserialize_auth(r: &[u8]) -> Authorization {
+    if let Ok(a) = bincode::deserialize::<Authorization>(r) {
+        return a;
+    }
+    let legacy: LegacyAuthorization = bincode::deserialize(r).unwrap_or_default();
+    Authorization {
+        aid: legacy.aid,
+        created: legacy.created,
+        rnd: legacy.rnd,
+        token: legacy.token,
+        uid: legacy.uid,
+        xmr_address: legacy.xmr_address,
+        scopes: join_token_scopes(&TokenScope::all()),
+        read_only: false,
+    }
+}
+
 /// Authorization lookup for recurring requests
 pub fn find(aid: &String) -> Result<Authorization, MdbError> {
     info!("searching for auth: {}", aid);
@@ -66,8 +160,7 @@ pub fn find(aid: &String) -> Result<Authorization, MdbError> {
     if r.is_empty() {
         return Err(MdbError::NotFound);
     }
-    let result: Authorization = bincode::deserialize(&r[..]).unwrap_or_default();
-    Ok(result)
+    Ok(deserialize_auth(&r[..]))
 }
 
 /// Update new authorization creation time
@@ -80,7 +173,13 @@ fn update_expiration(f_auth: &Authorization, address: &String) -> Result<Authori
         f_auth,
         time,
         data,
-        create_token(String::from(address), time),
+        create_token(
+            String::from(address),
+            &f_auth.aid,
+            &f_auth.scopes,
+            f_auth.read_only,
+            time,
+        ),
     );
     let db = &DATABASE_LOCK;
     db::DatabaseEnvironment::delete(&db.env, &db.handle, u_auth.aid.as_bytes())?;
@@ -90,6 +189,120 @@ fn update_expiration(f_auth: &Authorization, address: &String) -> Result<Authori
     Ok(u_auth)
 }
 
+/// Issue a new token for the session behind a still valid token.
+///
+/// Only the latest token of a session is accepted, so the old one is revoked.
+pub fn refresh(token: &String) -> Result<Authorization, MdbError> {
+    info!("refreshing auth token");
+    let claims = get_claims(token).ok_or(MdbError::NotFound)?;
+    let sid = claims.get("sid").cloned().unwrap_or_default();
+    let f_auth: Authorization = find(&sid)?;
+    let now: i64 = chrono::offset::Utc::now().timestamp();
+    if f_auth.xmr_address.is_empty() || f_auth.token != *token || is_token_expired(&claims, now) {
+        error!("session {} not found", sid);
+        return Err(MdbError::NotFound);
+    }
+    let time: i64 = chrono::offset::Utc::now().timestamp();
+    let r_token = create_token(
+        String::from(&f_auth.xmr_address),
+        &f_auth.aid,
+        &f_auth.scopes,
+        f_auth.read_only,
+        time,
+    );
+    let u_auth = Authorization {
+        created: time,
+        token: r_token,
+        ..f_auth
+    };
+    let db = &DATABASE_LOCK;
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, u_auth.aid.as_bytes())?;
+    let v = bincode::serialize(&u_auth).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, u_auth.aid.as_bytes(), &v)?;
+    Ok(u_auth)
+}
+
+/// Mint a token for a new session, e.g. a read-only token for a monitoring
+///
+/// script. The scopes must be held by the token minting it.
+pub fn mint(
+    token: &String,
+    scopes: &[TokenScope],
+    read_only: bool,
+) -> Result<Authorization, MdbError> {
+    let claims = get_claims(token).ok_or(MdbError::NotFound)?;
+    let sid = claims.get("sid").cloned().unwrap_or_default();
+    let f_auth: Authorization = find(&sid)?;
+    let held = parse_token_scopes(&f_auth.scopes);
+    if scopes.is_empty() || !scopes.iter().all(|s| held.contains(s)) {
+        error!("token scopes exceed those of session {}", sid);
+        return Err(MdbError::Panic);
+    }
+    info!("minting {} token", if read_only { "read-only" } else { "scoped" });
+    create_session(&f_auth.xmr_address, &f_auth.uid, scopes, read_only)
+}
+
+/// Active sessions, tokens and data to sign are not exposed.
+///
+/// Expired sessions are deleted and dropped from the index.
+pub fn find_sessions() -> Result<Vec<Session>, MdbError> {
+    let db = &DATABASE_LOCK;
+    let list_key = crate::AUTH_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())?;
+    let auth_list: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let now: i64 = chrono::offset::Utc::now().timestamp();
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut active: Vec<&str> = Vec::new();
+    for aid in auth_list.split(",").filter(|a| !a.is_empty()) {
+        let f_auth: Authorization = match find(&String::from(aid)) {
+            Ok(a) => a,
+            Err(_) => continue,
+        };
+        if f_auth.xmr_address.is_empty() {
+            continue;
+        }
+        let expire = f_auth.created + get_auth_expiration();
+        if now > expire {
+            debug!("pruning expired session: {}", aid);
+            db::DatabaseEnvironment::delete(&db.env, &db.handle, aid.as_bytes())?;
+            continue;
+        }
+        active.push(aid);
+        sessions.push(Session {
+            sid: f_auth.aid,
+            created: f_auth.created,
+            expire,
+            scopes: f_auth.scopes,
+            read_only: f_auth.read_only,
+            xmr_address: f_auth.xmr_address,
+        });
+    }
+    let u_auth_list = active.join(",");
+    if u_auth_list != auth_list {
+        let s_auth_list = bincode::serialize(&u_auth_list).unwrap_or_default();
+        db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_auth_list)?;
+    }
+    Ok(sessions)
+}
+
+/// Delete a session, tokens issued for it are no longer accepted
+pub fn revoke(sid: &String) -> Result<(), MdbError> {
+    info!("revoking session: {}", sid);
+    let db = &DATABASE_LOCK;
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, sid.as_bytes())?;
+    let list_key = crate::AUTH_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())?;
+    let old: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let auth_list = remove_from_index(&old, sid);
+    let s_auth_list = bincode::serialize(&auth_list).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_auth_list)?;
+    Ok(())
+}
+
+fn remove_from_index(list: &str, sid: &str) -> String {
+    list.split(",").filter(|a| !a.is_empty() && *a != sid).collect::<Vec<&str>>().join(",")
+}
+
 /// Performs the signature verfication against stored auth
 pub async fn verify_login(
     aid: String,
@@ -99,18 +312,22 @@ pub async fn verify_login(
     let wallet_name = String::from(crate::APP_NAME);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let m_address: reqres::XmrRpcAddressResponse = monero::get_address().await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password)
+        .await
+        .map_err(|_| MdbError::Panic)?;
+    let m_address: reqres::XmrRpcAddressResponse =
+        monero::get_address(&wallet).await.unwrap_or_default();
     let address = m_address.result.address;
     let f_auth: Authorization = find(&aid)?;
     if f_auth.xmr_address.is_empty() {
         error!("auth not found");
-        monero::close_wallet(&wallet_name, &wallet_password).await;
         return create(&address);
     }
     let data: String = String::from(&f_auth.rnd);
     let is_valid_sig: bool =
-        monero::verify(String::from(&address), data, String::from(&signature)).await;
+        monero::verify(&wallet, String::from(&address), data, String::from(&signature))
+            .await
+            .unwrap_or(false);
     let sig_address: String = if is_valid_sig {
         String::from(&address)
     } else {
@@ -118,7 +335,6 @@ pub async fn verify_login(
     };
     if sig_address == utils::ApplicationErrors::LoginError.value() {
         error!("signature validation failed");
-        monero::close_wallet(&wallet_name, &wallet_password).await;
         return Ok(f_auth);
     }
     let f_user: User = user::find(&uid)?;
@@ -131,26 +347,26 @@ pub async fn verify_login(
         db::DatabaseEnvironment::delete(&db.env, &db.handle, u_auth.aid.as_bytes())?;
         let v = bincode::serialize(&u_auth).unwrap_or_default();
         db::write_chunks(&db.env, &db.handle, u_auth.aid.as_bytes(), &v)?;
-        monero::close_wallet(&wallet_name, &wallet_password).await;
         Ok(u_auth)
     } else if !f_user.xmr_address.is_empty() {
         info!("returning user");
-        let m_access = verify_access(&address, &signature).await?;
+        let m_access = verify_access(&wallet, &address, &signature).await?;
         if !m_access {
-            monero::close_wallet(&wallet_name, &wallet_password).await;
             return Ok(Default::default());
         }
-        monero::close_wallet(&wallet_name, &wallet_password).await;
         return Ok(f_auth);
     } else {
         error!("error creating user");
-        monero::close_wallet(&wallet_name, &wallet_password).await;
         return Ok(Default::default());
     }
 }
 
 /// Called during auth flow to update data to sign and expiration
-async fn verify_access(address: &String, signature: &String) -> Result<bool, MdbError> {
+async fn verify_access(
+    wallet: &monero::WalletSession,
+    address: &String,
+    signature: &String,
+) -> Result<bool, MdbError> {
     // look up auth for address
     let f_auth: Authorization = find(address)?;
     if !f_auth.xmr_address.is_empty() {
@@ -165,7 +381,9 @@ async fn verify_access(address: &String, signature: &String) -> Result<bool, Mdb
     // verify signature on the data if not expired
     let data = f_auth.rnd;
     let is_valid_sig: bool =
-        monero::verify(String::from(address), data, String::from(signature)).await;
+        monero::verify(wallet, String::from(address), data, String::from(signature))
+            .await
+            .unwrap_or(false);
     let sig_address: String = if is_valid_sig {
         String::from(address)
     } else {
@@ -185,7 +403,13 @@ fn get_auth_expiration() -> i64 {
     args.token_timeout * 60
 }
 
-fn create_token(address: String, created: i64) -> String {
+fn create_token(
+    address: String,
+    sid: &String,
+    scopes: &String,
+    read_only: bool,
+    created: i64,
+) -> String {
     let jwt_secret_key = utils::get_jwt_secret_key().unwrap_or_default();
     let key: Hmac<Sha384> = Hmac::new_from_slice(jwt_secret_key.as_bytes()).expect("hash");
     let header = Header {
@@ -193,14 +417,49 @@ fn create_token(address: String, created: i64) -> String {
         ..Default::default()
     };
     let mut claims = BTreeMap::new();
-    let expiration = get_auth_expiration() * created;
+    let expiration = created + get_auth_expiration();
     claims.insert("address", address);
-    claims.insert("expiration", expiration.to_string());
+    claims.insert("sid", String::from(sid));
+    claims.insert("scopes", String::from(scopes));
+    claims.insert("read_only", read_only.to_string());
+    claims.insert("iat", created.to_string());
+    claims.insert("exp", expiration.to_string());
     let token = Token::new(header, claims).sign_with_key(&key);
     String::from(token.expect("expected token").as_str())
 }
 
-/// This token is used for internal micro server authentication
+/// Verified claims of a token signed with the current key
+fn get_claims(token: &str) -> Option<BTreeMap<String, String>> {
+    let jwt_secret_key = utils::get_jwt_secret_key().unwrap_or_default();
+    let key: Hmac<Sha384> = Hmac::new_from_slice(jwt_secret_key.as_bytes()).expect("hash");
+    let jwt: Result<
+        Token<jwt::Header, BTreeMap<std::string::String, std::string::String>, _>,
+        jwt::Error,
+    > = token.verify_with_key(&key);
+    jwt.ok().map(|j| j.claims().clone())
+}
+
+/// Whether the token claims grant `required`. Read-only tokens never grant
+///
+/// write access and an empty requirement accepts any scope.
+fn is_authorized(claims: &BTreeMap<String, String>, required: &[TokenScope], write: bool) -> bool {
+    let scopes = parse_token_scopes(&claims.get("scopes").cloned().unwrap_or_default());
+    let read_only = claims.get("read_only").map(|v| v != "false").unwrap_or(true);
+    if write && read_only {
+        return false;
+    }
+    required.iter().all(|s| scopes.contains(s))
+}
+
+fn is_token_expired(claims: &BTreeMap<String, String>, now: i64) -> bool {
+    let iat = claims.get("iat").and_then(|v| v.parse::<i64>().ok()).unwrap_or(0);
+    let exp = claims.get("exp").and_then(|v| v.parse::<i64>().ok()).unwrap_or(0);
+    iat > now || now > exp
+}
+
+/// This token is used for internal micro server authentication.
+///
+/// Any valid session is accepted regardless of scope.
 #[derive(Debug)]
 pub struct BearerToken(String);
 
@@ -210,11 +469,45 @@ impl BearerToken {
     }
 }
 
+/// Bearer token with the `contacts` scope, read-only tokens may only `GET`
+#[derive(Debug)]
+pub struct ContactsToken(String);
+
+/// Bearer token with the `messages` scope, read-only tokens may only `GET`
+#[derive(Debug)]
+pub struct MessagesToken(String);
+
+/// Bearer token with the `market-admin` scope, read-only tokens may only `GET`
+#[derive(Debug)]
+pub struct MarketAdminToken(String);
+
+/// Bearer token with write access to the `market-admin` scope, for routes
+///
+/// that change state on `GET`
+#[derive(Debug)]
+pub struct MarketAdminWriteToken(String);
+
+/// Bearer token with write access to the `wallet` scope
+#[derive(Debug)]
+pub struct WalletToken(String);
+
+/// Bearer token with write access to every scope, e.g. from login
+#[derive(Debug)]
+pub struct AdminToken(String);
+
+impl AdminToken {
+    pub fn get_token(self) -> String {
+        self.0
+    }
+}
+
 #[derive(Debug)]
 pub enum BearerTokenError {
     Expired,
     Missing,
     Invalid,
+    Revoked,
+    Scope,
 }
 
 #[rocket::async_trait]
@@ -222,56 +515,124 @@ impl<'r> FromRequest<'r> for BearerToken {
     type Error = BearerTokenError;
 
     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let env = utils::get_release_env();
-        let dev = utils::ReleaseEnvironment::Development;
-        if env == dev {
-            return Outcome::Success(BearerToken(String::new()));
-        }
-        let token = request.headers().get_one("token");
-        let wallet_name = String::from(crate::APP_NAME);
-        let wallet_password =
-            std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-        monero::open_wallet(&wallet_name, &wallet_password).await;
-        let m_address: reqres::XmrRpcAddressResponse = monero::get_address().await;
-        monero::close_wallet(&wallet_name, &wallet_password).await;
-        let address = m_address.result.address;
-        debug!("{}", address);
-        match token {
-            Some(token) => {
-                // check validity
-                let jwt_secret_key = utils::get_jwt_secret_key().unwrap_or_default();
-                let key: Hmac<Sha384> = Hmac::new_from_slice(jwt_secret_key.as_bytes()).expect("");
-                let jwt: Result<
-                    Token<jwt::Header, BTreeMap<std::string::String, std::string::String>, _>,
-                    jwt::Error,
-                > = token.verify_with_key(&key);
-                return match jwt {
-                    Ok(j) => {
-                        let claims = j.claims();
-                        debug!("claim address: {}", claims["address"]);
-                        // verify address
-                        if claims["address"] != address {
-                            return Outcome::Error((
-                                Status::Unauthorized,
-                                BearerTokenError::Invalid,
-                            ));
-                        }
-                        // verify expiration
-                        let now: i64 = chrono::offset::Utc::now().timestamp();
-                        let expire = claims["expiration"].parse::<i64>().unwrap_or(0);
-                        if now > expire {
-                            return Outcome::Error((
-                                Status::Unauthorized,
-                                BearerTokenError::Expired,
-                            ));
-                        }
-                        Outcome::Success(BearerToken(String::from(token)))
-                    }
-                    Err(_) => Outcome::Error((Status::Unauthorized, BearerTokenError::Invalid)),
-                };
+        validate_token(request, &[], false).await.map(BearerToken)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ContactsToken {
+    type Error = BearerTokenError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let write = request.method() != Method::Get;
+        validate_token(request, &[TokenScope::Contacts], write).await.map(ContactsToken)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MessagesToken {
+    type Error = BearerTokenError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let write = request.method() != Method::Get;
+        validate_token(request, &[TokenScope::Messages], write).await.map(MessagesToken)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MarketAdminToken {
+    type Error = BearerTokenError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let write = request.method() != Method::Get;
+        validate_token(request, &[TokenScope::MarketAdmin], write).await.map(MarketAdminToken)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MarketAdminWriteToken {
+    type Error = BearerTokenError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        validate_token(request, &[TokenScope::MarketAdmin], true)
+            .await
+            .map(MarketAdminWriteToken)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WalletToken {
+    type Error = BearerTokenError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        validate_token(request, &[TokenScope::Wallet], true).await.map(WalletToken)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminToken {
+    type Error = BearerTokenError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        validate_token(request, &TokenScope::all(), true).await.map(AdminToken)
+    }
+}
+
+/// Shared validation for the bearer token guards. An empty `required`
+///
+/// accepts any scope.
+async fn validate_token(
+    request: &Request<'_>,
+    required: &[TokenScope],
+    write: bool,
+) -> request::Outcome<String, BearerTokenError> {
+    let env = utils::get_release_env();
+    let dev = utils::ReleaseEnvironment::Development;
+    if env == dev {
+        return Outcome::Success(String::new());
+    }
+    let token = request.headers().get_one("token");
+    let wallet_name = String::from(crate::APP_NAME);
+    let wallet_password =
+        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+    let m_address: reqres::XmrRpcAddressResponse =
+        match monero::open_wallet(&wallet_name, &wallet_password).await {
+            Ok(wallet) => monero::get_address(&wallet).await.unwrap_or_default(),
+            Err(_) => Default::default(),
+        };
+    let address = m_address.result.address;
+    debug!("{}", address);
+    match token {
+        Some(token) => {
+            // check validity
+            let claims = match get_claims(token) {
+                Some(c) => c,
+                None => return Outcome::Error((Status::Unauthorized, BearerTokenError::Invalid)),
+            };
+            let claim_address = claims.get("address").cloned().unwrap_or_default();
+            debug!("claim address: {}", claim_address);
+            // verify address
+            if claim_address != address {
+                return Outcome::Error((Status::Unauthorized, BearerTokenError::Invalid));
             }
-            None => Outcome::Error((Status::Unauthorized, BearerTokenError::Missing)),
+            // verify expiration
+            let now: i64 = chrono::offset::Utc::now().timestamp();
+            if is_token_expired(&claims, now) {
+                return Outcome::Error((Status::Unauthorized, BearerTokenError::Expired));
+            }
+            // verify scope and access
+            if !is_authorized(&claims, required, write) {
+                return Outcome::Error((Status::Forbidden, BearerTokenError::Scope));
+            }
+            // verify the session has not been revoked or refreshed
+            let sid = claims.get("sid").cloned().unwrap_or_default();
+            let f_auth = find(&sid).unwrap_or_default();
+            if f_auth.xmr_address.is_empty() || f_auth.token != token {
+                return Outcome::Error((Status::Unauthorized, BearerTokenError::Revoked));
+            }
+            Outcome::Success(String::from(token))
         }
+        None => Outcome::Error((Status::Unauthorized, BearerTokenError::Missing)),
     }
 }
 
@@ -342,7 +703,9 @@ mod tests {
     #[test]
     fn create_token_test() -> Result<(), NevekoError> {
         let test_value = "test";
-        let test_jwt = create_token(String::from(test_value), 0);
+        let scopes = join_token_scopes(&TokenScope::all());
+        let sid = String::from("sid");
+        let test_jwt = create_token(String::from(test_value), &sid, &scopes, true, 0);
         let jwt_secret_key = utils::get_jwt_secret_key().unwrap_or_default();
         let key: Hmac<Sha384> = Hmac::new_from_slice(&jwt_secret_key.as_bytes()).expect("");
         let jwt: Result<
@@ -355,9 +718,104 @@ mod tests {
                 let expected = String::from(test_value);
                 let actual = String::from(&claims["address"]);
                 assert_eq!(expected, actual);
+                assert_eq!(claims["iat"], "0");
+                assert_eq!(claims["exp"], get_auth_expiration().to_string());
+                assert_eq!(claims["scopes"], "contacts,messages,market-admin,wallet");
+                assert_eq!(claims["read_only"], "true");
             }
             Err(_) => error!("create_token_test error"),
         };
         Ok(())
     }
+    #[test]
+    fn is_token_expired_test() {
+        let mut claims = BTreeMap::new();
+        claims.insert(String::from("iat"), String::from("100"));
+        claims.insert(String::from("exp"), String::from("200"));
+        assert!(!is_token_expired(&claims, 150));
+        assert!(is_token_expired(&claims, 201));
+        assert!(is_token_expired(&claims, 50));
+        assert!(is_token_expired(&BTreeMap::new(), 150));
+    }
+
+    #[test]
+    fn find_sessions_test() -> Result<(), MdbError> {
+        let address = String::from("find-sessions-test-address");
+        let test_auth = create(&address)?;
+        let expired = Authorization {
+            created: 0,
+            ..create(&address)?
+        };
+        let db = &DATABASE_LOCK;
+        let v = bincode::serialize(&expired).unwrap_or_default();
+        db::write_chunks(&db.env, &db.handle, expired.aid.as_bytes(), &v)?;
+        let sessions = find_sessions()?;
+        assert!(sessions.iter().any(|s| s.sid == test_auth.aid));
+        assert!(!sessions.iter().any(|s| s.sid == expired.aid));
+        assert!(find(&expired.aid).is_err());
+        revoke(&test_auth.aid)?;
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_test() -> Result<(), MdbError> {
+        let address = String::from("refresh-test-address");
+        let test_auth = create(&address)?;
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        let r_auth = refresh(&test_auth.token)?;
+        assert_ne!(r_auth.token, test_auth.token);
+        // the old token is revoked
+        assert!(refresh(&test_auth.token).is_err());
+        assert!(refresh(&r_auth.token).is_ok());
+        revoke(&test_auth.aid)?;
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_auth_test() {
+        let legacy = LegacyAuthorization {
+            aid: String::from("aid"),
+            created: 100,
+            xmr_address: String::from("address"),
+            ..Default::default()
+        };
+        let v = bincode::serialize(&legacy).unwrap_or_default();
+        let migrated = deserialize_auth(&v);
+        assert_eq!(migrated.aid, "aid");
+        assert_eq!(migrated.created, 100);
+        assert_eq!(migrated.xmr_address, "address");
+        assert_eq!(migrated.scopes, join_token_scopes(&TokenScope::all()));
+        assert!(!migrated.read_only);
+        let current = Authorization {
+            scopes: TokenScope::Wallet.value(),
+            read_only: true,
+            ..migrated
+        };
+        let v = bincode::serialize(&current).unwrap_or_default();
+        let f_auth = deserialize_auth(&v);
+        assert_eq!(f_auth.scopes, "wallet");
+        assert!(f_auth.read_only);
+    }
+
+    #[test]
+    fn remove_from_index_test() {
+        assert_eq!(remove_from_index(",a1,a2,a3", "a2"), "a1,a3");
+        assert_eq!(remove_from_index("a1", "a1"), "");
+    }
+
+    #[test]
+    fn is_authorized_test() {
+        let mut claims = BTreeMap::new();
+        claims.insert(String::from("scopes"), String::from("contacts,messages"));
+        claims.insert(String::from("read_only"), String::from("false"));
+        assert!(is_authorized(&claims, &[], true));
+        assert!(is_authorized(&claims, &[TokenScope::Contacts], true));
+        assert!(!is_authorized(&claims, &[TokenScope::Wallet], false));
+        assert!(!is_authorized(&claims, &TokenScope::all(), true));
+        claims.insert(String::from("read_only"), String::from("true"));
+        assert!(is_authorized(&claims, &[TokenScope::Messages], false));
+        assert!(!is_authorized(&claims, &[TokenScope::Messages], true));
+        // tokens without an access claim are treated as read-only
+        assert!(!is_authorized(&BTreeMap::new(), &[], true));
+    }
 }
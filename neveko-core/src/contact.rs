@@ -9,6 +9,9 @@ use crate::{
     i2p,
     models::*,
     monero,
+    peer,
+    presence,
+    proof,
     reqres,
     utils,
 };
@@ -98,7 +101,12 @@ pub fn delete(cid: &String) -> Result<(), MdbError> {
         error!("contact not found");
         return Err(MdbError::NotFound);
     }
+    let contact: Contact = bincode::deserialize(&r[..]).unwrap_or_default();
     db::DatabaseEnvironment::delete(&db.env, &db.handle, cid.as_bytes())?;
+    if !contact.i2p_address.is_empty() {
+        presence::delete(&contact.i2p_address)
+            .unwrap_or_else(|_| error!("failed to delete presence history"));
+    }
     Ok(())
 }
 
@@ -130,13 +138,17 @@ async fn validate_contact(j: &Json<Contact>) -> bool {
     let wallet_name = String::from(crate::APP_NAME);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let validate_address = monero::validate_address(&j.xmr_address).await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
+    let validate_address = match monero::open_wallet(&wallet_name, &wallet_password).await {
+        Ok(wallet) => monero::validate_address(&wallet, &j.xmr_address)
+            .await
+            .unwrap_or_default(),
+        Err(_) => Default::default(),
+    };
     j.cid.len() < utils::string_limit()
         && j.i2p_address.len() < utils::string_limit()
         && j.i2p_address.contains(".b32.i2p")
         && j.nmpk.len() < utils::npmk_limit()
+        && utils::is_network_address(&j.xmr_address, utils::get_network())
         && validate_address.result.valid
 }
 
@@ -154,9 +166,9 @@ pub async fn share() -> Result<Contact, NevekoError> {
     let wallet_name = String::from(crate::APP_NAME);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let m_address: reqres::XmrRpcAddressResponse = monero::get_address().await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let m_address: reqres::XmrRpcAddressResponse = monero::get_address(&wallet).await?;
+    drop(wallet);
     let nmpk = utils::get_nmpk()?;
     let i2p_address = i2p::get_destination(i2p::ServerTunnelType::App)?;
     let xmr_address = m_address.result.address;
@@ -178,6 +190,61 @@ pub fn exists(from: &String) -> Result<bool, MdbError> {
     Ok(addresses.contains(from))
 }
 
+/// Check if a contact's i2p address is on the block list
+pub fn is_blocked(i2p_address: &String) -> bool {
+    find_blocked()
+        .unwrap_or_default()
+        .iter()
+        .any(|b| b == i2p_address)
+}
+
+/// All blocked i2p addresses
+pub fn find_blocked() -> Result<Vec<String>, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let list_key = crate::BLOCKED_CONTACT_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let str_r: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let v: Vec<String> = str_r
+        .split(",")
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    Ok(v)
+}
+
+fn write_blocked(blocked: &[String]) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let list_key = crate::BLOCKED_CONTACT_LIST_DB_KEY;
+    let blocked_list = blocked.join(",");
+    debug!("writing blocked contact index {} for key {}", blocked_list, list_key);
+    let s_blocked_list = bincode::serialize(&blocked_list).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_blocked_list)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Block a contact and revoke all jwps issued to them
+pub fn block(i2p_address: &String) -> Result<(), NevekoError> {
+    info!("blocking contact: {}", i2p_address);
+    let mut blocked = find_blocked()?;
+    if !blocked.contains(i2p_address) {
+        blocked.push(String::from(i2p_address));
+        write_blocked(&blocked)?;
+    }
+    proof::revoke_all(i2p_address)
+}
+
+/// Remove a contact from the block list. Revoked jwps stay revoked.
+pub fn unblock(i2p_address: &String) -> Result<(), NevekoError> {
+    info!("unblocking contact: {}", i2p_address);
+    let blocked: Vec<String> = find_blocked()?
+        .into_iter()
+        .filter(|b| b != i2p_address)
+        .collect();
+    write_blocked(&blocked)
+}
+
 /// Get invoice for jwp creation
 pub async fn request_invoice(contact: String) -> Result<reqres::Invoice, Box<dyn Error>> {
     let host = utils::get_i2p_http_proxy();
@@ -185,6 +252,7 @@ pub async fn request_invoice(contact: String) -> Result<reqres::Invoice, Box<dyn
     let client = reqwest::Client::builder().proxy(proxy).build();
     match client?
         .get(format!("http://{}/invoice", contact))
+        .headers(peer::get_auth_headers(&contact).await)
         .send()
         .await
     {
@@ -292,6 +360,33 @@ mod tests {
         Runtime::shutdown_background(rt);
     }
 
+    #[test]
+    fn block_test() {
+        let i2p_address = String::from("block-test.b32.i2p");
+        let r = Redemption {
+            subaddress: String::from("block-test-subaddress"),
+            destination: String::from(&i2p_address),
+            jid: String::from("block-test-jid"),
+            ..Default::default()
+        };
+        let db = &DATABASE_LOCK;
+        let k = format!("{}-{}", crate::REDEEMED_DB_KEY, &r.subaddress);
+        let list_key = format!("{}-{}", crate::ISSUED_JWP_LIST_DB_KEY, &i2p_address);
+        let v = bincode::serialize(&r).unwrap_or_default();
+        let _ = db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v);
+        let v_list = bincode::serialize(&r.subaddress).unwrap_or_default();
+        let _ = db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &v_list);
+        block(&i2p_address).expect("block");
+        assert!(is_blocked(&i2p_address));
+        assert!(proof::find_redemption(&r.subaddress).unwrap_or_default().revoked);
+        unblock(&i2p_address).expect("unblock");
+        assert!(!is_blocked(&i2p_address));
+        // unblocking doesn't bring back revoked jwps
+        assert!(proof::find_redemption(&r.subaddress).unwrap_or_default().revoked);
+        let _ = cleanup(&k);
+        let _ = cleanup(&list_key);
+    }
+
     #[test]
     fn validate_test() {
         // run and async cleanup so the test doesn't fail when deleting test data
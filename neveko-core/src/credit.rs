@@ -0,0 +1,442 @@
+//! Prepaid per-contact credit as an alternative to a JWP per payment
+
+use crate::{
+    contact,
+    db::{
+        self,
+        DATABASE_LOCK,
+    },
+    error::NevekoError,
+    i2p,
+    ledger,
+    models::*,
+    monero,
+    peer,
+    proof::{
+        self,
+        PaymentProofError,
+        TxProof,
+    },
+    reqres,
+    utils,
+};
+use hmac::{
+    Hmac,
+    Mac,
+};
+use jwt::*;
+use kn0sys_lmdb_rs::MdbError;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    error,
+    info,
+};
+use sha2::Sha512;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    sync::Mutex,
+};
+
+lazy_static! {
+    /// Held while reading and writing back an account or claiming a payment
+    static ref ACCOUNTS: Mutex<()> = Mutex::new(());
+}
+
+/// Credit account lookup
+pub fn find(destination: &String) -> Result<CreditAccount, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::CREDIT_DB_KEY, destination);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        return Err(NevekoError::Database(MdbError::NotFound));
+    }
+    let result: CreditAccount = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+fn write(account: &CreditAccount) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::CREDIT_DB_KEY, &account.destination);
+    let v = bincode::serialize(account).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Apply an entry to an account, keeping the most recent history.
+///
+/// Debits larger than the balance are refused.
+pub fn apply(account: &mut CreditAccount, entry: CreditEntry) -> bool {
+    if entry.debit {
+        if entry.amount > account.balance {
+            return false;
+        }
+        account.balance -= entry.amount;
+    } else {
+        account.balance += entry.amount;
+    }
+    account.history.push(entry);
+    if account.history.len() > crate::CREDIT_HISTORY_LIMIT {
+        let excess = account.history.len() - crate::CREDIT_HISTORY_LIMIT;
+        account.history.drain(..excess);
+    }
+    true
+}
+
+/// Payments to a subaddress are either credited or redeemed for a jwp, never both
+pub fn is_credited(subaddress: &String) -> bool {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::CREDITED_DB_KEY, subaddress);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    !r.is_empty()
+}
+
+fn write_credited(subaddress: &String, hash: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::CREDITED_DB_KEY, subaddress);
+    let v = bincode::serialize(hash).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+fn delete_credited(subaddress: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::CREDITED_DB_KEY, subaddress);
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Mark a payment as credited, false when it was credited or redeemed already.
+///
+/// `proof::create_jwp` claims a payment the same way until its redemption
+///
+/// is written, so a payment can't be credited and redeemed at once.
+pub fn claim(txp: &TxProof) -> Result<bool, NevekoError> {
+    let _lock = ACCOUNTS.lock().unwrap_or_else(|e| e.into_inner());
+    if is_credited(&txp.subaddress) || proof::find_redemption(&txp.subaddress).is_ok() {
+        return Ok(false);
+    }
+    write_credited(&txp.subaddress, &txp.hash)?;
+    Ok(true)
+}
+
+/// Release a claim on a payment that was not credited after all
+pub fn release(subaddress: &String) {
+    delete_credited(subaddress)
+        .unwrap_or_else(|_| error!("failed to release claim on {}", subaddress));
+}
+
+/// Verify a claimed payment on-chain, the claim is released when it doesn't hold up
+async fn check_claimed_payment(txp: &TxProof) -> Option<u128> {
+    let received = if proof::validate_subaddress(&txp.subaddress).await {
+        proof::check_proof(txp).await.map(|(_, received)| received)
+    } else {
+        error!("invalid subaddress");
+        None
+    };
+    if received.is_none() {
+        release(&txp.subaddress);
+    }
+    received
+}
+
+/// Credit an amount to an account and return its balance
+fn credit_account(destination: &String, entry: CreditEntry) -> Result<CreditAccount, NevekoError> {
+    let _lock = ACCOUNTS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut account = find(destination).unwrap_or_default();
+    if account.tid.is_empty() {
+        account.destination = String::from(destination);
+        account.tid = utils::generate_rnd();
+    }
+    apply(&mut account, entry);
+    write(&account)?;
+    Ok(account)
+}
+
+fn create_token(destination: &String, tid: &String) -> String {
+    let jwp_secret_key = utils::get_jwp_secret_key().unwrap_or_default();
+    let key: Hmac<Sha512> = Hmac::new_from_slice(jwp_secret_key.as_bytes()).expect("hash");
+    let header = Header {
+        algorithm: AlgorithmType::Hs512,
+        ..Default::default()
+    };
+    let mut claims = BTreeMap::new();
+    let credit = String::from("credit");
+    claims.insert("destination", destination);
+    claims.insert("id", tid);
+    claims.insert("type", &credit);
+    let token = Token::new(header, claims).sign_with_key(&key);
+    String::from(token.expect("expected token").as_str())
+}
+
+/// Credit the amount proven by a transaction proof to the requester.
+///
+/// Accounts belong to contacts whose destination is authenticated by
+///
+/// the address book. The payment is claimed before it is checked on-chain
+///
+/// so concurrent top ups with the same proof can't credit it twice.
+///
+/// The returned token authorizes calls debited from the balance.
+pub async fn top_up(peer: &peer::Peer, txp: &TxProof) -> Result<reqres::Credit, NevekoError> {
+    let destination = &peer.destination;
+    info!("crediting payment from: {}", destination);
+    if !peer.is_contact || contact::is_blocked(destination) {
+        error!("refusing credit for: {}", destination);
+        return Err(NevekoError::Payment);
+    }
+    if !claim(txp)? {
+        error!("payment to {} was already redeemed", &txp.subaddress);
+        return Err(NevekoError::Payment);
+    }
+    let received = check_claimed_payment(txp).await.ok_or(NevekoError::Payment)?;
+    let entry = CreditEntry {
+        amount: received,
+        created: chrono::offset::Utc::now().timestamp(),
+        debit: false,
+        reference: String::from(&txp.hash),
+    };
+    let account = credit_account(destination, entry)?;
+    Ok(reqres::Credit {
+        balance: account.balance,
+        token: create_token(destination, &account.tid),
+    })
+}
+
+/// Verify a credit token presented by the requester and debit the price of the call
+pub fn validate(
+    token: &str,
+    requester: &peer::Peer,
+    price: u128,
+    reference: String,
+) -> Result<(), PaymentProofError> {
+    let jwp_secret_key = utils::get_jwp_secret_key().unwrap_or_default();
+    let key: Hmac<Sha512> = Hmac::new_from_slice(jwp_secret_key.as_bytes()).expect("");
+    let verified: Result<
+        Token<jwt::Header, BTreeMap<std::string::String, std::string::String>, _>,
+        jwt::Error,
+    > = token.verify_with_key(&key);
+    let t = verified.map_err(|e| {
+        error!("credit token error: {:?}", e);
+        PaymentProofError::Invalid
+    })?;
+    let claims = t.claims();
+    let destination = claims.get("destination").cloned().unwrap_or_default();
+    let is_credit = claims.get("type").map(|v| v == "credit").unwrap_or(false);
+    if !is_credit || !requester.is_contact || destination != requester.destination {
+        error!("credit token presented by the wrong destination");
+        return Err(PaymentProofError::Invalid);
+    }
+    if contact::is_blocked(&destination) {
+        return Err(PaymentProofError::Revoked);
+    }
+    // parallel calls must not debit the same balance
+    let _lock = ACCOUNTS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut account = find(&destination).map_err(|_| PaymentProofError::Invalid)?;
+    let tid = claims.get("id").cloned().unwrap_or_default();
+    if tid != account.tid {
+        return Err(PaymentProofError::Revoked);
+    }
+    if price == 0 {
+        return Ok(());
+    }
+    let entry = CreditEntry {
+        amount: price,
+        created: chrono::offset::Utc::now().timestamp(),
+        debit: true,
+        reference,
+    };
+    if !apply(&mut account, entry) {
+        error!("insufficient credit for: {}", destination);
+        return Err(PaymentProofError::Balance);
+    }
+    write(&account).map_err(|_| PaymentProofError::Invalid)
+}
+
+/// Lookup the credit token a contact issued to us
+pub fn find_token(contact: &String) -> String {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::CREDIT_TOKEN_DB_KEY, contact);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    bincode::deserialize(&r[..]).unwrap_or_default()
+}
+
+fn write_token(contact: &String, token: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::CREDIT_TOKEN_DB_KEY, contact);
+    let v = bincode::serialize(token).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Send the transaction proof of a top up to the contact
+async fn prove_top_up(contact: &String, txp: &TxProof) -> Result<reqres::Credit, Box<dyn Error>> {
+    let host = utils::get_i2p_http_proxy();
+    let proxy = reqwest::Proxy::http(&host)?;
+    let client = reqwest::Client::builder().proxy(proxy).build();
+    match client?
+        .post(format!("http://{}/credit", contact))
+        .headers(peer::get_auth_headers(contact).await)
+        .json(txp)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            let res = response.json::<reqres::Credit>().await;
+            debug!("credit top up response: {:?}", res);
+            match res {
+                Ok(r) => Ok(r),
+                _ => Ok(Default::default()),
+            }
+        }
+        Err(e) => {
+            error!("failed to top up credit due to: {:?}", e);
+            Ok(Default::default())
+        }
+    }
+}
+
+/// Pay a contact's invoice and credit the amount to our balance with them
+pub async fn send_top_up(contact: &String, amount: u128) -> Result<reqres::Credit, NevekoError> {
+    let invoice = contact::request_invoice(String::from(contact))
+        .await
+        .map_err(|_| NevekoError::Contact)?;
+    if invoice.address.is_empty() {
+        error!("failed to get invoice from: {}", contact);
+        return Err(NevekoError::Contact);
+    }
+    let wallet_name = String::from(crate::APP_NAME);
+    let wallet_password =
+        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let d = reqres::Destination {
+        address: String::from(&invoice.address),
+        amount,
+    };
+    let transfer = ledger::transfer(&wallet, contact, d).await?;
+    // don't hold the wallet while the payment propagates
+    drop(wallet);
+    // give the payment time to reach the contact's daemon
+    tokio::time::sleep(std::time::Duration::from_secs(crate::CREDIT_PROPAGATION_SECS)).await;
+    let mut txp = TxProof {
+        destination: i2p::get_destination(i2p::ServerTunnelType::App).unwrap_or_default(),
+        subaddress: String::from(&invoice.address),
+        hash: String::from(&transfer.result.tx_hash),
+        ..Default::default()
+    };
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let get_txp = monero::get_tx_proof(
+        &wallet,
+        TxProof {
+            subaddress: String::from(&txp.subaddress),
+            hash: String::from(&txp.hash),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap_or_default();
+    drop(wallet);
+    txp.signature = get_txp.result.signature;
+    let credit = prove_top_up(contact, &txp)
+        .await
+        .map_err(|_| NevekoError::Contact)?;
+    if credit.token.is_empty() {
+        error!("failed to top up credit with: {}", contact);
+        return Err(NevekoError::Payment);
+    }
+    write_token(contact, &credit.token)?;
+    Ok(credit)
+}
+
+/// Fetch our balance and usage history from a contact
+pub async fn fetch_balance(contact: &String) -> Result<CreditAccount, Box<dyn Error>> {
+    let host = utils::get_i2p_http_proxy();
+    let proxy = reqwest::Proxy::http(&host)?;
+    let client = reqwest::Client::builder().proxy(proxy).build();
+    match client?
+        .get(format!("http://{}/credit", contact))
+        .header("credit", find_token(contact))
+        .headers(peer::get_auth_headers(contact).await)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            let res = response.json::<CreditAccount>().await;
+            debug!("credit balance response: {:?}", res);
+            match res {
+                Ok(r) => Ok(r),
+                _ => Ok(Default::default()),
+            }
+        }
+        Err(e) => {
+            error!("failed to fetch credit balance due to: {:?}", e);
+            Ok(Default::default())
+        }
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_test() {
+        let mut account: CreditAccount = Default::default();
+        let top_up = CreditEntry {
+            amount: 10,
+            ..Default::default()
+        };
+        assert!(apply(&mut account, top_up));
+        let debit = CreditEntry {
+            amount: 4,
+            debit: true,
+            ..Default::default()
+        };
+        assert!(apply(&mut account, debit.clone()));
+        assert!(apply(&mut account, debit.clone()));
+        assert!(!apply(&mut account, debit));
+        assert_eq!(account.balance, 2);
+        assert_eq!(account.history.len(), 3);
+    }
+
+    #[test]
+    fn apply_history_limit_test() {
+        let mut account: CreditAccount = Default::default();
+        for i in 0..crate::CREDIT_HISTORY_LIMIT + 5 {
+            let entry = CreditEntry {
+                amount: 1,
+                created: i as i64,
+                ..Default::default()
+            };
+            apply(&mut account, entry);
+        }
+        assert_eq!(account.history.len(), crate::CREDIT_HISTORY_LIMIT);
+        assert_eq!(account.history[0].created, 5);
+    }
+
+    #[test]
+    fn claim_test() {
+        let txp = TxProof {
+            subaddress: String::from("claim-test-subaddress"),
+            hash: String::from("claim-test-hash"),
+            ..Default::default()
+        };
+        assert!(claim(&txp).expect("claim"));
+        // a payment is credited once
+        assert!(!claim(&txp).expect("claim"));
+        assert!(is_credited(&txp.subaddress));
+        delete_credited(&txp.subaddress).expect("delete claim");
+        assert!(!is_credited(&txp.subaddress));
+    }
+}
@@ -57,7 +57,7 @@ impl DatabaseEnvironment {
         info!("$LMDB_USER={}", user);
         info!("excecuting lmdb open");
         let file_path: String = format!("/home/{}/.{}/", user, "neveko");
-        let env_str = utils::get_release_env().value();
+        let env_str = utils::get_network().data_dir();
         let env: Environment = EnvBuilder::new()
             .map_size(env_map_size)
             .open(format!("{}/{}", file_path, env_str), 0o777)
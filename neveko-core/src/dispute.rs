@@ -3,6 +3,7 @@
 use std::error::Error;
 
 use crate::{
+    credit,
     db::{
         self,
         DATABASE_LOCK,
@@ -10,6 +11,7 @@ use crate::{
     error::NevekoError,
     models::*,
     monero,
+    peer,
     utils,
 };
 use kn0sys_lmdb_rs::MdbError;
@@ -146,10 +148,18 @@ pub async fn settle_dispute() -> Result<(), NevekoError> {
                 if settle_date > now {
                     let wallet_name = dispute.orid;
                     let wallet_password = String::new();
-                    monero::open_wallet(&wallet_name, &wallet_password).await;
-                    let signed = monero::sign_multisig(dispute.tx_set).await;
-                    let submit = monero::submit_multisig(signed.result.tx_data_hex).await;
-                    monero::close_wallet(&wallet_name, &wallet_password).await;
+                    let wallet = match monero::open_wallet(&wallet_name, &wallet_password).await
+                    {
+                        Ok(w) => w,
+                        Err(e) => {
+                            error!("could not open wallet for dispute {}: {}", &dispute.did, e);
+                            continue;
+                        }
+                    };
+                    let signed = monero::sign_multisig(&wallet, dispute.tx_set).await?;
+                    let submit =
+                        monero::submit_multisig(&wallet, signed.result.tx_data_hex).await?;
+                    drop(wallet);
                     if submit.result.tx_hash_list.is_empty() {
                         error!("could not broadcast txset for dispute: {}", &dispute.did);
                         return Ok(());
@@ -225,6 +235,8 @@ async fn transmit_dispute_request(
     match client?
         .post(format!("http://{}/market/dispute/create", contact))
         .header("proof", jwp)
+        .header("credit", credit::find_token(contact))
+        .headers(peer::get_auth_headers(contact).await)
         .json(&request)
         .send()
         .await
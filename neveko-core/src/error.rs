@@ -15,7 +15,98 @@ pub enum NevekoError {
     MoneroRpc,
     MoneroDaemon,
     Nasr,
+    /// LMDB or the app wallet belongs to another monero network
+    Network,
     Order,
+    Payment,
+    Process,
     Product,
+    /// Needs spend keys the view-only app wallet does not have
+    ReadOnly,
     Unknown,
 }
+
+/// Failures from monero-wallet-rpc. JSON-RPC error codes are mapped to
+///
+/// variants callers are expected to act on, the rest are kept as `Rpc`.
+#[derive(Debug, Error)]
+pub enum WalletRpcError {
+    #[error("wallet rpc is unreachable: {0}")]
+    Connection(String),
+    #[error("wallet rpc returned http status {0}")]
+    Http(u16),
+    #[error("invalid wallet rpc response: {0}")]
+    Parse(String),
+    #[error("no wallet is open")]
+    NotOpen,
+    #[error("wallet already exists")]
+    WalletExists,
+    #[error("invalid wallet password")]
+    InvalidPassword,
+    #[error("invalid address")]
+    WrongAddress,
+    #[error("not enough unlocked balance")]
+    NotEnoughMoney,
+    #[error("daemon is busy")]
+    DaemonBusy,
+    #[error("wallet is not connected to a daemon")]
+    NoDaemonConnection,
+    #[error("wallet is already multisig")]
+    AlreadyMultisig,
+    #[error("wallet is not multisig")]
+    NotMultisig,
+    #[error("invalid multisig info")]
+    BadMultisigInfo,
+    #[error("invalid multisig tx data")]
+    BadMultisigTxData,
+    #[error("multisig threshold not reached")]
+    ThresholdNotReached,
+    #[error("wallet is view-only")]
+    WatchOnly,
+    #[error("wallet rpc error {code}: {message}")]
+    Rpc { code: i64, message: String },
+}
+
+impl WalletRpcError {
+    /// Map a JSON-RPC error object, see `wallet_rpc_server_error_codes.h`
+    pub fn from_rpc(code: i64, message: String) -> WalletRpcError {
+        match code {
+            -2 => WalletRpcError::WrongAddress,
+            -3 => WalletRpcError::DaemonBusy,
+            -13 => WalletRpcError::NotOpen,
+            -17 | -37 => WalletRpcError::NotEnoughMoney,
+            -21 => WalletRpcError::WalletExists,
+            -22 => WalletRpcError::InvalidPassword,
+            -28 => WalletRpcError::AlreadyMultisig,
+            -29 => WalletRpcError::WatchOnly,
+            -30 => WalletRpcError::BadMultisigInfo,
+            -31 => WalletRpcError::NotMultisig,
+            -33 => WalletRpcError::ThresholdNotReached,
+            -34 => WalletRpcError::BadMultisigTxData,
+            -38 => WalletRpcError::NoDaemonConnection,
+            _ => WalletRpcError::Rpc { code, message },
+        }
+    }
+}
+
+impl From<WalletRpcError> for NevekoError {
+    fn from(_: WalletRpcError) -> Self {
+        NevekoError::MoneroRpc
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_rpc_test() {
+        let e = WalletRpcError::from_rpc(-17, String::from("not enough money"));
+        assert!(matches!(e, WalletRpcError::NotEnoughMoney));
+        let e = WalletRpcError::from_rpc(-99, String::from("unknown"));
+        assert!(matches!(e, WalletRpcError::Rpc { code: -99, .. }));
+    }
+}
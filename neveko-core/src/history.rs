@@ -0,0 +1,284 @@
+//! Wallet transaction history cached in LMDB and annotated by purpose
+
+use crate::{
+    account,
+    db::{
+        self,
+        DATABASE_LOCK,
+    },
+    error::NevekoError,
+    models::*,
+    monero,
+    order,
+    proof,
+    reqres,
+};
+use kn0sys_lmdb_rs::MdbError;
+use log::{
+    debug,
+    error,
+    info,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    In,
+    Out,
+    Pending,
+    Pool,
+}
+
+impl Direction {
+    pub fn value(&self) -> String {
+        match *self {
+            Direction::In => String::from("in"),
+            Direction::Out => String::from("out"),
+            Direction::Pending => String::from("pending"),
+            Direction::Pool => String::from("pool"),
+        }
+    }
+}
+
+/// What a transfer was for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Purpose {
+    /// Outgoing payment of a contact's invoice
+    JwpPayment,
+    /// Incoming payment of an invoice we issued
+    JwpInvoice,
+    /// Funds for or from an order
+    Order,
+    Sweep,
+    /// Payment to an arbitrary address from the send screen
+    Send,
+    /// Funds returned from a disputed order
+    Refund,
+    Unknown,
+}
+
+impl Purpose {
+    pub fn value(&self) -> String {
+        match *self {
+            Purpose::JwpPayment => String::from("jwp payment"),
+            Purpose::JwpInvoice => String::from("jwp invoice"),
+            Purpose::Order => String::from("order"),
+            Purpose::Sweep => String::from("sweep"),
+            Purpose::Send => String::from("send"),
+            Purpose::Refund => String::from("refund"),
+            Purpose::Unknown => String::new(),
+        }
+    }
+}
+
+/// Record what an outgoing transfer was for when it is made, since the
+///
+/// wallet can't tell us later.
+pub fn annotate(txid: &String, purpose: Purpose, reference: &String) -> Result<(), NevekoError> {
+    if txid.is_empty() {
+        return Ok(());
+    }
+    debug!("annotating {} as {}", txid, purpose.value());
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::TRANSFER_PURPOSE_DB_KEY, txid);
+    let v = bincode::serialize(&(purpose.value(), String::from(reference))).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+fn find_annotation(txid: &String) -> Option<(String, String)> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::TRANSFER_PURPOSE_DB_KEY, txid);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec()).ok()?;
+    if r.is_empty() {
+        return None;
+    }
+    bincode::deserialize(&r[..]).ok()
+}
+
+fn get_purpose(t: &reqres::Transfer, direction: Direction, orders: &[Order]) -> (String, String) {
+    if let Some(annotation) = find_annotation(&t.txid) {
+        return annotation;
+    }
+    if direction == Direction::In {
+        if let Ok(r) = proof::find_redemption(&t.address) {
+            return (Purpose::JwpInvoice.value(), r.destination);
+        }
+    }
+    let index = &t.subaddr_index;
+    if let Some((purpose, reference)) = account::find_purpose(index.major, index.minor) {
+        let purpose = match purpose {
+            account::AccountPurpose::Messaging => Purpose::JwpInvoice,
+            account::AccountPurpose::Market => Purpose::Order,
+            account::AccountPurpose::Refunds => Purpose::Refund,
+        };
+        return (purpose.value(), reference);
+    }
+    match orders.iter().find(|o| !o.subaddress.is_empty() && o.subaddress == t.address) {
+        Some(o) => (Purpose::Order.value(), String::from(&o.orid)),
+        None => (Purpose::Unknown.value(), String::new()),
+    }
+}
+
+fn to_wallet_transfer(
+    t: &reqres::Transfer,
+    direction: Direction,
+    orders: &[Order],
+) -> WalletTransfer {
+    let (purpose, reference) = get_purpose(t, direction, orders);
+    WalletTransfer {
+        // a tx can pay more than one of our subaddresses
+        tid: format!(
+            "{}-{}-{}-{}-{}",
+            crate::WALLET_TRANSFER_DB_KEY,
+            direction.value(),
+            t.subaddr_index.major,
+            t.subaddr_index.minor,
+            t.txid
+        ),
+        txid: String::from(&t.txid),
+        direction: direction.value(),
+        address: String::from(&t.address),
+        amount: t.amount,
+        fee: t.fee,
+        confirmations: t.confirmations.unwrap_or(0),
+        height: t.height,
+        timestamp: t.timestamp,
+        account_index: t.subaddr_index.major,
+        subaddr_index: t.subaddr_index.minor,
+        purpose,
+        reference,
+    }
+}
+
+/// Lookup a cached transfer
+pub fn find(tid: &String) -> Result<WalletTransfer, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &tid.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        error!("wallet transfer not found");
+        return Err(NevekoError::Database(MdbError::NotFound));
+    }
+    let result: WalletTransfer = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+fn find_tids() -> Result<Vec<String>, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let list_key = crate::WALLET_TRANSFER_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let str_r: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(str_r
+        .split(",")
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Whether a cached transfer passes a filter
+pub fn is_match(t: &WalletTransfer, filter: &monero::TransferFilter) -> bool {
+    let is_direction = match t.direction.as_str() {
+        "in" => filter.is_in,
+        "out" => filter.is_out,
+        "pending" => filter.is_pending,
+        "pool" => filter.is_pool,
+        _ => false,
+    };
+    is_direction
+        && (filter.subaddr_indices.is_empty()
+            || filter.subaddr_indices.iter().any(|i| *i as u64 == t.subaddr_index))
+}
+
+/// Cached transfers passing a filter, most recent first
+pub fn find_all(filter: &monero::TransferFilter) -> Result<Vec<WalletTransfer>, NevekoError> {
+    let mut transfers: Vec<WalletTransfer> = Vec::new();
+    for tid in find_tids()? {
+        let t = find(&tid)?;
+        if is_match(&t, filter) {
+            transfers.push(t);
+        }
+    }
+    // pool and pending transfers have no timestamp yet, keep them on top
+    transfers.sort_by_key(|t| {
+        let timestamp = if t.height == 0 { u64::MAX } else { t.timestamp };
+        std::cmp::Reverse(timestamp)
+    });
+    Ok(transfers)
+}
+
+/// Refresh the cache from the session's wallet. Transfers that left the pool
+///
+/// or failed are dropped from the cache.
+pub async fn sync(wallet: &monero::WalletSession) -> Result<usize, NevekoError> {
+    let r = monero::get_transfers(wallet, &monero::TransferFilter::all())
+        .await
+        .map_err(|e| {
+            error!("failed to fetch transfers: {}", e);
+            NevekoError::MoneroRpc
+        })?;
+    let orders = order::find_all().unwrap_or_default();
+    let old_tids = find_tids()?;
+    let mut tids: Vec<String> = Vec::new();
+    let db = &DATABASE_LOCK;
+    for (direction, transfers) in [
+        (Direction::In, r.result.r#in),
+        (Direction::Out, r.result.out),
+        (Direction::Pending, r.result.pending),
+        (Direction::Pool, r.result.pool),
+    ] {
+        for t in transfers {
+            // warm the label cache, accounts past the primary one are ours
+            if t.subaddr_index.major > 0 {
+                let _ =
+                    account::lookup_purpose(wallet, t.subaddr_index.major, t.subaddr_index.minor)
+                        .await;
+            }
+            let wt = to_wallet_transfer(&t, direction, &orders);
+            let v = bincode::serialize(&wt).unwrap_or_default();
+            db::write_chunks(&db.env, &db.handle, wt.tid.as_bytes(), &v)
+                .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+            tids.push(wt.tid);
+        }
+    }
+    for tid in old_tids.iter().filter(|t| !tids.contains(t)) {
+        db::DatabaseEnvironment::delete(&db.env, &db.handle, tid.as_bytes())
+            .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    }
+    let list_key = crate::WALLET_TRANSFER_LIST_DB_KEY;
+    let s_list = bincode::serialize(&tids.join(",")).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_list)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    info!("cached {} wallet transfers", tids.len());
+    Ok(tids.len())
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_match_test() {
+        let t = WalletTransfer {
+            direction: Direction::In.value(),
+            subaddr_index: 2,
+            ..Default::default()
+        };
+        assert!(is_match(&t, &monero::TransferFilter::all()));
+        let mut filter = monero::TransferFilter {
+            is_in: true,
+            subaddr_indices: vec![1],
+            ..Default::default()
+        };
+        assert!(!is_match(&t, &filter));
+        filter.subaddr_indices.push(2);
+        assert!(is_match(&t, &filter));
+        filter.is_in = false;
+        filter.is_out = true;
+        assert!(!is_match(&t, &filter));
+    }
+}
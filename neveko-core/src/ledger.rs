@@ -0,0 +1,293 @@
+//! Ledger of outgoing JWP payments and spending caps
+
+use crate::{
+    account,
+    args,
+    db::{
+        self,
+        DATABASE_LOCK,
+    },
+    error::NevekoError,
+    history,
+    models::*,
+    monero,
+    proof,
+    reqres,
+    utils,
+};
+use clap::Parser;
+use kn0sys_lmdb_rs::MdbError;
+use log::{
+    debug,
+    error,
+    info,
+};
+
+const SECONDS_IN_DAY: i64 = 60 * 60 * 24;
+
+fn read_cap(k: &str) -> Option<u128> {
+    let db = &DATABASE_LOCK;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    if r.is_empty() {
+        return None;
+    }
+    bincode::deserialize(&r[..]).ok()
+}
+
+fn write_cap(k: &str, cap: u128) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let v = bincode::serialize(&cap).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Daily cap on all payments. The value set from the gui takes
+///
+/// precedence over `--daily-spending-cap`. Zero means no cap.
+pub fn get_daily_cap() -> u128 {
+    read_cap(crate::DAILY_SPENDING_CAP_DB_KEY)
+        .unwrap_or_else(|| args::Args::parse().daily_spending_cap)
+}
+
+pub fn set_daily_cap(cap: u128) -> Result<(), NevekoError> {
+    write_cap(crate::DAILY_SPENDING_CAP_DB_KEY, cap)
+}
+
+/// Daily cap on payments to a single contact. The value set from
+///
+/// the gui takes precedence over `--contact-spending-cap`. Zero means no cap.
+pub fn get_contact_cap() -> u128 {
+    read_cap(crate::CONTACT_SPENDING_CAP_DB_KEY)
+        .unwrap_or_else(|| args::Args::parse().contact_spending_cap)
+}
+
+pub fn set_contact_cap(cap: u128) -> Result<(), NevekoError> {
+    write_cap(crate::CONTACT_SPENDING_CAP_DB_KEY, cap)
+}
+
+/// Payment lookup
+pub fn find(pid: &String) -> Result<Payment, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &pid.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        error!("payment not found");
+        return Err(NevekoError::Database(MdbError::NotFound));
+    }
+    let result: Payment = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+/// All payment lookup, most recent last
+pub fn find_all() -> Result<Vec<Payment>, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let list_key = crate::PAYMENT_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        info!("payment index not found");
+        return Ok(Vec::new());
+    }
+    let str_r: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let mut payments: Vec<Payment> = Vec::new();
+    for pid in str_r.split(",") {
+        if !pid.is_empty() {
+            payments.push(find(&String::from(pid))?);
+        }
+    }
+    Ok(payments)
+}
+
+fn write(p: &Payment) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let v = bincode::serialize(p).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, p.pid.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+fn create(
+    contact: &String,
+    transfer: &reqres::XmrRpcTransferResponse,
+) -> Result<Payment, NevekoError> {
+    let pid = format!("{}{}", crate::PAYMENT_DB_KEY, &transfer.result.tx_hash);
+    info!("creating payment: {}", pid);
+    let payment = Payment {
+        pid: String::from(&pid),
+        amount: transfer.result.amount,
+        contact: String::from(contact),
+        created: chrono::offset::Utc::now().timestamp(),
+        fee: transfer.result.fee,
+        hash: String::from(&transfer.result.tx_hash),
+        ..Default::default()
+    };
+    write(&payment)?;
+    // in order to retrieve all payments, write keys to payl
+    let db = &DATABASE_LOCK;
+    let list_key = crate::PAYMENT_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let old: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let payment_list = [old, pid].join(",");
+    debug!("writing payment index {} for key {}", payment_list, list_key);
+    let s_payment_list = bincode::serialize(&payment_list).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_payment_list)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(payment)
+}
+
+/// Record the jwp obtained with a payment along with its expiration
+pub fn set_jwp(hash: &String, jwp: &String) -> Result<(), NevekoError> {
+    let pid = format!("{}{}", crate::PAYMENT_DB_KEY, hash);
+    let mut payment = find(&pid)?;
+    payment.expire = proof::get_unverified_claims(jwp)
+        .get("expire")
+        .and_then(|e| e.parse::<u64>().ok())
+        .unwrap_or(0);
+    payment.jwp = String::from(jwp);
+    write(&payment)
+}
+
+/// Sum of amounts and fees paid today, optionally to a single contact
+pub fn get_spent_today(payments: &[Payment], contact: Option<&String>, now: i64) -> u128 {
+    let today = now / SECONDS_IN_DAY;
+    payments
+        .iter()
+        .filter(|p| p.created / SECONDS_IN_DAY == today)
+        .filter(|p| contact.map_or(true, |c| &p.contact == c))
+        .map(|p| p.amount + p.fee)
+        .sum()
+}
+
+/// Check a payment against the daily and per-contact caps
+pub fn check_caps(contact: &String, amount: u128) -> Result<(), NevekoError> {
+    let payments = find_all()?;
+    let now = chrono::offset::Utc::now().timestamp();
+    let daily_cap = get_daily_cap();
+    if daily_cap > 0 && get_spent_today(&payments, None, now) + amount > daily_cap {
+        error!("payment of {} piconero(s) exceeds the daily cap", amount);
+        return Err(NevekoError::Payment);
+    }
+    let contact_cap = get_contact_cap();
+    if contact_cap > 0 && get_spent_today(&payments, Some(contact), now) + amount > contact_cap {
+        error!("payment of {} piconero(s) to {} exceeds the contact cap", amount, contact);
+        return Err(NevekoError::Payment);
+    }
+    Ok(())
+}
+
+/// Pay an invoice from a contact out of the session's wallet and record it
+///
+/// in the ledger. Paid from whichever account covers it, see
+///
+/// `account::select_source`.
+pub async fn transfer(
+    wallet: &monero::WalletSession,
+    contact: &String,
+    d: reqres::Destination,
+) -> Result<reqres::XmrRpcTransferResponse, NevekoError> {
+    utils::check_spend_keys()?;
+    // caps count the fee of past payments, so this one's counts too
+    let priority = monero::FeePriority::Normal;
+    let fee = utils::estimate_fee(priority).await;
+    check_caps(contact, d.amount + fee)?;
+    let source = match account::find_source(wallet, d.amount + fee).await? {
+        Some(s) => s,
+        None => {
+            error!("no account can cover the payment to {}", contact);
+            return Err(NevekoError::Payment);
+        }
+    };
+    debug!("paying {} from account {}", contact, source);
+    let transfer = monero::transfer(wallet, d, priority, source).await.map_err(|e| {
+        error!("payment to {} failed: {}", contact, e);
+        NevekoError::MoneroRpc
+    })?;
+    if transfer.result.tx_hash.is_empty() {
+        error!("payment to {} failed", contact);
+        return Err(NevekoError::MoneroRpc);
+    }
+    create(contact, &transfer)?;
+    history::annotate(&transfer.result.tx_hash, history::Purpose::JwpPayment, contact)
+        .unwrap_or_else(|_| error!("failed to annotate payment to {}", contact));
+    Ok(transfer)
+}
+
+/// Render payments as csv with a header row
+pub fn to_csv(payments: &[Payment]) -> String {
+    let mut csv = String::from("created,contact,amount,fee,hash,expire,jwp\n");
+    for p in payments {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            p.created, p.contact, p.amount, p.fee, p.hash, p.expire, p.jwp
+        ));
+    }
+    csv
+}
+
+/// Write the ledger to `~/.neveko/payments.csv` and return the path
+pub fn export_csv() -> Result<String, NevekoError> {
+    let payments = find_all()?;
+    let file_path = format!(
+        "/home/{}/.neveko/payments.csv",
+        std::env::var("USER").unwrap_or(String::from("user"))
+    );
+    std::fs::write(&file_path, to_csv(&payments)).map_err(|_| NevekoError::Payment)?;
+    info!("exported payment ledger to: {}", file_path);
+    Ok(file_path)
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_payments() -> Vec<Payment> {
+        vec![
+            Payment {
+                amount: 10,
+                contact: String::from("a.b32.i2p"),
+                created: SECONDS_IN_DAY + 1,
+                fee: 1,
+                ..Default::default()
+            },
+            Payment {
+                amount: 20,
+                contact: String::from("b.b32.i2p"),
+                created: SECONDS_IN_DAY + 2,
+                fee: 2,
+                ..Default::default()
+            },
+            Payment {
+                amount: 40,
+                contact: String::from("a.b32.i2p"),
+                created: 1,
+                fee: 4,
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn get_spent_today_test() {
+        let payments = test_payments();
+        let now = SECONDS_IN_DAY + 100;
+        let contact = String::from("a.b32.i2p");
+        assert_eq!(get_spent_today(&payments, None, now), 33);
+        assert_eq!(get_spent_today(&payments, Some(&contact), now), 11);
+    }
+
+    #[test]
+    fn to_csv_test() {
+        let csv = to_csv(&test_payments()[..1]);
+        let expected = format!(
+            "created,contact,amount,fee,hash,expire,jwp\n{},a.b32.i2p,10,1,,0,\n",
+            SECONDS_IN_DAY + 1
+        );
+        assert_eq!(csv, expected);
+    }
+}
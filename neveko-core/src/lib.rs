@@ -1,18 +1,29 @@
+pub mod account;
 pub mod args;
 pub mod auth;
 pub mod contact;
+pub mod credit;
 pub mod neveko25519;
 pub mod dispute;
 pub mod db;
 pub mod error;
+pub mod history;
 pub mod i2p;
+pub mod ledger;
 pub mod message;
 pub mod models;
 pub mod monero;
+pub mod node;
 pub mod order;
+pub mod peer;
+pub mod pow;
+pub mod presence;
 pub mod product;
 pub mod proof;
+pub mod renewal;
 pub mod reqres;
+pub mod send;
+pub mod supervisor;
 pub mod user;
 pub mod utils;
 
@@ -33,6 +44,7 @@ pub const MESSAGE_DB_KEY:               &str = "m";
 pub const ORDER_DB_KEY:                 &str = "o";
 pub const PRODUCT_DB_KEY:               &str = "p";
 pub const USER_DB_KEY:                  &str = "u";
+pub const AUTH_LIST_DB_KEY:             &str = "al";
 pub const CONTACT_LIST_DB_KEY:          &str = "cl";
 pub const DISPUTE_LIST_DB_KEY:          &str = "dl";
 pub const MESSAGE_LIST_DB_KEY:          &str = "ml";
@@ -47,10 +59,42 @@ pub const MSIG_MESSAGE_DB_KEY:          &str = "msig";
 pub const MSIG_MESSAGE_LIST_DB_KEY:     &str = "msigl";
 pub const FTS_JWP_DB_KEY:               &str = "fts-jwp";
 pub const DELIVERY_INFO_DB_KEY:         &str = "delivery";
+pub const PRESENCE_DB_KEY:              &str = "pres";
+pub const CHAIN_HEIGHT_DB_KEY:          &str = "chain-height";
+pub const JWP_CHECK_DB_KEY:             &str = "jwp-check";
+pub const REDEEMED_DB_KEY:              &str = "redeemed";
+pub const ISSUED_JWP_LIST_DB_KEY:       &str = "ijl";
+pub const BLOCKED_CONTACT_LIST_DB_KEY:  &str = "bcl";
+pub const TIER_OVERRIDE_DB_KEY:         &str = "tier";
+pub const JWP_RENEWAL_BUDGET_DB_KEY:    &str = "jwp-renewal-budget";
+pub const JWP_RENEWAL_SPENT_DB_KEY:     &str = "jwp-renewal-spent";
+pub const PAYMENT_DB_KEY:               &str = "pay";
+pub const PAYMENT_LIST_DB_KEY:          &str = "payl";
+pub const DAILY_SPENDING_CAP_DB_KEY:    &str = "cap-daily";
+pub const CONTACT_SPENDING_CAP_DB_KEY:  &str = "cap-contact";
+pub const INVOICE_DB_KEY:               &str = "inv";
+pub const INVOICE_LIST_DB_KEY:          &str = "invl";
+pub const SUBADDRESS_DB_KEY:            &str = "sub";
+pub const CREDIT_DB_KEY:                &str = "cred";
+pub const CREDITED_DB_KEY:              &str = "credited";
+pub const CREDIT_TOKEN_DB_KEY:          &str = "cred-token";
+pub const ORDER_CHALLENGE_DB_KEY:       &str = "och";
+pub const REMOTE_NODE_LIST_DB_KEY:      &str = "rnl";
+pub const REMOTE_NODE_CURRENT_DB_KEY:   &str = "rnc";
+pub const FEE_ESTIMATE_DB_KEY:          &str = "fee";
+pub const WALLET_TRANSFER_DB_KEY:       &str = "wt";
+pub const WALLET_TRANSFER_LIST_DB_KEY:  &str = "wtl";
+pub const TRANSFER_PURPOSE_DB_KEY:      &str = "wtp";
+pub const READ_ONLY_DB_KEY:             &str = "read-only";
+pub const ACCOUNT_DB_KEY:               &str = "acct";
+pub const SUBADDRESS_LABEL_DB_KEY:      &str = "sub-label";
+pub const NETWORK_DB_KEY:               &str = "network";
 // End LMDB Keys
 
 /// Environment variable for injecting wallet password
 pub const MONERO_WALLET_PASSWORD: &str = "MONERO_WALLET_PASSWORD";
+/// File under `~/.neveko` holding the seed or keys to restore the app wallet from
+pub const WALLET_RESTORE_FILE: &str = "restore.json";
 /// Environment variable for I2P proxy host
 pub const NEVEKO_I2P_PROXY_HOST: &str = "NEVEKO_I2P_PROXY_HOST";
 /// Environment variable for I2P advanced mode
@@ -59,6 +103,8 @@ pub const NEVEKO_I2P_ADVANCED_MODE: &str = "NEVEKO_I2P_ADVANCED_MODE";
 pub const MONERO_DAEMON_HOST: &str = "MONERO_DAEMON_HOST";
 /// Environment variable for I2P advanced mode
 pub const MONERO_WALLET_RPC_HOST: &str = "MONERO_WALLET_RPC_HOST";
+/// Environment variable for the number of wallet rpc workers the gui started core with
+pub const MONERO_WALLET_RPC_WORKERS: &str = "MONERO_WALLET_RPC_WORKERS";
 /// Reference to check if gui set remote node flag
 pub const GUI_REMOTE_NODE: &str = "GUI_REMOTE_NODE";
 pub const GUI_SET_REMOTE_NODE: &str = "1";
@@ -67,6 +113,76 @@ pub const FTS_RETRY_INTERVAL: u32 = 60000;
 pub const DISPUTE_AUTO_SETTLE: u32 = 1000 * 60 * 60 * 24 * 7;
 /// Daily dispute auto-settle check interval
 pub const DISPUTE_CHECK_INTERVAL: u32 = 1000 * 60 * 60 * 24;
+/// Contact presence probe interval
+pub const PRESENCE_CHECK_INTERVAL: u32 = 1000 * 60 * 5;
+/// Number of probes kept in a contact's reachability history
+pub const PRESENCE_HISTORY_LIMIT: usize = 48;
+/// Remote node health check interval
+pub const REMOTE_NODE_CHECK_INTERVAL: u32 = 1000 * 60 * 2;
+/// Fail over when the current remote node is this many blocks behind the best
+pub const REMOTE_NODE_MAX_LAG_BLOCKS: u64 = 3;
+/// A remote node that has not answered a probe within this is treated as offline
+pub const REMOTE_NODE_PROBE_TIMEOUT_SECS: u64 = 30;
+/// Cached fee estimates are refetched after this even if the height has not moved
+pub const FEE_ESTIMATE_TTL_SECS: i64 = 60 * 5;
+/// Approximate weight in bytes of a 2 input 2 output transaction
+pub const FEE_ESTIMATE_TX_WEIGHT: u64 = 1500;
+/// Refresh the cached chain height used for offline JWP validation
+pub const CHAIN_HEIGHT_REFRESH_INTERVAL: u32 = 1000 * 60 * 2;
+/// JWPs validated offline are re-checked against the blockchain this often
+pub const JWP_RECHECK_INTERVAL: u32 = 1000 * 60 * 60;
+/// Interval for checking cached JWPs that are close to expiry
+pub const JWP_RENEWAL_INTERVAL: u32 = 1000 * 60 * 10;
+/// Renew cached JWPs with this many blocks or less remaining
+pub const JWP_RENEWAL_LEAD_BLOCKS: u64 = 30;
+/// Time for a renewal payment to reach the contact's daemon
+pub const JWP_RENEWAL_PROPAGATION_SECS: u64 = 60;
+/// Signed requests from peers are accepted this long around their timestamp
+pub const PEER_AUTH_WINDOW_SECS: i64 = 60 * 5;
+/// Unpaid invoices are handed out again to the same destination until they expire
+pub const INVOICE_EXPIRATION_SECS: i64 = 60 * 60;
+/// Invoice requests allowed per destination within the rate limit window
+pub const INVOICE_RATE_LIMIT: usize = 10;
+/// Invoice requests allowed from all peers outside the address book together
+pub const INVOICE_GLOBAL_RATE_LIMIT: usize = 60;
+/// Sliding window for invoice rate limits
+pub const INVOICE_RATE_WINDOW_SECS: i64 = 60;
+/// Cap on unpaid invoices outstanding for peers outside the address book
+pub const INVOICE_OUTSTANDING_LIMIT: usize = 100;
+/// Number of entries kept in a credit account's usage history
+pub const CREDIT_HISTORY_LIMIT: usize = 100;
+/// Time for a credit top up to reach the contact's daemon
+pub const CREDIT_PROPAGATION_SECS: u64 = 60;
+/// Proof-of-work challenges must be solved and spent within this time
+pub const POW_CHALLENGE_EXPIRATION_SECS: i64 = 60 * 10;
+/// Challenges issued to all peers outside the address book within the rate limit window
+pub const POW_RATE_LIMIT: usize = 60;
+/// Sliding window for the proof-of-work challenge rate limit
+pub const POW_RATE_WINDOW_SECS: i64 = 60;
+/// Cap on unspent challenges held for peers outside the address book
+pub const POW_OUTSTANDING_LIMIT: usize = 1000;
+/// Refuse to solve challenges from contacts asking for more leading zero bits
+pub const POW_MAX_DIFFICULTY: u32 = 32;
+/// Order retrieval and cancellation challenges must be signed within this time
+pub const ORDER_CHALLENGE_EXPIRATION_SECS: i64 = 60 * 5;
+/// Upper bound on monero-wallet-rpc workers
+pub const MONERO_RPC_WORKERS_MAX: u16 = 16;
+/// Keep a released wallet open this long in case the next session wants it
+pub const WALLET_SESSION_LINGER_MILLIS: u64 = 1000 * 5;
+/// How often supervised children are checked and readiness is polled
+pub const SUPERVISOR_POLL_MILLIS: u64 = 1000;
+/// First restart delay for a crashed child, doubled on each restart
+pub const SUPERVISOR_BACKOFF_BASE_MILLIS: u64 = 1000;
+/// Longest restart delay, a child up this long has its restarts forgotten
+pub const SUPERVISOR_BACKOFF_MAX_MILLIS: u64 = 1000 * 60;
+/// Give up waiting for monerod or monero-wallet-rpc to answer after this
+pub const SUPERVISOR_READY_TIMEOUT_SECS: u64 = 60;
+/// Kill a child that has not exited this long after SIGTERM
+pub const SUPERVISOR_STOP_TIMEOUT_SECS: u64 = 10;
+/// Rotate a child log once it grows past this size
+pub const SUPERVISOR_LOG_MAX_BYTES: u64 = 1024 * 1024 * 10;
+/// Rotated logs kept per child
+pub const SUPERVISOR_LOG_KEEP: usize = 3;
 /// Default app port
 pub const DEFAULT_HTTP_PROXY_PORT: u16 = 4455;
 /// Default app port
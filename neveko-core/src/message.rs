@@ -2,6 +2,7 @@
 
 use crate::{
     contact,
+    credit,
     db::{
         self,
         DATABASE_LOCK,
@@ -12,6 +13,9 @@ use crate::{
     monero,
     neveko25519,
     order,
+    peer,
+    pow,
+    presence,
     reqres,
     utils,
 };
@@ -329,13 +333,21 @@ async fn send_message(out: &Message, jwp: &str, m_type: MessageType) -> Result<(
         url = format!("http://{}/message/rx/multisig", out.to)
     }
     // check if the contact is online
-    let is_online: bool = is_contact_online(&out.to, String::from(jwp))
-        .await
-        .unwrap_or(false);
+    let is_online: bool = presence::check(&out.to).await.unwrap_or(false);
     if is_online {
+        // strangers without a jwp or credit may be admitted with proof-of-work
+        let credit_token = credit::find_token(&out.to);
+        let stamp = if jwp.is_empty() && credit_token.is_empty() {
+            pow::request_stamp(&out.to).await.unwrap_or_default()
+        } else {
+            String::new()
+        };
         match client?
             .post(url)
             .header("proof", jwp)
+            .header("credit", credit_token)
+            .header("pow", stamp)
+            .headers(peer::get_auth_headers(&out.to).await)
             .json(&out)
             .send()
             .await
@@ -379,38 +391,6 @@ pub fn delete(mid: &String) -> Result<(), NevekoError> {
     Ok(())
 }
 
-/// ping the contact health check over i2p
-async fn is_contact_online(contact: &String, jwp: String) -> Result<bool, Box<dyn Error>> {
-    let host = utils::get_i2p_http_proxy();
-    let proxy = reqwest::Proxy::http(&host)?;
-    let client = reqwest::Client::builder().proxy(proxy).build();
-    match client?
-        .get(format!("http://{}/xmr/rpc/version", contact))
-        .header("proof", jwp)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcVersionResponse>().await;
-            debug!("check is contact online by version response: {:?}", res);
-            match res {
-                Ok(r) => {
-                    if r.result.version != monero::INVALID_VERSION {
-                        Ok(true)
-                    } else {
-                        Ok(false)
-                    }
-                }
-                _ => Ok(false),
-            }
-        }
-        Err(e) => {
-            error!("failed to send message due to: {:?}", e);
-            Ok(false)
-        }
-    }
-}
-
 /// stage message for async retry
 async fn send_to_retry(mid: String) -> Result<(), NevekoError> {
     info!("sending {} to fts", &mid);
@@ -515,23 +495,27 @@ pub async fn retry_fts() -> Result<(), NevekoError> {
         }
         for m in v {
             let message: Message = find(&m)?;
+            if !message.mid.is_empty() && !presence::is_retry_due(&message.to) {
+                debug!("contact {} unreachable, backing off", &message.to);
+                continue;
+            }
             if !message.mid.is_empty() {
                 // get jwp from db
                 let k = format!("{}-{}", crate::FTS_JWP_DB_KEY, &message.to);
                 let jwp =
                     db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
                         .map_err(|_| NevekoError::Database(MdbError::Panic))?;
-                if !jwp.is_empty() {
-                    let m_type = if message.mid.contains("msig") {
-                        MessageType::Multisig
-                    } else {
-                        MessageType::Normal
-                    };
-                    let str_jwp: String = bincode::deserialize(&jwp[..]).unwrap_or_default();
-                    send_message(&message, &str_jwp, m_type).await.unwrap();
-                } else {
-                    error!("not jwp found for fts id: {}", &message.mid);
+                if jwp.is_empty() {
+                    // credit or proof-of-work may still get the message through
+                    debug!("no jwp found for fts id: {}", &message.mid);
                 }
+                let m_type = if message.mid.contains("msig") {
+                    MessageType::Multisig
+                } else {
+                    MessageType::Normal
+                };
+                let str_jwp: String = bincode::deserialize(&jwp[..]).unwrap_or_default();
+                send_message(&message, &str_jwp, m_type).await.unwrap();
             }
         }
     }
@@ -562,11 +546,18 @@ fn is_fts_clear(r: String) -> bool {
 ///
 /// `prepare_multisig_info` method.
 pub async fn send_prepare_info(orid: &String, contact: &String) -> Result<(), NevekoError> {
+    utils::check_spend_keys()?;
     let db = &DATABASE_LOCK;
     let wallet_name = String::from(orid);
     let wallet_password = String::new();
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let prepare_info = monero::prepare_wallet().await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let prepare_info = match monero::prepare_wallet(&wallet).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to prepare multisig for order {}: {}", orid, e);
+            return Err(NevekoError::MoneroRpc);
+        }
+    };
     let k = format!("{}-{}", crate::FTS_JWP_DB_KEY, contact);
     let jwp = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
         .map_err(|_| NevekoError::Database(MdbError::Panic))?;
@@ -581,7 +572,7 @@ pub async fn send_prepare_info(orid: &String, contact: &String) -> Result<(), Ne
         ..Default::default()
     };
     let j_message: Json<Message> = utils::message_to_json(&message);
-    monero::close_wallet(orid, &wallet_password).await;
+    drop(wallet);
     let str_jwp: String = bincode::deserialize(&jwp[..]).unwrap_or_default();
     create(j_message, str_jwp, MessageType::Multisig).await?;
     Ok(())
@@ -595,11 +586,18 @@ pub async fn send_make_info(
     contact: &String,
     info: Vec<String>,
 ) -> Result<(), NevekoError> {
+    utils::check_spend_keys()?;
     let db = &DATABASE_LOCK;
     let wallet_name = String::from(orid);
     let wallet_password = String::new();
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let make_info = monero::make_wallet(info).await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let make_info = match monero::make_wallet(&wallet, info).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to make multisig for order {}: {}", orid, e);
+            return Err(NevekoError::MoneroRpc);
+        }
+    };
     let k = format!("{}-{}", crate::FTS_JWP_DB_KEY, contact);
     let jwp = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
         .map_err(|_| NevekoError::Database(MdbError::Panic))?;
@@ -611,7 +609,7 @@ pub async fn send_make_info(
         ..Default::default()
     };
     let j_message: Json<Message> = utils::message_to_json(&message);
-    monero::close_wallet(orid, &wallet_password).await;
+    drop(wallet);
     let str_jwp: String = bincode::deserialize(&jwp[..]).unwrap_or_default();
     create(j_message, str_jwp, MessageType::Multisig).await?;
     Ok(())
@@ -626,11 +624,19 @@ pub async fn send_exchange_info(
     info: Vec<String>,
     kex_init: bool,
 ) -> Result<(), NevekoError> {
+    utils::check_spend_keys()?;
     let db = &DATABASE_LOCK;
     let wallet_name = String::from(orid);
     let wallet_password = String::new();
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let exchange_info = monero::exchange_multisig_keys(false, info, &wallet_password).await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let r_exchange = monero::exchange_multisig_keys(&wallet, false, info, &wallet_password).await;
+    let exchange_info = match r_exchange {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to exchange multisig keys for order {}: {}", orid, e);
+            return Err(NevekoError::MoneroRpc);
+        }
+    };
     let k = format!("{}-{}", crate::FTS_JWP_DB_KEY, contact);
     let jwp = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
         .map_err(|_| NevekoError::Database(MdbError::Panic))?;
@@ -651,7 +657,7 @@ pub async fn send_exchange_info(
         ..Default::default()
     };
     let j_message: Json<Message> = utils::message_to_json(&message);
-    monero::close_wallet(orid, &wallet_password).await;
+    drop(wallet);
     let str_jwp: String = bincode::deserialize(&jwp[..]).unwrap_or_default();
     create(j_message, str_jwp, MessageType::Multisig).await?;
     Ok(())
@@ -661,11 +667,18 @@ pub async fn send_exchange_info(
 ///
 /// `export_multisig_info` method.
 pub async fn send_export_info(orid: &String, contact: &String) -> Result<(), NevekoError> {
+    utils::check_spend_keys()?;
     let db = &DATABASE_LOCK;
     let wallet_name = String::from(orid);
     let wallet_password = String::new();
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let exchange_info = monero::export_multisig_info().await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let exchange_info = match monero::export_multisig_info(&wallet).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to export multisig info for order {}: {}", orid, e);
+            return Err(NevekoError::MoneroRpc);
+        }
+    };
     let k = format!("{}-{}", crate::FTS_JWP_DB_KEY, contact);
     let jwp = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
         .map_err(|_| NevekoError::Database(MdbError::Panic))?;
@@ -677,7 +690,7 @@ pub async fn send_export_info(orid: &String, contact: &String) -> Result<(), Nev
         ..Default::default()
     };
     let j_message: Json<Message> = utils::message_to_json(&message);
-    monero::close_wallet(orid, &wallet_password).await;
+    drop(wallet);
     let str_jwp: String = bincode::deserialize(&jwp[..]).unwrap_or_default();
     create(j_message, str_jwp, MessageType::Multisig).await?;
     Ok(())
@@ -689,11 +702,18 @@ pub async fn send_export_info(orid: &String, contact: &String) -> Result<(), Nev
 ///
 /// successfully the order needs to be updated to `MultisigComplete`.
 pub async fn send_import_info(orid: &String, info: &[String]) -> Result<(), NevekoError> {
+    utils::check_spend_keys()?;
     let wallet_name = String::from(orid);
     let wallet_password = String::new();
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let pre_import = monero::import_multisig_info(info.to_vec()).await;
-    monero::close_wallet(orid, &wallet_password).await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let pre_import = match monero::import_multisig_info(&wallet, info.to_vec()).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to import multisig info for order {}: {}", orid, e);
+            return Err(NevekoError::MoneroRpc);
+        }
+    };
+    drop(wallet);
     if pre_import.result.n_outputs == 0 {
         error!("unable to import multisig info for order: {}", orid);
         return Err(NevekoError::Database(MdbError::Panic))?;
@@ -725,6 +745,8 @@ async fn trigger_msig_info_request(
     match client?
         .post(format!("http://{}/multisig/info", contact))
         .header("proof", jwp)
+        .header("credit", credit::find_token(&contact))
+        .headers(peer::get_auth_headers(&contact).await)
         .json(&request)
         .send()
         .await
@@ -15,6 +15,21 @@ pub struct Authorization {
     pub token: String,
     pub uid: String,
     pub xmr_address: String,
+    /// Comma separated token scopes
+    pub scopes: String,
+    pub read_only: bool,
+}
+
+/// `Authorization` as persisted before token scopes, fields must not change
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LegacyAuthorization {
+    pub aid: String,
+    pub created: i64,
+    pub rnd: String,
+    pub token: String,
+    pub uid: String,
+    pub xmr_address: String,
 }
 
 impl Authorization {
@@ -24,6 +39,8 @@ impl Authorization {
             created: a.created,
             uid,
             rnd: a.rnd,
+            scopes: a.scopes,
+            read_only: a.read_only,
             token: a.token,
             xmr_address: a.xmr_address,
         }
@@ -39,12 +56,26 @@ impl Authorization {
             created,
             uid: String::from(&a.uid),
             rnd,
+            scopes: String::from(&a.scopes),
+            read_only: a.read_only,
             token,
             xmr_address: String::from(&a.xmr_address),
         }
     }
 }
 
+/// Bearer token session as listed by neveko-auth
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Session {
+    pub sid: String,
+    pub created: i64,
+    pub expire: i64,
+    pub scopes: String,
+    pub read_only: bool,
+    pub xmr_address: String,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct Contact {
@@ -110,6 +141,16 @@ impl Product {
     }
 }
 
+/// Single use nonce a customer signs to retrieve or cancel an order
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct OrderChallenge {
+    pub orid: String,
+    pub action: String,
+    pub nonce: String,
+    pub created: i64,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct Order {
@@ -192,3 +233,151 @@ pub struct Dispute {
     pub orid: String,
     pub tx_set: String,
 }
+
+/// Single reachability probe of a contact over i2p
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Probe {
+    pub created: i64,
+    pub latency: u64,
+    pub online: bool,
+}
+
+/// Fee in piconeros of a typical transfer for each priority tier, cached by height
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct FeeEstimate {
+    pub created: i64,
+    pub height: u64,
+    pub fees: Vec<u128>,
+}
+
+/// Wallet transfer cached from `get_transfers` along with what it was for
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WalletTransfer {
+    pub tid: String,
+    pub txid: String,
+    /// in, out, pending or pool
+    pub direction: String,
+    pub address: String,
+    pub amount: u128,
+    pub fee: u128,
+    pub confirmations: u64,
+    pub height: u64,
+    pub timestamp: u64,
+    pub account_index: u64,
+    pub subaddr_index: u64,
+    pub purpose: String,
+    /// Contact, order id or jwp destination the purpose refers to
+    pub reference: String,
+}
+
+/// Signed but unrelayed transfer waiting on confirmation
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SendPreview {
+    pub address: String,
+    pub amount: u128,
+    pub fee: u128,
+    /// `FeePriority` value the transfer was built with
+    pub priority: u8,
+    pub account_index: u32,
+    pub subaddr_indices: Vec<u32>,
+    pub tx_hash: String,
+    pub tx_metadata: String,
+}
+
+/// Health of a remote monero daemon from its last check
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RemoteNode {
+    pub address: String,
+    pub checked: i64,
+    pub height: u64,
+    pub latency: u64,
+    pub online: bool,
+    pub synchronized: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Presence {
+    pub i2p_address: String,
+    pub failures: u32,
+    pub history: Vec<Probe>,
+    pub last_probe: i64,
+    pub last_seen: i64,
+}
+
+/// Ledger entry for a tx proof that was redeemed for a JWP
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Redemption {
+    pub subaddress: String,
+    pub created: i64,
+    pub destination: String,
+    pub expire: u64,
+    pub hash: String,
+    pub jid: String,
+    pub jwp: String,
+    pub revoked: bool,
+    pub scope: String,
+}
+
+/// Subaddress handed out to a destination requesting an invoice
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct IssuedInvoice {
+    pub destination: String,
+    pub address: String,
+    pub created: i64,
+    pub expire: i64,
+    /// timestamps of recent invoice requests, used for rate limiting
+    pub requests: Vec<i64>,
+}
+
+/// Proof-of-work challenge issued to a peer
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PowChallenge {
+    pub challenge: String,
+    pub created: i64,
+    pub difficulty: u32,
+}
+
+/// Top up or debit of a credit balance
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreditEntry {
+    pub amount: u128,
+    pub created: i64,
+    pub debit: bool,
+    /// tx hash for top ups, scope for debits
+    pub reference: String,
+}
+
+/// Prepaid balance a contact holds with us
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreditAccount {
+    pub destination: String,
+    pub balance: u128,
+    pub history: Vec<CreditEntry>,
+    /// id of the current credit token
+    pub tid: String,
+}
+
+/// Outgoing invoice payment made to obtain a JWP
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Payment {
+    pub pid: String,
+    pub amount: u128,
+    pub contact: String,
+    pub created: i64,
+    pub expire: u64,
+    pub fee: u128,
+    pub hash: String,
+    pub jwp: String,
+}
@@ -2,10 +2,15 @@
 
 use crate::{
     args,
-    error::NevekoError,
+    error::{
+        NevekoError,
+        WalletRpcError,
+    },
     i2p,
+    node,
     proof,
     reqres,
+    supervisor,
     utils,
 };
 use clap::Parser;
@@ -14,22 +19,47 @@ use log::{
     debug,
     error,
     info,
-    warn,
 };
 use std::{
+    collections::hash_map::DefaultHasher,
     error::Error,
-    process::Command,
+    hash::{
+        Hash,
+        Hasher,
+    },
+    sync::Arc,
+    time::Duration,
 };
 
 use lazy_static::lazy_static;
-use std::sync::Mutex;
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+use tokio::sync::{
+    Mutex,
+    OwnedMutexGuard,
+};
 
 pub const INVALID_VERSION: u32 = 0;
 
 // global variable
 lazy_static! {
-    /// used to avoid multisig wallet collision
-    static ref IS_WALLET_BUSY: Mutex<bool> = Mutex::new(false);
+    /// Each monero-wallet-rpc worker holds a single open wallet. Waiters on a
+    ///
+    /// worker's lock are served in the order they arrived so sessions are
+    ///
+    /// granted fairly. The locks only order sessions within this process,
+    ///
+    /// another process sharing the workers (e.g. the GUI next to core) is not
+    ///
+    /// queued with them and must not open wallets pinned to the same worker.
+    ///
+    /// Sized for the most workers, `get_rpc_workers` tells how many are used.
+    static ref WALLET_WORKERS: Vec<Arc<Mutex<OpenWallet>>> =
+        (0..usize::from(crate::MONERO_RPC_WORKERS_MAX))
+            .map(|_| Arc::new(Mutex::new(Default::default())))
+            .collect();
 }
 
 /// Current xmr ring size updated here.
@@ -38,6 +68,15 @@ const RING_SIZE: u32 = 0x10;
 /// Query view key type
 const QUERY_TYPE_VIEW_KEY: &str = "view_key";
 
+/// Query spend key type
+const QUERY_TYPE_SPEND_KEY: &str = "spend_key";
+
+/// Params for wallet rpc methods that take none
+const NO_PARAMS: Option<()> = None;
+
+/// Supervised monero-wallet-rpc workers are named this plus their index
+const RPC_WORKER_NAME_PREFIX: &str = "monero-wallet-rpc-";
+
 struct RpcLogin {
     username: String,
     credential: String,
@@ -72,24 +111,33 @@ enum RpcFields {
     CheckTxProof,
     ChangeWalletPassword,
     Close,
+    CreateAccount,
     CreateAddress,
     CreateWallet,
+    GenerateFromKeys,
     DescribeTransfer,
     ExchangeMultisigKeys,
     Export,
+    GetAccounts,
+    GetAddressIndex,
     GetHeight,
     GetTxProof,
     GetTxById,
+    GetTransfers,
     GetVersion,
     Id,
     Import,
     IsMultisig,
     JsonRpcVersion,
+    LabelAddress,
     Make,
     Open,
     Prepare,
     QueryKey,
     Refresh,
+    RelayTx,
+    RestoreDeterministicWallet,
+    SetDaemon,
     Sign,
     SignMultisig,
     SubmitMultisig,
@@ -107,23 +155,32 @@ impl RpcFields {
             RpcFields::ChangeWalletPassword => String::from("change_wallet_password"),
             RpcFields::CheckTxProof => String::from("check_tx_proof"),
             RpcFields::Close => String::from("close_wallet"),
+            RpcFields::CreateAccount => String::from("create_account"),
             RpcFields::CreateAddress => String::from("create_address"),
             RpcFields::CreateWallet => String::from("create_wallet"),
+            RpcFields::GenerateFromKeys => String::from("generate_from_keys"),
             RpcFields::DescribeTransfer => String::from("describe_transfer"),
             RpcFields::ExchangeMultisigKeys => String::from("exchange_multisig_keys"),
             RpcFields::Export => String::from("export_multisig_info"),
+            RpcFields::GetAccounts => String::from("get_accounts"),
+            RpcFields::GetAddressIndex => String::from("get_address_index"),
             RpcFields::GetHeight => String::from("get_height"),
             RpcFields::GetTxProof => String::from("get_tx_proof"),
             RpcFields::GetTxById => String::from("get_transfer_by_txid"),
+            RpcFields::GetTransfers => String::from("get_transfers"),
             RpcFields::GetVersion => String::from("get_version"),
             RpcFields::Id => String::from("0"),
             RpcFields::Import => String::from("import_multisig_info"),
             RpcFields::IsMultisig => String::from("is_multisig"),
             RpcFields::JsonRpcVersion => String::from("2.0"),
+            RpcFields::LabelAddress => String::from("label_address"),
             RpcFields::Make => String::from("make_multisig"),
             RpcFields::Open => String::from("open_wallet"),
             RpcFields::Prepare => String::from("prepare_multisig"),
             RpcFields::Refresh => String::from("refresh"),
+            RpcFields::RelayTx => String::from("relay_tx"),
+            RpcFields::RestoreDeterministicWallet => String::from("restore_deterministic_wallet"),
+            RpcFields::SetDaemon => String::from("set_daemon"),
             RpcFields::QueryKey => String::from("query_key"),
             RpcFields::Sign => String::from("sign"),
             RpcFields::SignMultisig => String::from("sign_multisig"),
@@ -138,6 +195,7 @@ impl RpcFields {
 
 enum DaemonFields {
     GetBlock,
+    GetFeeEstimate,
     GetHeight,
     GetInfo,
     GetTransactions,
@@ -149,6 +207,7 @@ impl DaemonFields {
     pub fn value(&self) -> String {
         match *self {
             DaemonFields::GetBlock => String::from("get_block"),
+            DaemonFields::GetFeeEstimate => String::from("get_fee_estimate"),
             DaemonFields::GetHeight => String::from("get_height"),
             DaemonFields::GetInfo => String::from("get_info"),
             DaemonFields::GetTransactions => String::from("get_transactions"),
@@ -158,6 +217,67 @@ impl DaemonFields {
     }
 }
 
+/// Transfer priority tiers, `Normal` is what monero-wallet-rpc picks by default
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeePriority {
+    Slow,
+    Normal,
+    Fast,
+    Fastest,
+}
+
+impl FeePriority {
+    pub const ALL: [FeePriority; 4] = [
+        FeePriority::Slow,
+        FeePriority::Normal,
+        FeePriority::Fast,
+        FeePriority::Fastest,
+    ];
+
+    /// The `priority` transfer param
+    pub fn value(&self) -> u8 {
+        match *self {
+            FeePriority::Slow => 1,
+            FeePriority::Normal => 2,
+            FeePriority::Fast => 3,
+            FeePriority::Fastest => 4,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match *self {
+            FeePriority::Slow => String::from("slow"),
+            FeePriority::Normal => String::from("normal"),
+            FeePriority::Fast => String::from("fast"),
+            FeePriority::Fastest => String::from("fastest"),
+        }
+    }
+}
+
+/// Which transfers `get_transfers` returns. No subaddress indices means
+///
+/// all subaddresses of the primary account.
+#[derive(Clone, Debug, Default)]
+pub struct TransferFilter {
+    pub is_in: bool,
+    pub is_out: bool,
+    pub is_pending: bool,
+    pub is_pool: bool,
+    pub subaddr_indices: Vec<u32>,
+}
+
+impl TransferFilter {
+    pub fn all() -> Self {
+        TransferFilter {
+            is_in: true,
+            is_out: true,
+            is_pending: true,
+            is_pool: true,
+            subaddr_indices: Vec::new(),
+        }
+    }
+}
+
 pub enum LockTimeLimit {
     Blocks,
 }
@@ -177,7 +297,7 @@ pub fn start_daemon() -> Result<(), NevekoError> {
     info!("starting monerod");
     let blockchain_dir = get_blockchain_dir();
     let bin_dir = get_monero_location();
-    let release_env = utils::get_release_env();
+    let network = utils::get_network();
     let mut socks_proxy_host = utils::get_i2p_wallet_proxy_host();
     if socks_proxy_host.contains("http://") {
         let values = socks_proxy_host.split("http://");
@@ -189,53 +309,49 @@ pub fn start_daemon() -> Result<(), NevekoError> {
     let anon_in_port = get_anon_inbound_port();
     let destination = i2p::get_destination(i2p::ServerTunnelType::App)?;
     let anon_inbound = format!("{},127.0.0.1:{}", destination, anon_in_port);
-    let mut args = vec!["--data-dir", &blockchain_dir, "--detach"];
-    if release_env == utils::ReleaseEnvironment::Development {
-        args.push("--stagenet");
-        let output = Command::new(format!("{}/monerod", bin_dir))
-            .args(args)
-            .spawn()
-            .expect("monerod failed to start");
-        debug!("{:?}", output.stdout);
-        Ok(())
-    } else {
-        args.push("--tx-proxy");
-        args.push(&tx_proxy);
-        args.push("--anonymous-inbound");
-        args.push(&anon_inbound);
-        let output = Command::new(format!("{}/monerod", bin_dir))
-            .args(args)
-            .spawn()
-            .expect("monerod failed to start");
-        debug!("{:?}", output.stdout);
-        Ok(())
+    // stay attached so the supervisor owns the process
+    let mut args = vec!["--data-dir", &blockchain_dir, "--non-interactive"];
+    match network.flag() {
+        Some(flag) => args.push(flag),
+        None => {
+            args.push("--tx-proxy");
+            args.push(&tx_proxy);
+            args.push("--anonymous-inbound");
+            args.push(&anon_inbound);
+        }
     }
+    supervisor::spawn(supervisor::ChildSpec {
+        name: String::from("monerod"),
+        program: format!("{}/monerod", bin_dir),
+        args: args.into_iter().map(String::from).collect(),
+        ..Default::default()
+    })
 }
 
-/// Start monero-wallet-rpc
+/// Start the pool of monero-wallet-rpc workers from `--monero-rpc-workers`
 pub fn start_rpc() {
+    for worker in 0..get_rpc_workers() {
+        if start_rpc_worker(worker).is_err() {
+            error!("monero-wallet-rpc worker {} failed to start", worker);
+        }
+    }
+}
+
+/// Start a monero-wallet-rpc worker on the base rpc port plus its index
+fn start_rpc_worker(worker: usize) -> Result<(), NevekoError> {
     let cli_args = args::Args::parse();
     let bin_dir = get_monero_location();
-    let port = get_rpc_port();
+    let port = get_rpc_port(worker);
     let login = get_rpc_creds();
-    info!("starting monero-wallet-rpc from {}", &bin_dir);
+    info!("starting monero-wallet-rpc worker {} from {}", worker, &bin_dir);
     let daemon_address = cli_args.monero_rpc_daemon;
     let rpc_login = format!("{}:{}", &login.username, &login.credential);
-    let release_env = utils::get_release_env();
-    let is_dev = release_env == utils::ReleaseEnvironment::Development;
-    let wallet_path = if is_dev {
-        ".neveko/stagenet/wallet/"
-    } else {
-        ".neveko/wallet/"
-    };
+    let network = utils::get_network();
     let wallet_dir = format!(
         "/home/{}/{}",
         std::env::var("USER").unwrap_or(String::from("user")),
-        wallet_path
+        network.wallet_dir()
     );
-    if cli_args.remote_node && !&daemon_address.contains(".i2p") {
-        warn!("invalid i2p monero remote node detected");
-    }
     // proxy host can't have protocol
     let mut proxy_host = utils::get_i2p_wallet_proxy_host();
     if proxy_host.contains("http://") {
@@ -262,32 +378,79 @@ pub fn start_rpc() {
         args.push("--daemon-address");
         args.push(&daemon_address);
     }
-    if is_dev {
-        args.push("--stagenet");
-        let output = Command::new(format!("{}/monero-wallet-rpc", bin_dir))
-            .args(args)
-            .spawn()
-            .expect("monero-wallet-rpc failed to start");
-        debug!("{:?}", output.stdout);
-    } else {
-        let output = Command::new(format!("{}/monero-wallet-rpc", bin_dir))
-            .args(args)
-            .spawn()
-            .expect("monero-wallet-rpc failed to start");
-        debug!("{:?}", output.stdout);
+    if let Some(flag) = network.flag() {
+        args.push(flag);
     }
+    supervisor::spawn(supervisor::ChildSpec {
+        name: format!("{}{}", RPC_WORKER_NAME_PREFIX, worker),
+        program: format!("{}/monero-wallet-rpc", bin_dir),
+        args: args.into_iter().map(String::from).collect(),
+        on_restart: Some(on_rpc_worker_restart),
+    })
 }
 
-fn get_rpc_port() -> String {
+/// A restarted monero-wallet-rpc worker has no wallet open, forget the one
+///
+/// the old process held so the next session opens it again. The worker is
+///
+/// also pointed back at the remote node the others were failed over to.
+fn on_rpc_worker_restart(name: &str) {
+    let worker = match name
+        .strip_prefix(RPC_WORKER_NAME_PREFIX)
+        .and_then(|w| w.parse::<usize>().ok())
+    {
+        Some(w) if w < WALLET_WORKERS.len() => w,
+        _ => return,
+    };
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(async move {
+            let mut open = WALLET_WORKERS[worker].clone().lock_owned().await;
+            info!("monero-wallet-rpc worker {} restarted, no wallet is open", worker);
+            open.filename = String::new();
+            open.password = String::new();
+            drop(open);
+            if let Some(address) = node::get_current() {
+                if let Err(e) = set_worker_daemon(worker, &address).await {
+                    error!("failed to set worker {} daemon to {}: {}", worker, address, e);
+                }
+            }
+        });
+    }
+}
+
+fn get_rpc_port(worker: usize) -> String {
     let args = args::Args::parse();
     let rpc = args.monero_rpc_host;
     let values = rpc.split(":");
     let mut v: Vec<String> = values.map(String::from).collect();
-    let port = v.remove(2);
-    debug!("monero-wallet-rpc port: {}", port);
+    let base = v.remove(2);
+    let port = format!("{}", base.parse::<usize>().unwrap_or(0) + worker);
+    debug!("monero-wallet-rpc worker {} port: {}", worker, port);
     port
 }
 
+/// Number of monero-wallet-rpc workers, at least one. The GUI reads the
+///
+/// count it started core with so both pin wallets to the same workers.
+fn get_rpc_workers() -> usize {
+    let args = args::Args::parse();
+    let gui_workers = std::env::var(crate::MONERO_WALLET_RPC_WORKERS).unwrap_or_default();
+    let workers = gui_workers.parse::<u16>().unwrap_or(args.monero_rpc_workers);
+    usize::from(workers.clamp(1, crate::MONERO_RPC_WORKERS_MAX))
+}
+
+/// Worker a wallet is pinned to. The app wallet keeps the first worker to
+///
+/// itself when there are others, order wallets are spread over the rest.
+fn get_wallet_worker(filename: &String, workers: usize) -> usize {
+    if workers < 2 || filename == crate::APP_NAME {
+        return 0;
+    }
+    let mut hasher = DefaultHasher::new();
+    filename.hash(&mut hasher);
+    1 + (hasher.finish() % (workers as u64 - 1)) as usize
+}
+
 pub fn get_daemon_port() -> u16 {
     let args = args::Args::parse();
     let rpc = args.monero_rpc_daemon;
@@ -325,8 +488,10 @@ fn get_monero_location() -> String {
     args.monero_location.to_string()
 }
 
-/// Get monero rpc host from the `--monero-rpc-host` cli arg
-fn get_rpc_host() -> String {
+/// Get monero rpc host from the `--monero-rpc-host` cli arg, offset by the
+///
+/// worker index for the rest of the pool.
+fn get_rpc_host(worker: usize) -> String {
     let args = args::Args::parse();
     let gui_host = std::env::var(crate::MONERO_WALLET_RPC_HOST).unwrap_or_default();
     let rpc = if gui_host.is_empty() {
@@ -334,7 +499,16 @@ fn get_rpc_host() -> String {
     } else {
         gui_host
     };
-    format!("{}/json_rpc", rpc)
+    if worker == 0 {
+        return format!("{}/json_rpc", rpc);
+    }
+    match rpc.rsplit_once(":") {
+        Some((host, port)) => {
+            let port = port.parse::<usize>().unwrap_or(0) + worker;
+            format!("{}:{}/json_rpc", host, port)
+        }
+        None => format!("{}/json_rpc", rpc),
+    }
 }
 
 /// Get creds from the `--monero-rpc-daemon` cli arg
@@ -349,6 +523,10 @@ fn get_rpc_creds() -> RpcLogin {
 }
 
 fn get_rpc_daemon() -> String {
+    // remote nodes fail over, prefer the one wallet rpc was last switched to
+    if let Some(current) = node::get_current() {
+        return current;
+    }
     let args = args::Args::parse();
     let gui_host = std::env::var(crate::MONERO_DAEMON_HOST).unwrap_or_default();
     if gui_host.is_empty() {
@@ -358,951 +536,921 @@ fn get_rpc_daemon() -> String {
     }
 }
 
+/// JSON-RPC client for monero-wallet-rpc, only reachable through a
+///
+/// `WalletSession` or the module's wallet-less methods.
+struct WalletRpc {
+    client: reqwest::Client,
+    host: String,
+    login: RpcLogin,
+}
+
+impl Default for WalletRpc {
+    fn default() -> Self {
+        WalletRpc::new()
+    }
+}
+
+impl WalletRpc {
+    /// Client for the host and credentials from the command line args
+    fn new() -> WalletRpc {
+        WalletRpc::for_worker(0)
+    }
+
+    /// Client for a monero-wallet-rpc worker in the pool
+    fn for_worker(worker: usize) -> WalletRpc {
+        WalletRpc {
+            client: reqwest::Client::new(),
+            host: get_rpc_host(worker),
+            login: get_rpc_creds(),
+        }
+    }
+
+    /// Perform `method` and parse the result, or the JSON-RPC error object
+    ///
+    /// into a `WalletRpcError`.
+    async fn call<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: RpcFields,
+        params: Option<P>,
+    ) -> Result<R, WalletRpcError> {
+        let req = reqres::XmrRpcJsonRequest {
+            jsonrpc: RpcFields::JsonRpcVersion.value(),
+            id: RpcFields::Id.value(),
+            method: method.value(),
+            params,
+        };
+        let response = self
+            .client
+            .post(&self.host)
+            .json(&req)
+            .send_with_digest_auth(&self.login.username, &self.login.credential)
+            .await
+            .map_err(|e| WalletRpcError::Connection(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(WalletRpcError::Http(response.status().as_u16()));
+        }
+        let res = response
+            .json::<reqres::XmrRpcJsonResponse<R>>()
+            .await
+            .map_err(|e| WalletRpcError::Parse(e.to_string()))?;
+        if let Some(e) = res.error {
+            error!("{} failed with {}: {}", method.value(), e.code, e.message);
+            return Err(WalletRpcError::from_rpc(e.code, e.message));
+        }
+        res.result
+            .ok_or_else(|| WalletRpcError::Parse(format!("{} returned no result", method.value())))
+    }
+}
+
 /// Performs rpc 'get_version' method
-pub async fn get_version() -> reqres::XmrRpcVersionResponse {
+pub async fn get_version() -> Result<reqres::XmrRpcVersionResponse, WalletRpcError> {
     info!("executing {}", RpcFields::GetVersion.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let req = reqres::XmrRpcRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::GetVersion.value(),
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcVersionResponse>().await;
-            debug!("{} response: {:?}", RpcFields::GetVersion.value(), res);
-            res.unwrap_or_default()
+    let result = WalletRpc::new().call(RpcFields::GetVersion, NO_PARAMS).await?;
+    debug!("{} response: {:?}", RpcFields::GetVersion.value(), result);
+    Ok(reqres::XmrRpcVersionResponse { result })
+}
+
+/// Helper function for checking xmr rpc workers online during app startup
+pub async fn check_rpc_connection() {
+    for worker in 0..get_rpc_workers() {
+        if let Err(e) = is_rpc_ready(worker).await {
+            error!("failed to connect to monero-wallet-rpc worker {}: {}", worker, e);
         }
-        Err(_e) => Default::default(),
     }
 }
 
-/// Helper function for checking xmr rpc online during app startup
-pub async fn check_rpc_connection() {
-    let res: reqres::XmrRpcVersionResponse = get_version().await;
-    if res.result.version == INVALID_VERSION {
-        error!("failed to connect to monero-wallet-rpc");
+async fn is_rpc_ready(worker: usize) -> Result<(), WalletRpcError> {
+    let rpc = WalletRpc::for_worker(worker);
+    let version: reqres::XmrRpcVersionResult = rpc.call(RpcFields::GetVersion, NO_PARAMS).await?;
+    if version.version == INVALID_VERSION {
+        return Err(WalletRpcError::Parse(String::from("invalid version")));
+    }
+    Ok(())
+}
+
+/// Poll 'get_version' until every rpc worker answers or the readiness
+///
+/// timeout passes. Replaces waiting a fixed time after `start_rpc`.
+pub async fn wait_for_rpc() -> bool {
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(crate::SUPERVISOR_READY_TIMEOUT_SECS);
+    for worker in 0..get_rpc_workers() {
+        while let Err(e) = is_rpc_ready(worker).await {
+            if tokio::time::Instant::now() >= deadline {
+                error!("monero-wallet-rpc worker {} is not ready: {}", worker, e);
+                return false;
+            }
+            debug!("waiting on monero-wallet-rpc worker {}", worker);
+            tokio::time::sleep(Duration::from_millis(crate::SUPERVISOR_POLL_MILLIS)).await;
+        }
     }
+    info!("monero-wallet-rpc is ready");
+    true
+}
+
+/// Poll the daemon 'get_info' method until it answers or the readiness
+///
+/// timeout passes.
+pub async fn wait_for_daemon() -> bool {
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(crate::SUPERVISOR_READY_TIMEOUT_SECS);
+    while get_info().await.result.status != "OK" {
+        if tokio::time::Instant::now() >= deadline {
+            error!("monerod is not ready");
+            return false;
+        }
+        debug!("waiting on monerod");
+        tokio::time::sleep(Duration::from_millis(crate::SUPERVISOR_POLL_MILLIS)).await;
+    }
+    info!("monerod is ready");
+    true
 }
 
 /// Performs the xmr rpc 'sign' method
-pub async fn sign(data: String) -> reqres::XmrRpcSignResponse {
+pub async fn sign(
+    wallet: &WalletSession,
+    data: String,
+) -> Result<reqres::XmrRpcSignResponse, WalletRpcError> {
     info!("executing {} on: {}", RpcFields::Sign.value(), &data);
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcSignParams { data };
-    let req = reqres::XmrRpcSignRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Sign.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcSignResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Sign.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_e) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Sign, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::Sign.value(), result);
+    Ok(reqres::XmrRpcSignResponse { result })
 }
 
 /// Performs the xmr rpc 'verify' method
-pub async fn verify(address: String, data: String, signature: String) -> bool {
+pub async fn verify(
+    wallet: &WalletSession,
+    address: String,
+    data: String,
+    signature: String,
+) -> Result<bool, WalletRpcError> {
     info!(
         "executing {} for sig: {} on {}",
         RpcFields::Verify.value(),
         &signature,
         &data
     );
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcVerifyParams {
         address,
         data,
         signature,
     };
-    let req = reqres::XmrRpcVerifyRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Verify.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcVerifyResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Verify.value(), res);
-            match res {
-                Ok(res) => res.result.good,
-                _ => false,
-            }
+    let result: reqres::XmrRpcVerifyResult =
+        wallet.rpc.call(RpcFields::Verify, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::Verify.value(), result);
+    Ok(result.good)
+}
+
+/// The wallet currently open in a monero-wallet-rpc worker
+#[derive(Default)]
+struct OpenWallet {
+    filename: String,
+    password: String,
+    /// Bumped for every session so a lingering close can tell it is stale
+    generation: u64,
+}
+
+/// Exclusive access to an open wallet. Wallet rpc methods take a session so
+///
+/// they never run against whichever wallet happens to be open. Dropping the
+///
+/// session closes the wallet after `WALLET_SESSION_LINGER_MILLIS` unless the
+///
+/// next session asks for the same one. Sessions are not reentrant, release
+///
+/// one before opening another. Requests are routed to the worker the
+///
+/// wallet is pinned to.
+pub struct WalletSession {
+    open: OwnedMutexGuard<OpenWallet>,
+    rpc: WalletRpc,
+    worker: usize,
+}
+
+impl WalletSession {
+    /// Name of the wallet held by this session
+    pub fn filename(&self) -> &String {
+        &self.open.filename
+    }
+
+    /// Index of the monero-wallet-rpc worker serving this session
+    pub fn worker(&self) -> usize {
+        self.worker
+    }
+}
+
+impl Drop for WalletSession {
+    fn drop(&mut self) {
+        let generation = self.open.generation;
+        let worker = self.worker;
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(linger_close(worker, generation));
         }
-        Err(_e) => false,
     }
 }
 
-/// Performs the xmr rpc 'create_wallet' method
-pub async fn create_wallet(filename: &String, password: &String) -> bool {
+/// Close a released wallet if no session has claimed it since
+async fn linger_close(worker: usize, generation: u64) {
+    tokio::time::sleep(Duration::from_millis(crate::WALLET_SESSION_LINGER_MILLIS)).await;
+    let mut open = WALLET_WORKERS[worker].clone().lock_owned().await;
+    if open.generation == generation {
+        close_open_wallet(&mut open, &WalletRpc::for_worker(worker)).await;
+    }
+}
+
+/// Performs the xmr rpc 'close_wallet' method on the open wallet, if any
+async fn close_open_wallet(open: &mut OpenWallet, rpc: &WalletRpc) {
+    if open.filename.is_empty() {
+        return;
+    }
+    info!("executing {}", RpcFields::Close.value());
+    let params = reqres::XmrRpcOpenWalletParams {
+        filename: String::from(&open.filename),
+        password: String::from(&open.password),
+    };
+    // The result from wallet operation is empty
+    let closed: Result<reqres::XmrRpcEmptyResult, WalletRpcError> =
+        rpc.call(RpcFields::Close, Some(params)).await;
+    if let Err(e) = closed {
+        error!("failed to close wallet {}: {}", &open.filename, e);
+    }
+    open.filename = String::new();
+    open.password = String::new();
+}
+
+/// Wait in line for the lock of the worker the wallet is pinned to and
+///
+/// close its open wallet unless it is the one requested.
+async fn acquire(filename: &String, password: &String) -> (WalletSession, bool) {
+    let worker = get_wallet_worker(filename, get_rpc_workers());
+    let rpc = WalletRpc::for_worker(worker);
+    let mut open = WALLET_WORKERS[worker].clone().lock_owned().await;
+    open.generation += 1;
+    let is_open = open.filename == *filename && open.password == *password;
+    if !is_open {
+        close_open_wallet(&mut open, &rpc).await;
+    }
+    (WalletSession { open, rpc, worker }, is_open)
+}
+
+/// Performs the xmr rpc 'create_wallet' method. The new wallet is left open
+///
+/// so it is returned as a session.
+pub async fn create_wallet(
+    filename: &String,
+    password: &String,
+) -> Result<WalletSession, WalletRpcError> {
+    let (mut wallet, _) = acquire(filename, password).await;
     info!("executing {}", RpcFields::CreateWallet.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcCreateWalletParams {
         filename: String::from(filename),
         language: String::from("English"),
         password: String::from(password),
     };
-    let req = reqres::XmrRpcCreateRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::CreateWallet.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            // The result from wallet creation is empty
-            let res = response.text().await;
-            debug!("{} response: {:?}", RpcFields::CreateWallet.value(), res);
-            match res {
-                Ok(r) => {
-                    if r.contains("-1") {
-                        return false;
-                    }
-                    true
-                }
-                _ => false,
-            }
-        }
-        Err(_) => false,
-    }
+    // The result from wallet creation is empty
+    let _: reqres::XmrRpcEmptyResult =
+        wallet.rpc.call(RpcFields::CreateWallet, Some(params)).await?;
+    wallet.open.filename = String::from(filename);
+    wallet.open.password = String::from(password);
+    Ok(wallet)
 }
 
-/// Set the wallet lock to true during operations to avoid collisons
+/// Performs the xmr rpc 'restore_deterministic_wallet' method. The restored
 ///
-/// on different types of wallet (e.g. order versus NEVEKO instance).
-///
-/// The open functionality will break on `false` if busy.
-fn update_wallet_lock(filename: &String, closing: bool) -> bool {
-    let is_busy: bool = match IS_WALLET_BUSY.lock() {
-        Ok(m) => *m,
-        Err(_) => true,
+/// wallet is left open and scans from `restore_height` on the next refresh.
+pub async fn restore_deterministic_wallet(
+    filename: &String,
+    password: &String,
+    seed: &String,
+    restore_height: u64,
+) -> Result<WalletSession, WalletRpcError> {
+    let (mut wallet, _) = acquire(filename, password).await;
+    info!("executing {}", RpcFields::RestoreDeterministicWallet.value());
+    let params = reqres::XmrRpcRestoreDeterministicWalletParams {
+        restore_height,
+        filename: String::from(filename),
+        seed: String::from(seed),
+        seed_offset: String::new(),
+        password: String::from(password),
+        language: String::from("English"),
+        autosave_current: true,
     };
-    if is_busy && !closing {
-        debug!("wallet {} is busy", filename);
-        return false;
-    }
-    if !closing {
-        *IS_WALLET_BUSY.lock().unwrap() = true;
-        true
-    } else {
-        *IS_WALLET_BUSY.lock().unwrap() = false;
-        true
-    }
+    let result: reqres::XmrRpcRestoreDeterministicWalletResult = wallet
+        .rpc
+        .call(RpcFields::RestoreDeterministicWallet, Some(params))
+        .await?;
+    info!("{}", result.info);
+    wallet.open.filename = String::from(filename);
+    wallet.open.password = String::from(password);
+    Ok(wallet)
 }
 
-// TODO(c2m): search the app and use this output to either bail (false) or
-// continue (true)
-
-/// Performs the xmr rpc 'open_wallet' method
-pub async fn open_wallet(filename: &String, password: &String) -> bool {
-    let updated = update_wallet_lock(filename, false);
-    if !updated {
-        return updated;
-    }
-    info!("executing {}", RpcFields::Open.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let params = reqres::XmrRpcOpenWalletParams {
+/// Performs the xmr rpc 'generate_from_keys' method. An empty spend key
+///
+/// generates a view-only wallet.
+pub async fn generate_from_keys(
+    filename: &String,
+    password: &String,
+    address: &String,
+    viewkey: &String,
+    spendkey: &String,
+    restore_height: u64,
+) -> Result<WalletSession, WalletRpcError> {
+    let (mut wallet, _) = acquire(filename, password).await;
+    info!("executing {}", RpcFields::GenerateFromKeys.value());
+    let params = reqres::XmrRpcGenerateFromKeysParams {
+        restore_height,
         filename: String::from(filename),
+        address: String::from(address),
+        spendkey: String::from(spendkey),
+        viewkey: String::from(viewkey),
         password: String::from(password),
+        autosave_current: true,
     };
-    let req = reqres::XmrRpcOpenRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Open.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            // The result from wallet operation is empty
-            let res = response.text().await;
-            debug!("{} response: {:?}", RpcFields::Open.value(), res);
-            match res {
-                Ok(r) => {
-                    if r.contains("-1") {
-                        return false;
-                    }
-                    true
-                }
-                _ => false,
-            }
-        }
-        Err(_) => false,
-    }
+    let result: reqres::XmrRpcGenerateFromKeysResult = wallet
+        .rpc
+        .call(RpcFields::GenerateFromKeys, Some(params))
+        .await?;
+    info!("{}", result.info);
+    wallet.open.filename = String::from(filename);
+    wallet.open.password = String::from(password);
+    Ok(wallet)
 }
 
-/// Performs the xmr rpc 'close_wallet' method
-pub async fn close_wallet(filename: &String, password: &String) -> bool {
-    let updated = update_wallet_lock(filename, true);
-    if !updated {
-        return updated;
+/// Performs the xmr rpc 'open_wallet' method once it is our turn. The call
+///
+/// is skipped when the previous session left the same wallet open.
+pub async fn open_wallet(
+    filename: &String,
+    password: &String,
+) -> Result<WalletSession, WalletRpcError> {
+    let (mut wallet, is_open) = acquire(filename, password).await;
+    if is_open {
+        debug!("wallet {} is still open on worker {}", filename, wallet.worker);
+        return Ok(wallet);
     }
-    info!("executing {}", RpcFields::Close.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
+    info!("executing {} on worker {}", RpcFields::Open.value(), wallet.worker);
     let params = reqres::XmrRpcOpenWalletParams {
         filename: String::from(filename),
         password: String::from(password),
     };
-    let req = reqres::XmrRpcOpenRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Close.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            // The result from wallet operation is empty
-            let res = response.text().await;
-            debug!("{} response: {:?}", RpcFields::Close.value(), res);
-            res.is_ok()
-        }
-        Err(_) => false,
-    }
+    // The result from wallet operation is empty
+    let _: reqres::XmrRpcEmptyResult = wallet.rpc.call(RpcFields::Open, Some(params)).await?;
+    wallet.open.filename = String::from(filename);
+    wallet.open.password = String::from(password);
+    Ok(wallet)
+}
+
+/// Release a session and close its wallet without lingering
+pub async fn close_wallet(mut wallet: WalletSession) {
+    close_open_wallet(&mut wallet.open, &wallet.rpc).await;
 }
 
 /// Performs the xmr rpc 'change_wallet_password' method
-pub async fn change_wallet_password(new_password: &String) -> bool {
+pub async fn change_wallet_password(
+    wallet: &mut WalletSession,
+    new_password: &String,
+) -> Result<(), WalletRpcError> {
     info!("executing {}", RpcFields::ChangeWalletPassword.value());
-    let old_password: String =
-        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    let new_password: String = String::from(new_password);
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcChangePasswordParams {
-        old_password,
-        new_password,
-    };
-    let req = reqres::XmrRpcChangePasswordRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::ChangeWalletPassword.value(),
-        params,
+        old_password: String::from(&wallet.open.password),
+        new_password: String::from(new_password),
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            // The result from wallet operation is empty
-            let res = response.text().await;
-            debug!(
-                "{} response: {:?}",
-                RpcFields::ChangeWalletPassword.value(),
-                res
-            );
-            match res {
-                Ok(r) => {
-                    if r.contains("-1") {
-                        return false;
-                    }
-                    true
-                }
-                _ => false,
-            }
-        }
-        Err(_) => false,
-    }
+    // The result from wallet operation is empty
+    let _: reqres::XmrRpcEmptyResult = wallet
+        .rpc
+        .call(RpcFields::ChangeWalletPassword, Some(params))
+        .await?;
+    wallet.open.password = String::from(new_password);
+    Ok(())
 }
 
-/// Performs the xmr rpc 'get_balance' method
-pub async fn get_balance() -> reqres::XmrRpcBalanceResponse {
+/// Performs the xmr rpc 'get_balance' method over all accounts. The totals
+///
+/// cover every account, `per_subaddress` breaks them down.
+pub async fn get_balance(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcBalanceResponse, WalletRpcError> {
     info!("executing {}", RpcFields::Balance.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcBalanceParams = reqres::XmrRpcBalanceParams {
         account_index: 0,
-        address_indices: vec![0],
-        all_accounts: false,
+        address_indices: Vec::new(),
+        all_accounts: true,
         strict: false,
     };
-    let req = reqres::XmrRpcBalanceRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Balance.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcBalanceResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Balance.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Balance, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::Balance.value(), result);
+    Ok(reqres::XmrRpcBalanceResponse { result })
 }
 
 /// Performs the xmr rpc 'get_address' method
-pub async fn get_address() -> reqres::XmrRpcAddressResponse {
+pub async fn get_address(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcAddressResponse, WalletRpcError> {
+    get_subaddresses(wallet, 0, Vec::new()).await
+}
+
+/// Performs the xmr rpc 'get_address' method for some subaddresses of an
+///
+/// account. No address indices returns all of them.
+pub async fn get_subaddresses(
+    wallet: &WalletSession,
+    account_index: u32,
+    address_index: Vec<u32>,
+) -> Result<reqres::XmrRpcAddressResponse, WalletRpcError> {
     info!("executing {}", RpcFields::Address.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let params: reqres::XmrRpcAddressParams = reqres::XmrRpcAddressParams { account_index: 0 };
-    let req = reqres::XmrRpcAddressRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Address.value(),
-        params,
+    let params: reqres::XmrRpcAddressParams = reqres::XmrRpcAddressParams {
+        account_index,
+        address_index,
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcAddressResponse>().await;
-            match res {
-                Ok(res) => {
-                    debug!(
-                        "{} count: {}",
-                        RpcFields::Address.value(),
-                        &res.result.addresses.len()
-                    );
-                    res
-                }
-                _ => Default::default(),
-            }
-        }
-        Err(_) => Default::default(),
-    }
+    let result: reqres::XmrRpcAddressResult =
+        wallet.rpc.call(RpcFields::Address, Some(params)).await?;
+    debug!(
+        "{} count: {}",
+        RpcFields::Address.value(),
+        &result.addresses.len()
+    );
+    Ok(reqres::XmrRpcAddressResponse { result })
 }
 
 /// Performs the xmr rpc 'get_address' method
-pub async fn validate_address(address: &String) -> reqres::XmrRpcValidateAddressResponse {
+pub async fn validate_address(
+    wallet: &WalletSession,
+    address: &String,
+) -> Result<reqres::XmrRpcValidateAddressResponse, WalletRpcError> {
     info!("executing {}", RpcFields::ValidateAddress.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcValidateAddressParams = reqres::XmrRpcValidateAddressParams {
         address: String::from(address),
         any_net_type: false,
         allow_openalias: true,
     };
-    let req = reqres::XmrRpcValidateAddressRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::ValidateAddress.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response
-                .json::<reqres::XmrRpcValidateAddressResponse>()
-                .await;
-            debug!("{} response: {:?}", RpcFields::ValidateAddress.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::ValidateAddress, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::ValidateAddress.value(), result);
+    Ok(reqres::XmrRpcValidateAddressResponse { result })
 }
 // START Multisig
 
 /// Performs the xmr rpc 'prepare_multisig' method
-pub async fn prepare_wallet() -> reqres::XmrRpcPrepareResponse {
+pub async fn prepare_wallet(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcPrepareResponse, WalletRpcError> {
     info!("executing {}", RpcFields::Prepare.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcPrepareParams {
         enable_experimental_multisig: true,
     };
-    let req = reqres::XmrRpcPrepareRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Prepare.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcPrepareResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Prepare.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Prepare, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::Prepare.value(), result);
+    Ok(reqres::XmrRpcPrepareResponse { result })
 }
 
 /// Performs the xmr rpc 'make_multisig' method
-pub async fn make_wallet(info: Vec<String>) -> reqres::XmrRpcMakeResponse {
+pub async fn make_wallet(
+    wallet: &WalletSession,
+    info: Vec<String>,
+) -> Result<reqres::XmrRpcMakeResponse, WalletRpcError> {
     info!("executing {}", RpcFields::Make.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcMakeParams {
         multisig_info: info,
         threshold: 2,
     };
-    let req = reqres::XmrRpcMakeRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Make.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcMakeResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Make.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Make, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::Make.value(), result);
+    Ok(reqres::XmrRpcMakeResponse { result })
 }
 
 /// Performs the xmr rpc 'export_multisig_info' method
-pub async fn export_multisig_info() -> reqres::XmrRpcExportResponse {
+pub async fn export_multisig_info(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcExportResponse, WalletRpcError> {
     info!("executing {}", RpcFields::Export.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let req = reqres::XmrRpcRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Export.value(),
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcExportResponse>().await;
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Export, NO_PARAMS).await?;
+    Ok(reqres::XmrRpcExportResponse { result })
 }
 
 /// Performs the xmr rpc 'import_multisig_info' method
-pub async fn import_multisig_info(info: Vec<String>) -> reqres::XmrRpcImportResponse {
+pub async fn import_multisig_info(
+    wallet: &WalletSession,
+    info: Vec<String>,
+) -> Result<reqres::XmrRpcImportResponse, WalletRpcError> {
     info!("executing {}", RpcFields::Import.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcImportParams { info };
-    let req = reqres::XmrRpcImportRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Import.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcImportResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Import.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Import, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::Import.value(), result);
+    Ok(reqres::XmrRpcImportResponse { result })
 }
 
 /// Performs the xmr rpc 'sign_multisig' method
-pub async fn sign_multisig(tx_data_hex: String) -> reqres::XmrRpcSignMultisigResponse {
+pub async fn sign_multisig(
+    wallet: &WalletSession,
+    tx_data_hex: String,
+) -> Result<reqres::XmrRpcSignMultisigResponse, WalletRpcError> {
     info!("executing {}", RpcFields::SignMultisig.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcSignMultisigParams { tx_data_hex };
-    let req = reqres::XmrRpcSignMultisigRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::SignMultisig.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcSignMultisigResponse>().await;
-            debug!("{} response: {:?}", RpcFields::SignMultisig.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::SignMultisig, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::SignMultisig.value(), result);
+    Ok(reqres::XmrRpcSignMultisigResponse { result })
 }
 
 /// Performs the xmr rpc 'submit_multisig' method
-pub async fn submit_multisig(tx_data_hex: String) -> reqres::XmrRpcSubmitMultisigResponse {
+pub async fn submit_multisig(
+    wallet: &WalletSession,
+    tx_data_hex: String,
+) -> Result<reqres::XmrRpcSubmitMultisigResponse, WalletRpcError> {
     info!("executing {}", RpcFields::SubmitMultisig.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcSignMultisigParams { tx_data_hex };
-    let req = reqres::XmrRpcSignMultisigRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::SubmitMultisig.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response
-                .json::<reqres::XmrRpcSubmitMultisigResponse>()
-                .await;
-            debug!("{} response: {:?}", RpcFields::SubmitMultisig.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::SubmitMultisig, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::SubmitMultisig.value(), result);
+    Ok(reqres::XmrRpcSubmitMultisigResponse { result })
 }
 
 /// Performs the xmr rpc 'exchange_multisig_keys' method
 pub async fn exchange_multisig_keys(
+    wallet: &WalletSession,
     force_update_use_with_caution: bool,
     multisig_info: Vec<String>,
     password: &String,
-) -> reqres::XmrRpcExchangeMultisigKeysResponse {
+) -> Result<reqres::XmrRpcExchangeMultisigKeysResponse, WalletRpcError> {
     info!("executing: {}", RpcFields::ExchangeMultisigKeys.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params = reqres::XmrRpcExchangeMultisigKeysParams {
         force_update_use_with_caution,
         password: String::from(password),
         multisig_info,
     };
-    let req = reqres::XmrRpcExchangeMultisigKeysRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::ExchangeMultisigKeys.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response
-                .json::<reqres::XmrRpcExchangeMultisigKeysResponse>()
-                .await;
-            debug!(
-                "{} response: {:?}",
-                RpcFields::ExchangeMultisigKeys.value(),
-                res
-            );
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet
+        .rpc
+        .call(RpcFields::ExchangeMultisigKeys, Some(params))
+        .await?;
+    debug!(
+        "{} response: {:?}",
+        RpcFields::ExchangeMultisigKeys.value(),
+        result
+    );
+    Ok(reqres::XmrRpcExchangeMultisigKeysResponse { result })
 }
 // END Multisig
 
 /// Performs the xmr rpc 'check_tx_proof' method
-pub async fn check_tx_proof(txp: &proof::TxProof) -> reqres::XmrRpcCheckTxProofResponse {
+pub async fn check_tx_proof(
+    wallet: &WalletSession,
+    txp: &proof::TxProof,
+) -> Result<reqres::XmrRpcCheckTxProofResponse, WalletRpcError> {
     info!("executing {}", RpcFields::CheckTxProof.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcCheckTxProofParams = reqres::XmrRpcCheckTxProofParams {
         address: String::from(&txp.subaddress),
         message: String::from(&txp.message),
         signature: String::from(&txp.signature),
         txid: String::from(&txp.hash),
     };
-    let req = reqres::XmrRpcCheckTxProofRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::CheckTxProof.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcCheckTxProofResponse>().await;
-            debug!("{} response: {:?}", RpcFields::CheckTxProof.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::CheckTxProof, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::CheckTxProof.value(), result);
+    Ok(reqres::XmrRpcCheckTxProofResponse { result })
 }
 
 /// Performs the xmr rpc 'get_tx_proof' method
-pub async fn get_tx_proof(ptxp: proof::TxProof) -> reqres::XmrRpcGetTxProofResponse {
+pub async fn get_tx_proof(
+    wallet: &WalletSession,
+    ptxp: proof::TxProof,
+) -> Result<reqres::XmrRpcGetTxProofResponse, WalletRpcError> {
     info!("executing {}", RpcFields::GetTxProof.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcGetTxProofParams = reqres::XmrRpcGetTxProofParams {
         address: String::from(&ptxp.subaddress),
         message: String::from(&ptxp.message),
         txid: String::from(&ptxp.hash),
     };
-    let req = reqres::XmrRpcGetTxProofRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::GetTxProof.value(),
-        params,
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcGetTxProofResponse>().await;
-            debug!("{} response: {:?}", RpcFields::GetTxProof.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::GetTxProof, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::GetTxProof.value(), result);
+    Ok(reqres::XmrRpcGetTxProofResponse { result })
 }
 
 /// Performs the xmr rpc 'get_transfer_by_txid' method
-pub async fn get_transfer_by_txid(txid: &str) -> reqres::XmrRpcGetTxByIdResponse {
+pub async fn get_transfer_by_txid(
+    wallet: &WalletSession,
+    txid: &str,
+) -> Result<reqres::XmrRpcGetTxByIdResponse, WalletRpcError> {
     info!("executing: {}", RpcFields::GetTxById.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcGetTxByIdParams = reqres::XmrRpcGetTxByIdParams {
         txid: String::from(txid),
     };
-    let req = reqres::XmrRpcGetTxByIdRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::GetTxById.value(),
-        params,
+    let result = wallet.rpc.call(RpcFields::GetTxById, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::GetTxById.value(), result);
+    Ok(reqres::XmrRpcGetTxByIdResponse { result })
+}
+
+/// Performs the xmr rpc 'get_transfers' method
+pub async fn get_transfers(
+    wallet: &WalletSession,
+    filter: &TransferFilter,
+) -> Result<reqres::XmrRpcGetTransfersResponse, WalletRpcError> {
+    info!("executing: {}", RpcFields::GetTransfers.value());
+    let params = reqres::XmrRpcGetTransfersParams {
+        r#in: filter.is_in,
+        out: filter.is_out,
+        pending: filter.is_pending,
+        failed: false,
+        pool: filter.is_pool,
+        account_index: 0,
+        subaddr_indices: filter.subaddr_indices.clone(),
+        all_accounts: true,
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcGetTxByIdResponse>().await;
-            debug!("{} response: {:?}", RpcFields::GetTxById.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::GetTransfers, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::GetTransfers.value(), result);
+    Ok(reqres::XmrRpcGetTransfersResponse { result })
 }
 
-/// Performs the xmr rpc 'transfer' method
-pub async fn transfer(d: reqres::Destination) -> reqres::XmrRpcTransferResponse {
-    info!("executing {}", RpcFields::Transfer.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
+/// Performs the xmr rpc 'transfer' method from any subaddress of an account
+pub async fn transfer(
+    wallet: &WalletSession,
+    d: reqres::Destination,
+    priority: FeePriority,
+    account_index: u32,
+) -> Result<reqres::XmrRpcTransferResponse, WalletRpcError> {
+    info!("executing {} from account {}", RpcFields::Transfer.value(), account_index);
     let destinations = vec![d];
     let params: reqres::XmrRpcTransferParams = reqres::XmrRpcTransferParams {
-        account_index: 0,
+        account_index,
         destinations,
         get_tx_key: false,
-        priority: 0,
+        priority: priority.value(),
         ring_size: RING_SIZE,
-        subaddr_indices: vec![0],
+        subaddr_indices: Vec::new(),
+        do_not_relay: false,
+        get_tx_metadata: false,
     };
-    let req = reqres::XmrRpcTransfrerRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Transfer.value(),
-        params,
+    let result = wallet.rpc.call(RpcFields::Transfer, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::Transfer.value(), result);
+    Ok(reqres::XmrRpcTransferResponse { result })
+}
+
+/// Performs the xmr rpc 'transfer' method without relaying. The signed tx
+///
+/// comes back as `tx_metadata` so the fee can be shown before `relay_tx`.
+pub async fn transfer_dry_run(
+    wallet: &WalletSession,
+    d: reqres::Destination,
+    priority: FeePriority,
+    account_index: u32,
+    subaddr_indices: Vec<u32>,
+) -> Result<reqres::XmrRpcTransferResponse, WalletRpcError> {
+    info!("executing {} dry run", RpcFields::Transfer.value());
+    let params: reqres::XmrRpcTransferParams = reqres::XmrRpcTransferParams {
+        account_index,
+        destinations: vec![d],
+        get_tx_key: false,
+        priority: priority.value(),
+        ring_size: RING_SIZE,
+        subaddr_indices,
+        do_not_relay: true,
+        get_tx_metadata: true,
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcTransferResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Transfer.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Transfer, Some(params)).await?;
+    debug!("{} dry run response: {:?}", RpcFields::Transfer.value(), result);
+    Ok(reqres::XmrRpcTransferResponse { result })
+}
+
+/// Performs the xmr rpc 'relay_tx' method
+pub async fn relay_tx(
+    wallet: &WalletSession,
+    tx_metadata: &String,
+) -> Result<reqres::XmrRpcRelayTxResponse, WalletRpcError> {
+    info!("executing {}", RpcFields::RelayTx.value());
+    let params: reqres::XmrRpcRelayTxParams = reqres::XmrRpcRelayTxParams {
+        hex: String::from(tx_metadata),
+    };
+    let result = wallet.rpc.call(RpcFields::RelayTx, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::RelayTx.value(), result);
+    Ok(reqres::XmrRpcRelayTxResponse { result })
 }
 
 /// Performs the xmr rpc 'describe_transfer' method
-pub async fn describe_transfer(multisig_txset: &String) -> reqres::XmrRpcDescribeTransferResponse {
+pub async fn describe_transfer(
+    wallet: &WalletSession,
+    multisig_txset: &String,
+) -> Result<reqres::XmrRpcDescribeTransferResponse, WalletRpcError> {
     info!("executing {}", RpcFields::DescribeTransfer.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcDescribeTransferParams = reqres::XmrRpcDescribeTransferParams {
         multisig_txset: String::from(multisig_txset),
     };
-    let req = reqres::XmrRpcDescribeTransfrerRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::DescribeTransfer.value(),
-        params,
+    let result = wallet
+        .rpc
+        .call(RpcFields::DescribeTransfer, Some(params))
+        .await?;
+    debug!(
+        "{} response: {:?}",
+        RpcFields::DescribeTransfer.value(),
+        result
+    );
+    Ok(reqres::XmrRpcDescribeTransferResponse { result })
+}
+
+/// Performs the xmr rpc 'sweep_all' method on every subaddress of an account
+pub async fn sweep_all(
+    wallet: &WalletSession,
+    address: String,
+    account_index: u32,
+) -> Result<reqres::XmrRpcSweepAllResponse, WalletRpcError> {
+    info!("executing {} from account {}", RpcFields::SweepAll.value(), account_index);
+    let params: reqres::XmrRpcSweepAllParams = reqres::XmrRpcSweepAllParams {
+        address,
+        account_index,
+        subaddr_indices_all: true,
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response
-                .json::<reqres::XmrRpcDescribeTransferResponse>()
-                .await;
-            debug!(
-                "{} response: {:?}",
-                RpcFields::DescribeTransfer.value(),
-                res
-            );
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
+    let result = wallet.rpc.call(RpcFields::SweepAll, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::SweepAll.value(), result);
+    Ok(reqres::XmrRpcSweepAllResponse { result })
+}
+
+/// Sweep every account with an unlocked balance to an address. wallet-rpc
+///
+/// sweeps one account per call so the results are merged, an account that
+///
+/// fails is skipped unless none of them could be swept.
+pub async fn sweep_accounts(
+    wallet: &WalletSession,
+    address: String,
+) -> Result<reqres::XmrRpcSweepAllResponse, WalletRpcError> {
+    let accounts = get_accounts(wallet).await?.result.subaddress_accounts;
+    let mut merged: reqres::XmrRpcSweepAllResult = Default::default();
+    let mut failure: Option<WalletRpcError> = None;
+    for a in accounts.iter().filter(|a| a.unlocked_balance > 0) {
+        let r = match sweep_all(wallet, String::from(&address), a.account_index).await {
+            Ok(r) => r.result,
+            Err(e) => {
+                error!("failed to sweep account {}: {}", a.account_index, e);
+                failure = Some(e);
+                continue;
+            }
+        };
+        merged.amount_list.extend(r.amount_list);
+        merged.fee_list.extend(r.fee_list);
+        merged.spent_key_images_list.extend(r.spent_key_images_list);
+        merged.weight_list.extend(r.weight_list);
+        let hashes = r.tx_hash_list.unwrap_or_default();
+        merged.tx_hash_list.get_or_insert_with(Vec::new).extend(hashes);
+    }
+    match failure {
+        Some(e) if merged.tx_hash_list.is_none() => Err(e),
+        _ => Ok(reqres::XmrRpcSweepAllResponse { result: merged }),
     }
 }
 
-/// Performs the xmr rpc 'sweep_all' method
-pub async fn sweep_all(address: String) -> reqres::XmrRpcSweepAllResponse {
-    info!("executing {}", RpcFields::SweepAll.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let params: reqres::XmrRpcSweepAllParams = reqres::XmrRpcSweepAllParams { address };
-    let req = reqres::XmrRpcSweepAllRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::SweepAll.value(),
-        params,
+/// Performs the xmr rpc 'create_account' method
+pub async fn create_account(
+    wallet: &WalletSession,
+    label: &String,
+) -> Result<reqres::XmrRpcCreateAccountResponse, WalletRpcError> {
+    info!("executing {}", RpcFields::CreateAccount.value());
+    let params: reqres::XmrRpcCreateAccountParams = reqres::XmrRpcCreateAccountParams {
+        label: String::from(label),
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcSweepAllResponse>().await;
-            debug!("{} response: {:?}", RpcFields::SweepAll.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::CreateAccount, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::CreateAccount.value(), result);
+    Ok(reqres::XmrRpcCreateAccountResponse { result })
 }
 
 /// Performs the xmr rpc 'create_address' method
-pub async fn create_address() -> reqres::XmrRpcCreateAddressResponse {
+pub async fn create_address(
+    wallet: &WalletSession,
+    account_index: u32,
+) -> Result<reqres::XmrRpcCreateAddressResponse, WalletRpcError> {
     info!("executing {}", RpcFields::CreateAddress.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcCreateAddressParams =
-        reqres::XmrRpcCreateAddressParams { account_index: 0 };
-    let req = reqres::XmrRpcCreateAddressRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::CreateAddress.value(),
-        params,
+        reqres::XmrRpcCreateAddressParams { account_index };
+    let result = wallet.rpc.call(RpcFields::CreateAddress, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::CreateAddress.value(), result);
+    Ok(reqres::XmrRpcCreateAddressResponse { result })
+}
+
+/// Performs the xmr rpc 'get_accounts' method
+pub async fn get_accounts(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcGetAccountsResponse, WalletRpcError> {
+    info!("executing {}", RpcFields::GetAccounts.value());
+    let result = wallet.rpc.call(RpcFields::GetAccounts, NO_PARAMS).await?;
+    debug!("{} response: {:?}", RpcFields::GetAccounts.value(), result);
+    Ok(reqres::XmrRpcGetAccountsResponse { result })
+}
+
+/// Performs the xmr rpc 'label_address' method
+pub async fn label_address(
+    wallet: &WalletSession,
+    major: u64,
+    minor: u64,
+    label: &String,
+) -> Result<(), WalletRpcError> {
+    info!("executing {}", RpcFields::LabelAddress.value());
+    let params: reqres::XmrRpcLabelAddressParams = reqres::XmrRpcLabelAddressParams {
+        index: reqres::SubAddressIndex { major, minor },
+        label: String::from(label),
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcCreateAddressResponse>().await;
-            debug!("{} response: {:?}", RpcFields::CreateAddress.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    // The result from labelling is empty
+    let _: reqres::XmrRpcEmptyResult =
+        wallet.rpc.call(RpcFields::LabelAddress, Some(params)).await?;
+    Ok(())
+}
+
+/// Performs the xmr rpc 'get_address_index' method
+pub async fn get_address_index(
+    wallet: &WalletSession,
+    address: &String,
+) -> Result<reqres::XmrRpcGetAddressIndexResponse, WalletRpcError> {
+    info!("executing {}", RpcFields::GetAddressIndex.value());
+    let params: reqres::XmrRpcGetAddressIndexParams = reqres::XmrRpcGetAddressIndexParams {
+        address: String::from(address),
+    };
+    let result = wallet.rpc.call(RpcFields::GetAddressIndex, Some(params)).await?;
+    debug!("{} response: {:?}", RpcFields::GetAddressIndex.value(), result);
+    Ok(reqres::XmrRpcGetAddressIndexResponse { result })
 }
 
 /// Performs the xmr rpc 'refresh' method
-pub async fn refresh() -> reqres::XmrRpcRefreshResponse {
+pub async fn refresh(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcRefreshResponse, WalletRpcError> {
     info!("executing {}", RpcFields::Refresh.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let req = reqres::XmrRpcRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::Refresh.value(),
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcRefreshResponse>().await;
-            debug!("{} response: {:?}", RpcFields::Refresh.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::Refresh, NO_PARAMS).await?;
+    debug!("{} response: {:?}", RpcFields::Refresh.value(), result);
+    Ok(reqres::XmrRpcRefreshResponse { result })
 }
 
 /// Performs the xmr rpc 'is_multisig' method
-pub async fn is_multisig() -> reqres::XmrRpcIsMultisigResponse {
+pub async fn is_multisig(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcIsMultisigResponse, WalletRpcError> {
     info!("executing {}", RpcFields::IsMultisig.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let req = reqres::XmrRpcRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::IsMultisig.value(),
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcIsMultisigResponse>().await;
-            debug!("{} response: {:?}", RpcFields::IsMultisig.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::IsMultisig, NO_PARAMS).await?;
+    debug!("{} response: {:?}", RpcFields::IsMultisig.value(), result);
+    Ok(reqres::XmrRpcIsMultisigResponse { result })
 }
 
 /// Performs the xmr rpc 'get_height' method
-pub async fn get_wallet_height() -> reqres::XmrRpcGetHeightResponse {
+pub async fn get_wallet_height(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcGetHeightResponse, WalletRpcError> {
     info!("executing wallet {}", RpcFields::GetHeight.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
-    let req = reqres::XmrRpcRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::GetHeight.value(),
-    };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcGetHeightResponse>().await;
-            debug!("{} response: {:?}", RpcFields::GetHeight.value(), res);
-            res.unwrap_or_default()
-        }
-        Err(_) => Default::default(),
-    }
+    let result = wallet.rpc.call(RpcFields::GetHeight, NO_PARAMS).await?;
+    debug!("{} response: {:?}", RpcFields::GetHeight.value(), result);
+    Ok(reqres::XmrRpcGetHeightResponse { result })
 }
 
 /// Performs the xmr rpc 'query_key' method
-pub async fn query_view_key() -> reqres::XmrRpcQueryKeyResponse {
+pub async fn query_view_key(
+    wallet: &WalletSession,
+) -> Result<reqres::XmrRpcQueryKeyResponse, WalletRpcError> {
     info!("executing wallet {}", RpcFields::QueryKey.value());
-    let client = reqwest::Client::new();
-    let host = get_rpc_host();
     let params: reqres::XmrRpcQueryKeyParams = reqres::XmrRpcQueryKeyParams {
         key_type: String::from(QUERY_TYPE_VIEW_KEY),
     };
-    let req = reqres::XmrRpcQueryKeyRequest {
-        jsonrpc: RpcFields::JsonRpcVersion.value(),
-        id: RpcFields::Id.value(),
-        method: RpcFields::QueryKey.value(),
-        params,
+    let result = wallet.rpc.call(RpcFields::QueryKey, Some(params)).await?;
+    Ok(reqres::XmrRpcQueryKeyResponse { result })
+}
+
+/// Whether the session's wallet has no spend key. Watch-only wallets refuse
+///
+/// to return it, older versions return an all zero key instead.
+pub async fn is_view_only(wallet: &WalletSession) -> Result<bool, WalletRpcError> {
+    info!("executing wallet {} for spend key", RpcFields::QueryKey.value());
+    let params: reqres::XmrRpcQueryKeyParams = reqres::XmrRpcQueryKeyParams {
+        key_type: String::from(QUERY_TYPE_SPEND_KEY),
     };
-    let login: RpcLogin = get_rpc_creds();
-    match client
-        .post(host)
-        .json(&req)
-        .send_with_digest_auth(&login.username, &login.credential)
-        .await
-    {
-        Ok(response) => {
-            let res = response.json::<reqres::XmrRpcQueryKeyResponse>().await;
-            res.unwrap_or_default()
+    let result: Result<reqres::XmrRpcQueryKeyResult, WalletRpcError> =
+        wallet.rpc.call(RpcFields::QueryKey, Some(params)).await;
+    match result {
+        Ok(r) => Ok(r.key.chars().all(|c| c == '0')),
+        Err(WalletRpcError::WatchOnly) => Ok(true),
+        Err(e) => Err(e),
+    }
+}
+
+/// Performs the xmr rpc 'set_daemon' method on every worker. Not bound to
+///
+/// a wallet session since it applies to the whole monero-wallet-rpc process.
+///
+/// Every worker is attempted, the last failure is returned.
+pub async fn set_daemon(address: &String) -> Result<(), WalletRpcError> {
+    info!("executing {} to {}", RpcFields::SetDaemon.value(), address);
+    let mut result = Ok(());
+    for worker in 0..get_rpc_workers() {
+        if let Err(e) = set_worker_daemon(worker, address).await {
+            error!("failed to set worker {} daemon to {}: {}", worker, address, e);
+            result = Err(e);
         }
-        Err(_) => Default::default(),
     }
+    result
+}
+
+async fn set_worker_daemon(worker: usize, address: &String) -> Result<(), WalletRpcError> {
+    let params = reqres::XmrRpcSetDaemonParams {
+        address: String::from(address),
+        trusted: true,
+        ssl_allow_any_cert: true,
+    };
+    // The result from wallet operation is empty
+    let _: reqres::XmrRpcEmptyResult = WalletRpc::for_worker(worker)
+        .call(RpcFields::SetDaemon, Some(params))
+        .await?;
+    Ok(())
 }
 
 // Daemon requests
@@ -1330,11 +1478,18 @@ pub async fn get_info() -> reqres::XmrDaemonGetInfoResponse {
 
 /// Performs the xmr daemon 'get_info' method for proxied daemons
 pub async fn p_get_info() -> Result<reqres::XmrDaemonGetInfoResponse, Box<dyn Error>> {
-    info!("fetching proxy daemon info");
+    p_get_info_from(&get_rpc_daemon()).await
+}
+
+/// Performs the xmr daemon 'get_info' method on a specific proxied daemon
+pub async fn p_get_info_from(
+    daemon: &String,
+) -> Result<reqres::XmrDaemonGetInfoResponse, Box<dyn Error>> {
+    info!("fetching proxy daemon info from {}", daemon);
     let host = utils::get_i2p_http_proxy();
     let proxy = reqwest::Proxy::http(&host)?;
     let client = reqwest::Client::builder().proxy(proxy).build();
-    let host = format!("{}/json_rpc", get_rpc_daemon());
+    let host = format!("{}/json_rpc", daemon);
     let req = reqres::XmrRpcRequest {
         jsonrpc: DaemonFields::Version.value(),
         id: DaemonFields::Id.value(),
@@ -1369,6 +1524,52 @@ pub async fn get_height() -> reqres::XmrDaemonGetHeightResponse {
     }
 }
 
+/// Performs the xmr daemon 'get_fee_estimate' method
+pub async fn get_fee_estimate() -> reqres::XmrDaemonGetFeeEstimateResponse {
+    info!("fetching fee estimate");
+    let client = reqwest::Client::new();
+    let host = format!("{}/json_rpc", get_rpc_daemon());
+    let req = reqres::XmrRpcRequest {
+        jsonrpc: DaemonFields::Version.value(),
+        id: DaemonFields::Id.value(),
+        method: DaemonFields::GetFeeEstimate.value(),
+    };
+    match client.post(host).json(&req).send().await {
+        Ok(response) => {
+            let res = response.json::<reqres::XmrDaemonGetFeeEstimateResponse>().await;
+            debug!("fee estimate response: {:?}", res);
+            res.unwrap_or_default()
+        }
+        Err(_) => Default::default(),
+    }
+}
+
+/// Performs the xmr daemon 'get_fee_estimate' method for remote daemon
+pub async fn p_get_fee_estimate(
+) -> Result<reqres::XmrDaemonGetFeeEstimateResponse, Box<dyn Error>> {
+    info!("fetching proxy fee estimate");
+    let host = utils::get_i2p_http_proxy();
+    let proxy = reqwest::Proxy::http(&host)?;
+    let client = reqwest::Client::builder().proxy(proxy).build();
+    let host = format!("{}/json_rpc", get_rpc_daemon());
+    let req = reqres::XmrRpcRequest {
+        jsonrpc: DaemonFields::Version.value(),
+        id: DaemonFields::Id.value(),
+        method: DaemonFields::GetFeeEstimate.value(),
+    };
+    match client?.post(host).json(&req).send().await {
+        Ok(response) => {
+            let res = response.json::<reqres::XmrDaemonGetFeeEstimateResponse>().await;
+            debug!("proxy fee estimate response: {:?}", res);
+            match res {
+                Ok(res) => Ok(res),
+                _ => Ok(Default::default()),
+            }
+        }
+        Err(_) => Ok(Default::default()),
+    }
+}
+
 /// Performs the xmr daemon 'get_height' method for remote daemon
 pub async fn p_get_height() -> Result<reqres::XmrDaemonGetHeightResponse, Box<dyn Error>> {
     info!("fetching proxy daemon height");
@@ -1491,3 +1692,38 @@ pub async fn p_get_transactions(
 }
 
 // End XMR daemon methods
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acquire_test() {
+        let rt = tokio::runtime::Runtime::new().expect("Unable to create Runtime for test");
+        rt.block_on(async {
+            let filename = String::from("test");
+            let (wallet, is_open) = acquire(&filename, &String::new()).await;
+            assert!(!is_open);
+            assert!(wallet.open.filename.is_empty());
+            let generation = wallet.open.generation;
+            drop(wallet);
+            // waiters are served once the previous holder releases the lock
+            let (wallet, _) = acquire(&filename, &String::new()).await;
+            assert_eq!(wallet.open.generation, generation + 1);
+        });
+    }
+
+    #[test]
+    fn get_wallet_worker_test() {
+        let orid = String::from("O123");
+        assert_eq!(get_wallet_worker(&orid, 1), 0);
+        assert_eq!(get_wallet_worker(&String::from(crate::APP_NAME), 4), 0);
+        let worker = get_wallet_worker(&orid, 4);
+        assert!(worker > 0 && worker < 4);
+        // pinned wallets always route to the same worker
+        assert_eq!(get_wallet_worker(&orid, 4), worker);
+    }
+}
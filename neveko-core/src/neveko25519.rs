@@ -16,6 +16,7 @@ use sha2::{
     Digest,
     Sha512,
 };
+use std::sync::Mutex;
 
 #[derive(Debug)]
 /// Container for the Neveko Message Keys
@@ -36,6 +37,11 @@ pub struct NevekoMessageKeys {
 const CURVE_L: &str = "edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010";
 pub const ENCIPHER: &str = "ENCIPHER";
 
+/// NMSK kept after the first derivation. Peer authentication needs it on
+///
+/// every request and deriving it opens the wallet.
+static NMSK: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
 fn curve_l_as_big_int() -> BigInt {
     BigInt::from_bytes_le(Sign::Plus, &hex::decode(CURVE_L).unwrap_or_default())
 }
@@ -83,14 +89,22 @@ pub async fn generate_neveko_message_keys() -> NevekoMessageKeys {
     log::info!("generating neveko message keys");
     let password = std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or_default();
     let filename = String::from(crate::APP_NAME);
-    let m_wallet = monero::open_wallet(&filename, &password).await;
-    if !m_wallet {
-        log::error!("failed to open wallet");
-        return Default::default();
-    }
-    let svk_res = monero::query_view_key().await;
-    monero::close_wallet(&filename, &password).await;
-    let svk = svk_res.result.key;
+    let wallet = match monero::open_wallet(&filename, &password).await {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("failed to open wallet: {}", e);
+            return Default::default();
+        }
+    };
+    let svk_res = monero::query_view_key(&wallet).await;
+    drop(wallet);
+    let svk = match svk_res {
+        Ok(r) => r.result.key,
+        Err(e) => {
+            log::error!("failed to query view key: {}", e);
+            return Default::default();
+        }
+    };
     let scalar_nmsk = hash_to_scalar(vec![&svk[..], crate::APP_NAME]);
     let point_nmpk = EdwardsPoint::mul_base(&scalar_nmsk);
     let nmsk = *scalar_nmsk.as_bytes();
@@ -105,6 +119,40 @@ pub async fn generate_neveko_message_keys() -> NevekoMessageKeys {
     }
 }
 
+/// Our NMSK, derived from the wallet once per process
+async fn get_nmsk() -> Option<[u8; 32]> {
+    let cached = *NMSK.lock().unwrap_or_else(|e| e.into_inner());
+    if cached.is_some() {
+        return cached;
+    }
+    let keys: NevekoMessageKeys = generate_neveko_message_keys().await;
+    if keys.hex_nmsk.is_empty() {
+        return None;
+    }
+    *NMSK.lock().unwrap_or_else(|e| e.into_inner()) = Some(keys.nmsk);
+    Some(keys.nmsk)
+}
+
+/// Hex encoded `nmpk * nmsk`. Keys that don't decompress to a point of
+///
+/// prime order are refused, their shared secret would be guessable.
+fn get_shared_secret_with(nmsk: &[u8; 32], hex_nmpk: &str) -> Option<String> {
+    let mut nmpk: [u8; 32] = [0u8; 32];
+    hex::decode_to_slice(hex_nmpk, &mut nmpk as &mut [u8]).ok()?;
+    let point_nmpk = CompressedEdwardsY(nmpk).decompress()?;
+    if point_nmpk.is_small_order() {
+        return None;
+    }
+    let shared_secret = point_nmpk * Scalar::from_bytes_mod_order(*nmsk);
+    Some(hex::encode(shared_secret.compress().as_bytes()))
+}
+
+/// Shared secret of our NMSK and the NMPK of a peer
+pub async fn get_shared_secret(hex_nmpk: &str) -> Option<String> {
+    let nmsk = get_nmsk().await?;
+    get_shared_secret_with(&nmsk, hex_nmpk)
+}
+
 /// Encipher a string by using the contact's Neveko Message Public Key.
 ///
 /// E.g. shared_secret_alice = nmpk_bob * nmsk_alice = h`
@@ -188,6 +236,22 @@ mod tests {
         };
     }
 
+    #[test]
+    fn get_shared_secret_with_test() {
+        let alice = hash_to_scalar(vec!["alice"]);
+        let bob = hash_to_scalar(vec!["bob"]);
+        let alice_nmpk = hex::encode(EdwardsPoint::mul_base(&alice).compress().as_bytes());
+        let bob_nmpk = hex::encode(EdwardsPoint::mul_base(&bob).compress().as_bytes());
+        let alice_ss = get_shared_secret_with(alice.as_bytes(), &bob_nmpk);
+        let bob_ss = get_shared_secret_with(bob.as_bytes(), &alice_nmpk);
+        assert!(alice_ss.is_some());
+        assert_eq!(alice_ss, bob_ss);
+        // the identity point would give the same secret for every nmsk
+        let identity = hex::encode(EdwardsPoint::default().compress().as_bytes());
+        assert!(get_shared_secret_with(alice.as_bytes(), &identity).is_none());
+        assert!(get_shared_secret_with(alice.as_bytes(), "not hex").is_none());
+    }
+
     #[test]
     pub fn encipher_decipher() {
         let message = String::from(
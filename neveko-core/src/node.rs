@@ -0,0 +1,253 @@
+//! Remote node pool with health checks and automatic failover
+
+use crate::{
+    args,
+    db::{
+        self,
+        DATABASE_LOCK,
+    },
+    error::NevekoError,
+    models::*,
+    monero,
+};
+use clap::Parser;
+use kn0sys_lmdb_rs::MdbError;
+use log::{
+    debug,
+    error,
+    info,
+    warn,
+};
+
+/// Remote nodes from `--monero-rpc-daemon` and `--monero-remote-nodes`.
+///
+/// The first one is what monero-wallet-rpc is started with.
+pub fn get_pool() -> Vec<String> {
+    let args = args::Args::parse();
+    parse_pool(&args.monero_rpc_daemon, &args.monero_remote_nodes)
+}
+
+fn parse_pool(daemon: &str, remote_nodes: &str) -> Vec<String> {
+    let mut pool: Vec<String> = Vec::new();
+    for n in std::iter::once(daemon).chain(remote_nodes.split(",")) {
+        let n = n.trim();
+        if !n.is_empty() && !pool.iter().any(|p| p == n) {
+            pool.push(String::from(n));
+        }
+    }
+    pool
+}
+
+/// Lookup the status of every remote node from the last check
+pub fn find_all() -> Result<Vec<RemoteNode>, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = crate::REMOTE_NODE_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        debug!("remote nodes have not been checked yet");
+        return Ok(Vec::new());
+    }
+    let result: Vec<RemoteNode> = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+fn save_all(nodes: &Vec<RemoteNode>) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = crate::REMOTE_NODE_LIST_DB_KEY;
+    let v = bincode::serialize(nodes).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Remote node monero-wallet-rpc was last failed over to, if any
+pub fn get_current() -> Option<String> {
+    let db = &DATABASE_LOCK;
+    let k = crate::REMOTE_NODE_CURRENT_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec()).ok()?;
+    if r.is_empty() {
+        return None;
+    }
+    bincode::deserialize(&r[..]).ok()
+}
+
+fn set_current(address: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = crate::REMOTE_NODE_CURRENT_DB_KEY;
+    let v = bincode::serialize(address).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Forget remote node state from a previous run, called on app startup
+pub fn reset() -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    for k in [
+        crate::REMOTE_NODE_LIST_DB_KEY,
+        crate::REMOTE_NODE_CURRENT_DB_KEY,
+    ] {
+        db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())
+            .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    }
+    Ok(())
+}
+
+/// Fetch 'get_info' from a remote node over i2p and time it
+pub async fn probe(address: &String) -> RemoteNode {
+    let start = std::time::Instant::now();
+    let checked = chrono::offset::Utc::now().timestamp();
+    let timeout = std::time::Duration::from_secs(crate::REMOTE_NODE_PROBE_TIMEOUT_SECS);
+    let info = match tokio::time::timeout(timeout, monero::p_get_info_from(address)).await {
+        Ok(r) => r.unwrap_or_default().result,
+        Err(_) => {
+            warn!("remote node {} probe timed out", address);
+            Default::default()
+        }
+    };
+    let online = info.status == "OK" && !info.offline;
+    RemoteNode {
+        address: String::from(address),
+        checked,
+        height: info.height,
+        latency: if online {
+            start.elapsed().as_millis() as u64
+        } else {
+            0
+        },
+        online,
+        synchronized: info.synchronized,
+    }
+}
+
+fn get_best_height(nodes: &[RemoteNode]) -> u64 {
+    nodes.iter().filter(|n| n.online).map(|n| n.height).max().unwrap_or(0)
+}
+
+/// Online, synchronized and no more than the max lag behind the best height
+pub fn is_healthy(node: &RemoteNode, best_height: u64) -> bool {
+    node.online
+        && node.synchronized
+        && node.height + crate::REMOTE_NODE_MAX_LAG_BLOCKS >= best_height
+}
+
+/// The healthy node with the lowest latency
+pub fn select(nodes: &[RemoteNode]) -> Option<&RemoteNode> {
+    let best_height = get_best_height(nodes);
+    nodes
+        .iter()
+        .filter(|n| is_healthy(n, best_height))
+        .min_by_key(|n| n.latency)
+}
+
+/// Probe every remote node and switch monero-wallet-rpc over to the
+///
+/// healthiest one when the current node lags or disappears.
+pub async fn check() -> Result<(), NevekoError> {
+    let pool = get_pool();
+    let probes: Vec<_> = pool
+        .iter()
+        .map(|address| {
+            let address = String::from(address);
+            tokio::spawn(async move { probe(&address).await })
+        })
+        .collect();
+    let mut nodes: Vec<RemoteNode> = Vec::new();
+    for (address, p) in pool.iter().zip(probes) {
+        let node = match p.await {
+            Ok(n) => n,
+            Err(e) => {
+                error!("remote node {} probe failed: {}", address, e);
+                RemoteNode {
+                    address: String::from(address),
+                    checked: chrono::offset::Utc::now().timestamp(),
+                    ..Default::default()
+                }
+            }
+        };
+        info!(
+            "remote node {} online: {}, height: {}",
+            address, node.online, node.height
+        );
+        nodes.push(node);
+    }
+    save_all(&nodes)?;
+    let current = get_current().unwrap_or(pool.first().cloned().unwrap_or_default());
+    let best_height = get_best_height(&nodes);
+    if nodes
+        .iter()
+        .any(|n| n.address == current && is_healthy(n, best_height))
+    {
+        return Ok(());
+    }
+    let next = match select(&nodes) {
+        Some(n) => String::from(&n.address),
+        None => {
+            error!("no healthy remote node to fail over to");
+            return Ok(());
+        }
+    };
+    warn!("remote node {} lags or is offline, failing over to {}", current, next);
+    monero::set_daemon(&next).await.map_err(|e| {
+        error!("failed to fail over to {}: {}", next, e);
+        NevekoError::MoneroDaemon
+    })?;
+    set_current(&next)
+}
+
+/// Triggered on app startup in remote node mode, checks the pool on an interval
+pub async fn monitor() -> Result<(), NevekoError> {
+    for address in get_pool() {
+        if !address.contains(".i2p") {
+            warn!("invalid i2p monero remote node detected: {}", address);
+        }
+    }
+    let period = std::time::Duration::from_millis(crate::REMOTE_NODE_CHECK_INTERVAL as u64);
+    let mut tick = tokio::time::interval(period);
+    loop {
+        tick.tick().await;
+        debug!("running remote node monitor thread");
+        if let Err(e) = check().await {
+            error!("remote node check failed: {:?}", e);
+        }
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(address: &str, height: u64, latency: u64, online: bool) -> RemoteNode {
+        RemoteNode {
+            address: String::from(address),
+            height,
+            latency,
+            online,
+            synchronized: online,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_pool_test() {
+        let pool = parse_pool("http://a.b32.i2p", " http://b.b32.i2p,,http://a.b32.i2p");
+        assert_eq!(pool, vec!["http://a.b32.i2p", "http://b.b32.i2p"]);
+    }
+
+    #[test]
+    fn select_test() {
+        let nodes = vec![
+            node("lagging", 90, 10, true),
+            node("slow", 100, 300, true),
+            node("fast", 99, 100, true),
+            node("offline", 0, 0, false),
+        ];
+        assert_eq!(select(&nodes).map(|n| n.address.as_str()), Some("fast"));
+        assert!(!is_healthy(&nodes[0], 100));
+        assert!(select(&[node("offline", 0, 0, false)]).is_none());
+    }
+}
@@ -3,7 +3,9 @@
 use std::error::Error;
 
 use crate::{
+    account,
     contact,
+    credit,
     db::{
         self,
         DATABASE_LOCK,
@@ -14,6 +16,7 @@ use crate::{
     monero,
     neveko25519,
     order,
+    peer,
     product,
     reqres,
     utils,
@@ -48,18 +51,43 @@ impl StatusType {
     }
 }
 
+/// Actions a customer must sign a challenge for
+#[derive(Debug, PartialEq)]
+pub enum ChallengeAction {
+    Cancel,
+    Retrieve,
+}
+
+impl ChallengeAction {
+    pub fn value(&self) -> String {
+        match *self {
+            ChallengeAction::Cancel => String::from("cancel"),
+            ChallengeAction::Retrieve => String::from("retrieve"),
+        }
+    }
+    pub fn from_value(value: &str) -> Option<ChallengeAction> {
+        match value {
+            "cancel" => Some(ChallengeAction::Cancel),
+            "retrieve" => Some(ChallengeAction::Retrieve),
+            _ => None,
+        }
+    }
+}
+
 /// Create a intial order
 pub async fn create(j_order: Json<reqres::OrderRequest>) -> Result<Order, NevekoError> {
     info!("creating order");
+    utils::check_spend_keys()?;
     let wallet_name = String::from(crate::APP_NAME);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
     let ts = chrono::offset::Utc::now().timestamp();
     let orid: String = format!("{}{}", crate::ORDER_DB_KEY, utils::generate_rnd());
-    let r_subaddress = monero::create_address().await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
-    let subaddress = r_subaddress.result.address;
+    let r_subaddress =
+        account::create_address(&wallet, account::AccountPurpose::Market, &orid).await;
+    drop(wallet);
+    let subaddress = r_subaddress?.address;
     let new_order = Order {
         orid: String::from(&orid),
         cid: String::from(&j_order.cid),
@@ -73,13 +101,10 @@ pub async fn create(j_order: Json<reqres::OrderRequest>) -> Result<Order, Neveko
     };
     debug!("insert order: {:?}", new_order);
     let order_wallet_password = String::new();
-    let m_wallet = monero::create_wallet(&orid, &order_wallet_password).await;
-    if !m_wallet {
-        error!("error creating msig wallet for order {}", &orid);
-        monero::close_wallet(&orid, &wallet_password).await;
+    if let Err(e) = monero::create_wallet(&orid, &order_wallet_password).await {
+        error!("error creating msig wallet for order {}: {}", &orid, e);
         return Err(NevekoError::Order);
     }
-    monero::close_wallet(&orid, &order_wallet_password).await;
     debug!("insert order: {:?}", &new_order);
     let db = &DATABASE_LOCK;
     // inject adjudicator separately, modifying the order model is mendokusai
@@ -281,31 +306,133 @@ pub async fn sign_and_submit_multisig(
     tx_data_hex: &String,
 ) -> reqres::XmrRpcSubmitMultisigResponse {
     info!("signing and submitting multisig");
+    if utils::check_spend_keys().is_err() {
+        return Default::default();
+    }
     let wallet_password = String::new();
-    monero::open_wallet(orid, &wallet_password).await;
+    let wallet = match monero::open_wallet(orid, &wallet_password).await {
+        Ok(w) => w,
+        Err(e) => {
+            error!("failed to open wallet for order {}: {}", orid, e);
+            return Default::default();
+        }
+    };
     let r_sign: reqres::XmrRpcSignMultisigResponse =
-        monero::sign_multisig(String::from(tx_data_hex)).await;
+        match monero::sign_multisig(&wallet, String::from(tx_data_hex)).await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to sign multisig for order {}: {}", orid, e);
+                return Default::default();
+            }
+        };
     let r_submit: reqres::XmrRpcSubmitMultisigResponse =
-        monero::submit_multisig(r_sign.result.tx_data_hex).await;
-    monero::close_wallet(orid, &wallet_password).await;
+        match monero::submit_multisig(&wallet, r_sign.result.tx_data_hex).await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to submit multisig for order {}: {}", orid, e);
+                return Default::default();
+            }
+        };
+    drop(wallet);
     if r_submit.result.tx_hash_list.is_empty() {
         error!("unable to submit payment for order: {}", orid);
     }
     r_submit
 }
 
-/// In order for the order (...ha) to only be accessed by the customer
+/// Issue a challenge to a peer asking for one, which must be the
 ///
-/// they must sign the order id with their NEVEKO wallet instance. This means
+/// customer of the order authenticated by the address book.
+pub fn issue_challenge(
+    requester: &peer::Peer,
+    orid: &String,
+    action: &ChallengeAction,
+) -> Result<OrderChallenge, NevekoError> {
+    let m_order: Order = find(orid).map_err(|_| NevekoError::Order)?;
+    if !requester.is_contact || m_order.cid != requester.destination {
+        error!("{} is not the customer of order: {}", &requester.destination, orid);
+        return Err(NevekoError::Order);
+    }
+    create_challenge(orid, action)
+}
+
+/// Issue a nonce the customer signs along with the order id and action.
 ///
-/// that the adjudicator can see order id for disputes without being able to
-/// access
+/// Only the latest challenge per order and action is honored.
+pub fn create_challenge(
+    orid: &String,
+    action: &ChallengeAction,
+) -> Result<OrderChallenge, NevekoError> {
+    let m_order: Order = find(orid).map_err(|_| NevekoError::Order)?;
+    if m_order.orid.is_empty() {
+        return Err(NevekoError::Order);
+    }
+    let challenge = OrderChallenge {
+        orid: String::from(orid),
+        action: action.value(),
+        nonce: utils::generate_rnd(),
+        created: chrono::offset::Utc::now().timestamp(),
+    };
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}-{}", crate::ORDER_CHALLENGE_DB_KEY, action.value(), orid);
+    let v = bincode::serialize(&challenge).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(challenge)
+}
+
+/// The data signed by the customer's NEVEKO wallet
+pub fn get_challenge_data(challenge: &OrderChallenge) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        challenge.orid, challenge.action, challenge.nonce, challenge.created
+    )
+}
+
+fn is_challenge_expired(created: i64, now: i64) -> bool {
+    now - created >= crate::ORDER_CHALLENGE_EXPIRATION_SECS || created > now
+}
+
+/// Look up and spend the challenge. A wrong nonce leaves it in place,
 ///
-/// the details of said order.
-pub async fn secure_retrieval(orid: &String, signature: &String) -> Result<Order, NevekoError> {
-    info!("secure order retrieval for {}", orid);
+/// only a matching one removes it so it can never be replayed.
+fn redeem_challenge(
+    orid: &String,
+    action: &ChallengeAction,
+    nonce: &String,
+) -> Result<OrderChallenge, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}-{}", crate::ORDER_CHALLENGE_DB_KEY, action.value(), orid);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        error!("no challenge issued for order: {}", orid);
+        return Err(NevekoError::Order);
+    }
+    let challenge: OrderChallenge = bincode::deserialize(&r[..]).unwrap_or_default();
+    if challenge.nonce != *nonce {
+        error!("invalid challenge for order: {}", orid);
+        return Err(NevekoError::Order);
+    }
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let now = chrono::offset::Utc::now().timestamp();
+    if is_challenge_expired(challenge.created, now) {
+        error!("expired challenge for order: {}", orid);
+        return Err(NevekoError::Order);
+    }
+    Ok(challenge)
+}
+
+/// Verify the challenge was signed by the customer's NEVEKO wallet
+async fn verify_challenge(
+    m_order: &Order,
+    action: &ChallengeAction,
+    nonce: &String,
+    signature: &String,
+) -> Result<(), NevekoError> {
+    let challenge = redeem_challenge(&m_order.orid, action, nonce)?;
     // get customer address for NEVEKO NOT order wallet
-    let m_order: Order = find(orid).map_err(|_| NevekoError::Order)?;
     let mut xmr_address: String = String::new();
     let a_customers: Vec<Contact> = contact::find_all().map_err(|_| NevekoError::Contact)?;
     for customer in a_customers {
@@ -314,48 +441,51 @@ pub async fn secure_retrieval(orid: &String, signature: &String) -> Result<Order
             break;
         }
     }
-    // send address, orid and signature to verify()
-    let id: String = String::from(&m_order.orid);
+    // send address, challenge data and signature to verify()
+    let data: String = get_challenge_data(&challenge);
     let sig: String = String::from(signature);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
     let wallet_name = String::from(crate::APP_NAME);
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let is_valid_signature = monero::verify(xmr_address, id, sig).await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+    let is_valid_signature =
+        monero::verify(&wallet, xmr_address, data, sig).await.unwrap_or(false);
+    drop(wallet);
     if !is_valid_signature {
         return Err(NevekoError::Order);
     }
+    Ok(())
+}
+
+/// In order for the order (...ha) to only be accessed by the customer
+///
+/// they must sign a retrieval challenge with their NEVEKO wallet instance.
+///
+/// This means that the adjudicator can see order id for disputes without
+///
+/// being able to access the details of said order.
+pub async fn secure_retrieval(
+    orid: &String,
+    nonce: &String,
+    signature: &String,
+) -> Result<Order, NevekoError> {
+    info!("secure order retrieval for {}", orid);
+    let m_order: Order = find(orid).map_err(|_| NevekoError::Order)?;
+    verify_challenge(&m_order, &ChallengeAction::Retrieve, nonce, signature).await?;
     Ok(m_order)
 }
 
 /// In order for the order (...ha) to only be cancelled by the customer
 ///
-/// they must sign the order id with their NEVEKO wallet instance.
-pub async fn cancel_order(orid: &String, signature: &String) -> Result<Order, NevekoError> {
+/// they must sign a cancel challenge with their NEVEKO wallet instance.
+pub async fn cancel_order(
+    orid: &String,
+    nonce: &String,
+    signature: &String,
+) -> Result<Order, NevekoError> {
     info!("cancel order {}", orid);
-    // get customer address for NEVEKO NOT order wallet
     let mut m_order: Order = find(orid).map_err(|_| NevekoError::Order)?;
-    let mut xmr_address: String = String::new();
-    let a_customers: Vec<Contact> = contact::find_all().map_err(|_| NevekoError::Contact)?;
-    for customer in a_customers {
-        if customer.i2p_address == m_order.cid {
-            xmr_address = customer.xmr_address;
-            break;
-        }
-    }
-    // send address, orid and signature to verify()
-    let id: String = String::from(&m_order.orid);
-    let sig: String = String::from(signature);
-    let wallet_password =
-        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    let wallet_name = String::from(crate::APP_NAME);
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let is_valid_signature = monero::verify(xmr_address, id, sig).await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
-    if !is_valid_signature {
-        return Err(NevekoError::Order);
-    }
+    verify_challenge(&m_order, &ChallengeAction::Cancel, nonce, signature).await?;
     // update the order status and send to customer
     m_order.status = order::StatusType::Cancelled.value();
     order::modify(Json(m_order))?;
@@ -381,10 +511,10 @@ pub async fn validate_order_for_ship(
     let price = m_product.price;
     let total = price * m_order.quantity;
     let wallet_password = String::new();
-    monero::open_wallet(orid, &wallet_password).await;
+    let wallet = monero::open_wallet(orid, &wallet_password).await?;
     // check balance and unlock_time
-    let r_balance = monero::get_balance().await;
-    monero::close_wallet(orid, &wallet_password).await;
+    let r_balance = monero::get_balance(&wallet).await?;
+    drop(wallet);
     // update the order status to multisig complete
     let ready_to_ship: bool = r_balance.result.balance >= total as u128
         && r_balance.result.blocks_to_unlock < monero::LockTimeLimit::Blocks.value();
@@ -423,6 +553,8 @@ pub async fn trigger_nasr(
             &customer, vendor, orid
         ))
         .header("proof", jwp)
+        .header("credit", credit::find_token(customer))
+        .headers(peer::get_auth_headers(customer).await)
         .send()
         .await
     {
@@ -468,11 +600,11 @@ pub async fn upload_delivery_info(
     }
     // get draft payment txset
     let wallet_password = String::new();
-    monero::open_wallet(orid, &wallet_password).await;
-    monero::refresh().await;
+    let wallet = monero::open_wallet(orid, &wallet_password).await?;
+    monero::refresh(&wallet).await?;
     let sweep: reqres::XmrRpcSweepAllResponse =
-        monero::sweep_all(String::from(&lookup.subaddress)).await;
-    monero::close_wallet(orid, &wallet_password).await;
+        monero::sweep_all(&wallet, String::from(&lookup.subaddress), 0).await?;
+    drop(wallet);
     if sweep.result.multisig_txset.is_empty() {
         error!("unable to create draft txset");
         return Err(NevekoError::MoneroRpc);
@@ -526,31 +658,29 @@ pub async fn finalize_order(orid: &String) -> Result<reqres::FinalizeOrderRespon
     }
     // get draft payment txset
     let wallet_password = String::new();
-    monero::open_wallet(orid, &wallet_password).await;
-    monero::refresh().await;
+    let wallet = monero::open_wallet(orid, &wallet_password).await?;
+    monero::refresh(&wallet).await?;
     let address: String = String::from(&m_order.subaddress);
-    let m_describe = monero::describe_transfer(&m_order.vend_msig_txset).await;
+    let m_describe = monero::describe_transfer(&wallet, &m_order.vend_msig_txset).await?;
     let check_destination: reqres::Destination = reqres::Destination {
         address,
         ..Default::default()
     };
-    let valid = m_describe.result.desc[0]
-        .recepients
-        .contains(&check_destination)
-        && m_describe.result.desc[0].unlock_time < monero::LockTimeLimit::Blocks.value();
+    let valid = m_describe.result.desc.first().is_some_and(|d| {
+        d.recepients.contains(&check_destination)
+            && d.unlock_time < monero::LockTimeLimit::Blocks.value()
+    });
     if !valid {
-        monero::close_wallet(orid, &wallet_password).await;
         error!("invalid txset");
         return Err(NevekoError::MoneroRpc);
     }
     // verify order wallet has been swept clean
-    let balance = monero::get_balance().await;
+    let balance = monero::get_balance(&wallet).await?;
+    drop(wallet);
     if balance.result.unlocked_balance != 0 {
-        monero::close_wallet(orid, &wallet_password).await;
         error!("order wallet not swept");
         return Err(NevekoError::MoneroRpc);
     }
-    monero::close_wallet(orid, &wallet_password).await;
     m_order.status = order::StatusType::Delivered.value();
     order::modify(Json(m_order))?;
     Ok(reqres::FinalizeOrderResponse {
@@ -578,6 +708,8 @@ pub async fn transmit_finalize_request(
     match client?
         .post(format!("http://{}/market/order/finalize/{}", contact, orid))
         .header("proof", jwp)
+        .header("credit", credit::find_token(contact))
+        .headers(peer::get_auth_headers(contact).await)
         .send()
         .await
     {
@@ -656,6 +788,8 @@ pub async fn transmit_order_request(
     match client?
         .post(format!("http://{}/market/order/create", contact))
         .header("proof", jwp)
+        .header("credit", credit::find_token(&contact))
+        .headers(peer::get_auth_headers(&contact).await)
         .json(&request)
         .send()
         .await
@@ -688,6 +822,8 @@ pub async fn transmit_ship_request(
     match client?
         .post(format!("http://{}/market/ship/{}", contact, orid))
         .header("proof", jwp)
+        .header("credit", credit::find_token(contact))
+        .headers(peer::get_auth_headers(contact).await)
         .send()
         .await
     {
@@ -706,15 +842,84 @@ pub async fn transmit_ship_request(
     }
 }
 
-/// Executes GET /order/retrieve/orid/signature returning the order information
+/// Executes GET /order/challenge/orid/action returning a nonce to sign
 ///
-/// from the vendor.
+/// for order retrieval or cancellation.
+pub async fn transmit_challenge_request(
+    contact: &String,
+    jwp: &String,
+    orid: &String,
+    action: &ChallengeAction,
+) -> Result<OrderChallenge, Box<dyn Error>> {
+    info!("executing transmit_challenge_request");
+    let host = utils::get_i2p_http_proxy();
+    let proxy = reqwest::Proxy::http(&host)?;
+    let client = reqwest::Client::builder().proxy(proxy).build();
+    match client?
+        .get(format!(
+            "http://{}/market/order/challenge/{}/{}",
+            contact,
+            orid,
+            action.value()
+        ))
+        .header("proof", jwp)
+        .header("credit", credit::find_token(contact))
+        .headers(peer::get_auth_headers(contact).await)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            let res = response.json::<OrderChallenge>().await;
+            debug!("order challenge response: {:?}", res);
+            match res {
+                Ok(r) => Ok(r),
+                _ => Ok(Default::default()),
+            }
+        }
+        Err(e) => {
+            error!("failed to request order challenge due to: {:?}", e);
+            Ok(Default::default())
+        }
+    }
+}
+
+/// Request a challenge from the vendor and sign it with the NEVEKO wallet
+async fn sign_challenge(
+    contact: &String,
+    jwp: &String,
+    orid: &String,
+    action: &ChallengeAction,
+) -> Result<(String, String), NevekoError> {
+    utils::check_spend_keys()?;
+    let challenge = transmit_challenge_request(contact, jwp, orid, action)
+        .await
+        .map_err(|_| NevekoError::Order)?;
+    if challenge.nonce.is_empty()
+        || challenge.orid != *orid
+        || challenge.action != action.value()
+    {
+        error!("invalid {} challenge for order: {}", action.value(), orid);
+        return Err(NevekoError::Order);
+    }
+    let data = get_challenge_data(&challenge);
+    let wallet_password =
+        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+    let wallet = monero::open_wallet(&String::from(crate::APP_NAME), &wallet_password).await?;
+    let pre_sign = monero::sign(&wallet, data).await?;
+    drop(wallet);
+    Ok((challenge.nonce, pre_sign.result.signature))
+}
+
+/// Executes GET /order/retrieve/orid/nonce/signature returning the order
 ///
-/// see `fn secure_order_retrieval()`
+/// information from the vendor.
+///
+/// see `fn secure_retrieval()`
 pub async fn transmit_sor_request(
     contact: &String,
     jwp: &String,
     orid: &String,
+    nonce: &String,
     signature: &String,
 ) -> Result<Order, Box<dyn Error>> {
     info!("executing transmit_sor_request");
@@ -723,10 +928,12 @@ pub async fn transmit_sor_request(
     let client = reqwest::Client::builder().proxy(proxy).build();
     match client?
         .get(format!(
-            "http://{}/market/order/retrieve/{}/{}",
-            contact, orid, signature
+            "http://{}/market/order/retrieve/{}/{}/{}",
+            contact, orid, nonce, signature
         ))
         .header("proof", jwp)
+        .header("credit", credit::find_token(contact))
+        .headers(peer::get_auth_headers(contact).await)
         .send()
         .await
     {
@@ -754,13 +961,8 @@ pub async fn trigger_ship_request(
     orid: &String,
 ) -> Result<Order, NevekoError> {
     info!("executing trigger_ship_request");
-    let data = String::from(orid);
-    let wallet_password =
-        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&String::from(crate::APP_NAME), &wallet_password).await;
-    let pre_sign = monero::sign(data).await;
-    monero::close_wallet(&String::from(crate::APP_NAME), &wallet_password).await;
-    let order = transmit_sor_request(contact, jwp, orid, &pre_sign.result.signature).await;
+    let (nonce, signature) = sign_challenge(contact, jwp, orid, &ChallengeAction::Retrieve).await?;
+    let order = transmit_sor_request(contact, jwp, orid, &nonce, &signature).await;
     // cache order request to db
     if order.is_err() {
         log::error!("failed to trigger shipping request");
@@ -780,13 +982,8 @@ pub async fn trigger_cancel_request(
     orid: &String,
 ) -> Result<Order, NevekoError> {
     info!("executing trigger_cancel_request");
-    let data = String::from(orid);
-    let wallet_password =
-        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&String::from(crate::APP_NAME), &wallet_password).await;
-    let pre_sign = monero::sign(data).await;
-    monero::close_wallet(&String::from(crate::APP_NAME), &wallet_password).await;
-    let order = transmit_cancel_request(contact, jwp, orid, &pre_sign.result.signature).await;
+    let (nonce, signature) = sign_challenge(contact, jwp, orid, &ChallengeAction::Cancel).await?;
+    let order = transmit_cancel_request(contact, jwp, orid, &nonce, &signature).await;
     // cache order request to db
     if order.is_err() {
         log::error!("failed to trigger cancel request");
@@ -829,7 +1026,7 @@ pub async fn d_trigger_ship_request(contact: &String, orid: &String) -> Result<O
     Ok(trigger)
 }
 
-/// Executes POST /order/cancel/orid/signature
+/// Executes POST /order/cancel/orid/nonce/signature
 ///
 /// cancelling the order on the vendor side.
 ///
@@ -840,6 +1037,7 @@ pub async fn transmit_cancel_request(
     contact: &String,
     jwp: &String,
     orid: &String,
+    nonce: &String,
     signature: &String,
 ) -> Result<Order, Box<dyn Error>> {
     info!("executing transmit_cancel_request");
@@ -848,10 +1046,12 @@ pub async fn transmit_cancel_request(
     let client = reqwest::Client::builder().proxy(proxy).build();
     match client?
         .post(format!(
-            "http://{}/market/order/cancel/{}/{}",
-            contact, orid, signature
+            "http://{}/market/order/cancel/{}/{}/{}",
+            contact, orid, nonce, signature
         ))
         .header("proof", jwp)
+        .header("credit", credit::find_token(contact))
+        .headers(peer::get_auth_headers(contact).await)
         .send()
         .await
     {
@@ -896,9 +1096,52 @@ pub async fn d_trigger_cancel_request(
 
 pub async fn init_adjudicator_wallet(orid: &String) {
     let password = String::new();
-    let m_wallet = monero::create_wallet(orid, &password).await;
-    if !m_wallet {
-        log::error!("failed to create adjudicator wallet");
+    if let Err(e) = monero::create_wallet(orid, &password).await {
+        log::error!("failed to create adjudicator wallet: {}", e);
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_test() {
+        let challenge = OrderChallenge {
+            orid: String::from("o123"),
+            action: ChallengeAction::Cancel.value(),
+            nonce: String::from("n456"),
+            created: 100,
+        };
+        assert_eq!(get_challenge_data(&challenge), "o123:cancel:n456:100");
+        assert!(!is_challenge_expired(100, 100 + crate::ORDER_CHALLENGE_EXPIRATION_SECS - 1));
+        assert!(is_challenge_expired(100, 100 + crate::ORDER_CHALLENGE_EXPIRATION_SECS));
+        assert!(is_challenge_expired(100, 50));
+        assert_eq!(ChallengeAction::from_value("retrieve"), Some(ChallengeAction::Retrieve));
+        assert_eq!(ChallengeAction::from_value("ship"), None);
+    }
+
+    #[test]
+    fn redeem_challenge_test() {
+        let orid = String::from("redeem-challenge-test-orid");
+        let action = ChallengeAction::Retrieve;
+        let challenge = OrderChallenge {
+            orid: String::from(&orid),
+            action: action.value(),
+            nonce: String::from("n456"),
+            created: chrono::offset::Utc::now().timestamp(),
+        };
+        let db = &DATABASE_LOCK;
+        let k = format!("{}-{}-{}", crate::ORDER_CHALLENGE_DB_KEY, action.value(), &orid);
+        let v = bincode::serialize(&challenge).unwrap_or_default();
+        db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v).expect("write challenge");
+        // a wrong nonce doesn't burn the challenge
+        assert!(redeem_challenge(&orid, &action, &String::from("bogus")).is_err());
+        assert!(redeem_challenge(&orid, &action, &challenge.nonce).is_ok());
+        // spent
+        assert!(redeem_challenge(&orid, &action, &challenge.nonce).is_err());
     }
-    monero::close_wallet(orid, &password).await;
 }
@@ -0,0 +1,236 @@
+//! Authenticated i2p destinations of peers calling our routes
+
+use crate::{
+    contact,
+    i2p,
+    neveko25519,
+    utils,
+};
+use hmac::{
+    Hmac,
+    Mac,
+};
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    error,
+};
+use reqwest::header::{
+    HeaderMap,
+    HeaderValue,
+};
+use rocket::{
+    http::Status,
+    outcome::Outcome,
+    request,
+    request::FromRequest,
+    Request,
+};
+use sha2::Sha512;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+};
+
+/// i2p destination the requester claims
+const DESTINATION_HEADER: &str = "destination";
+/// Hex encoded NMPK of the requester
+const NMPK_HEADER: &str = "nmpk";
+/// UNIX timestamp the request was signed at
+const TIMESTAMP_HEADER: &str = "timestamp";
+/// Random value, each one is accepted once
+const NONCE_HEADER: &str = "nonce";
+/// HMAC of the destination, timestamp and nonce
+const AUTH_HEADER: &str = "auth";
+
+lazy_static! {
+    /// Nonces of accepted requests by their timestamp, kept while the
+    ///
+    /// timestamp is inside the auth window.
+    static ref NONCES: Mutex<HashMap<String, i64>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PeerError {
+    /// Auth headers are missing or malformed
+    Missing,
+    /// Timestamp is outside `PEER_AUTH_WINDOW_SECS`
+    Expired,
+    /// Nonce was used before
+    Replay,
+    /// The key or the HMAC doesn't hold up
+    Invalid,
+}
+
+/// A requester that proved it holds the NMSK of `nmpk`.
+///
+/// Anyone can make up a key for any destination, so `destination` is only
+///
+/// authenticated when `is_contact` is set. The NMPK stored for a contact
+///
+/// was fetched from their destination over i2p when they were added.
+#[derive(Clone, Debug, Default)]
+pub struct Peer {
+    pub destination: String,
+    pub nmpk: String,
+    pub is_contact: bool,
+}
+
+/// HMAC keyed with the shared secret of both message keys
+fn get_mac(shared_secret: &str, destination: &str, timestamp: i64, nonce: &str) -> Hmac<Sha512> {
+    let mut mac: Hmac<Sha512> = Hmac::new_from_slice(shared_secret.as_bytes()).expect("hash");
+    mac.update(format!("{}:{}:{}", destination, timestamp, nonce).as_bytes());
+    mac
+}
+
+fn is_in_window(timestamp: i64, now: i64) -> bool {
+    (now - timestamp).abs() <= crate::PEER_AUTH_WINDOW_SECS
+}
+
+/// Remember a nonce, false when it was already used
+fn use_nonce(nonces: &mut HashMap<String, i64>, nonce: &str, timestamp: i64, now: i64) -> bool {
+    nonces.retain(|_, t| is_in_window(*t, now));
+    if nonces.contains_key(nonce) {
+        return false;
+    }
+    nonces.insert(String::from(nonce), timestamp);
+    true
+}
+
+fn insert_header(headers: &mut HeaderMap, name: &'static str, value: &str) {
+    match HeaderValue::from_str(value) {
+        Ok(v) => {
+            headers.insert(name, v);
+        }
+        Err(_) => error!("invalid {} header", name),
+    }
+}
+
+/// Headers for requests to a contact. Besides our destination they prove
+///
+/// we hold the NMSK of the NMPK the contact has for us. Peers that are
+///
+/// not in the address book only get the destination.
+pub async fn get_auth_headers(contact: &String) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let destination = i2p::get_destination(i2p::ServerTunnelType::App).unwrap_or_default();
+    insert_header(&mut headers, DESTINATION_HEADER, &destination);
+    let contact_nmpk = contact::find_by_i2p_address(contact)
+        .map(|c| c.nmpk)
+        .unwrap_or_default();
+    if contact_nmpk.is_empty() {
+        debug!("{} is not a contact, sending destination only", contact);
+        return headers;
+    }
+    let shared_secret = match neveko25519::get_shared_secret(&contact_nmpk).await {
+        Some(s) => s,
+        None => {
+            error!("unable to authenticate to: {}", contact);
+            return headers;
+        }
+    };
+    let timestamp = chrono::offset::Utc::now().timestamp();
+    let nonce = utils::generate_rnd();
+    let mac = get_mac(&shared_secret, &destination, timestamp, &nonce);
+    let auth = hex::encode(mac.finalize().into_bytes());
+    let nmpk = utils::get_nmpk().unwrap_or_default();
+    insert_header(&mut headers, NMPK_HEADER, &nmpk);
+    insert_header(&mut headers, TIMESTAMP_HEADER, &format!("{}", timestamp));
+    insert_header(&mut headers, NONCE_HEADER, &nonce);
+    insert_header(&mut headers, AUTH_HEADER, &auth);
+    headers
+}
+
+/// Check the auth headers set by `get_auth_headers`. Guards of the same
+///
+/// request share the result since each nonce is only accepted once.
+pub async fn authenticate(request: &Request<'_>) -> Result<Peer, PeerError> {
+    request.local_cache_async(verify(request)).await.clone()
+}
+
+async fn verify(request: &Request<'_>) -> Result<Peer, PeerError> {
+    let get = |name: &str| String::from(request.headers().get_one(name).unwrap_or_default());
+    let destination = get(DESTINATION_HEADER);
+    let nmpk = get(NMPK_HEADER);
+    let nonce = get(NONCE_HEADER);
+    let auth = hex::decode(get(AUTH_HEADER)).unwrap_or_default();
+    let timestamp = get(TIMESTAMP_HEADER).parse::<i64>().unwrap_or(0);
+    if destination.is_empty() || nmpk.is_empty() || nonce.is_empty() || auth.is_empty() {
+        return Err(PeerError::Missing);
+    }
+    let now = chrono::offset::Utc::now().timestamp();
+    if !is_in_window(timestamp, now) {
+        error!("peer auth from {} expired", &destination);
+        return Err(PeerError::Expired);
+    }
+    // contacts must use the key we have for them
+    let contact = contact::find_by_i2p_address(&destination).ok();
+    if contact.as_ref().is_some_and(|c| c.nmpk != nmpk) {
+        error!("{} signed with a key that is not theirs", &destination);
+        return Err(PeerError::Invalid);
+    }
+    let shared_secret = neveko25519::get_shared_secret(&nmpk)
+        .await
+        .ok_or(PeerError::Invalid)?;
+    get_mac(&shared_secret, &destination, timestamp, &nonce)
+        .verify_slice(&auth)
+        .map_err(|_| PeerError::Invalid)?;
+    let mut nonces = NONCES.lock().unwrap_or_else(|e| e.into_inner());
+    if !use_nonce(&mut nonces, &nonce, timestamp, now) {
+        error!("replayed request from: {}", &destination);
+        return Err(PeerError::Replay);
+    }
+    Ok(Peer {
+        destination,
+        nmpk,
+        is_contact: contact.is_some(),
+    })
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Peer {
+    type Error = PeerError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match authenticate(request).await {
+            Ok(peer) => Outcome::Success(peer),
+            Err(e) => Outcome::Error((Status::Unauthorized, e)),
+        }
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_mac_test() {
+        let auth = get_mac("secret", "a.b32.i2p", 1000, "n1").finalize().into_bytes();
+        assert!(get_mac("secret", "a.b32.i2p", 1000, "n1")
+            .verify_slice(&auth)
+            .is_ok());
+        // a shared mac doesn't authenticate another destination or secret
+        assert!(get_mac("secret", "b.b32.i2p", 1000, "n1")
+            .verify_slice(&auth)
+            .is_err());
+        assert!(get_mac("other", "a.b32.i2p", 1000, "n1")
+            .verify_slice(&auth)
+            .is_err());
+    }
+
+    #[test]
+    fn use_nonce_test() {
+        let window = crate::PEER_AUTH_WINDOW_SECS;
+        let mut nonces = HashMap::new();
+        assert!(use_nonce(&mut nonces, "n1", 1000, 1000));
+        assert!(!use_nonce(&mut nonces, "n1", 1000, 1001));
+        assert!(use_nonce(&mut nonces, "n2", 1000, 1001));
+        // expired nonces are dropped, the window check refuses their requests
+        assert!(use_nonce(&mut nonces, "n3", 1000 + window + 1, 1000 + window + 1));
+        assert!(!nonces.contains_key("n1"));
+        assert!(!is_in_window(1000, 1000 + window + 1));
+    }
+}
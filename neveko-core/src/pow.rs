@@ -0,0 +1,279 @@
+//! Hashcash-style proof-of-work admission for low-privilege scopes
+
+use crate::{
+    args,
+    contact,
+    error::NevekoError,
+    i2p,
+    models::*,
+    peer,
+    proof::{
+        PaymentProofError,
+        Scope,
+    },
+    reqres,
+    utils,
+};
+use clap::Parser;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    error,
+    info,
+};
+use sha2::{
+    Digest,
+    Sha512,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::Mutex,
+};
+
+/// Challenges are short-lived and only kept in memory
+#[derive(Default)]
+struct Challenges {
+    /// unspent challenges by the NMPK of the peer they were issued to
+    issued: HashMap<String, PowChallenge>,
+    /// recent issues to peers outside the address book
+    requests: Vec<i64>,
+}
+
+lazy_static! {
+    static ref CHALLENGES: Mutex<Challenges> = Mutex::new(Default::default());
+}
+
+#[derive(Debug)]
+pub enum PowError {
+    RateLimited,
+}
+
+/// Leading zero bits a stamp must have, zero disables proof-of-work
+pub fn get_difficulty() -> u32 {
+    let args = args::Args::parse();
+    args.pow_difficulty
+}
+
+/// Scopes that can be entered with proof-of-work
+pub fn get_scopes() -> Vec<Scope> {
+    let args = args::Args::parse();
+    args.pow_scopes.split(",").filter_map(Scope::from_value).collect()
+}
+
+pub fn is_pow_scope(scope: &Scope) -> bool {
+    get_difficulty() > 0 && get_scopes().contains(scope)
+}
+
+/// Proof-of-work scopes refuse payment when set
+pub fn is_pow_only() -> bool {
+    let args = args::Args::parse();
+    args.pow_only
+}
+
+fn is_expired(challenge: &PowChallenge, now: i64) -> bool {
+    now - challenge.created >= crate::POW_CHALLENGE_EXPIRATION_SECS
+}
+
+/// Peers outside the address book can make up keys, so together they
+///
+/// share `POW_RATE_LIMIT` and `POW_OUTSTANDING_LIMIT`. Expired
+///
+/// challenges are dropped first.
+fn is_within_limits(challenges: &mut Challenges, now: i64) -> bool {
+    challenges.issued.retain(|_, c| !is_expired(c, now));
+    challenges.requests.retain(|r| now - r < crate::POW_RATE_WINDOW_SECS);
+    challenges.requests.len() < crate::POW_RATE_LIMIT
+        && challenges.issued.len() < crate::POW_OUTSTANDING_LIMIT
+}
+
+/// Issue a fresh challenge to a peer, replacing any unspent one.
+///
+/// Invoices don't carry challenges so requesting one doesn't use up
+///
+/// the invoice limits.
+pub fn issue(peer: &peer::Peer) -> Result<reqres::PowChallenge, PowError> {
+    let difficulty = get_difficulty();
+    if difficulty == 0 {
+        return Ok(Default::default());
+    }
+    let now = chrono::offset::Utc::now().timestamp();
+    let mut challenges = CHALLENGES.lock().unwrap_or_else(|e| e.into_inner());
+    if !peer.is_contact {
+        if !is_within_limits(&mut challenges, now) {
+            error!("pow challenge limit reached for peers outside the address book");
+            return Err(PowError::RateLimited);
+        }
+        challenges.requests.push(now);
+    }
+    let challenge = PowChallenge {
+        challenge: utils::generate_rnd(),
+        created: now,
+        difficulty,
+    };
+    let res = reqres::PowChallenge {
+        challenge: String::from(&challenge.challenge),
+        difficulty,
+    };
+    challenges.issued.insert(String::from(&peer.nmpk), challenge);
+    Ok(res)
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for b in hash {
+        if *b == 0 {
+            bits += 8;
+        } else {
+            bits += b.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Stamps are bound to the challenge and the destination presenting them
+pub fn is_solved(challenge: &str, destination: &str, nonce: &str, difficulty: u32) -> bool {
+    let mut hasher = Sha512::new();
+    hasher.update(format!("{}:{}:{}", challenge, destination, nonce));
+    leading_zero_bits(&hasher.finalize()[..]) >= difficulty
+}
+
+/// Search for a nonce that solves the challenge. CPU bound, run it off the
+///
+/// async runtime.
+pub fn solve(challenge: &str, destination: &str, difficulty: u32) -> String {
+    let mut nonce: u64 = 0;
+    while !is_solved(challenge, destination, &format!("{}", nonce), difficulty) {
+        nonce += 1;
+    }
+    debug!("solved pow challenge after {} attempts", nonce + 1);
+    format!("{}", nonce)
+}
+
+/// Verify a `challenge:nonce` stamp presented for a scope and spend the challenge
+pub fn validate(
+    stamp: &str,
+    requester: &peer::Peer,
+    required: Option<Scope>,
+) -> Result<(), PaymentProofError> {
+    let scope = required.ok_or(PaymentProofError::Scope)?;
+    if !is_pow_scope(&scope) {
+        error!("pow not accepted for scope: {}", scope.value());
+        return Err(PaymentProofError::Scope);
+    }
+    let destination = &requester.destination;
+    if contact::is_blocked(destination) {
+        return Err(PaymentProofError::Invalid);
+    }
+    let (challenge, nonce) = stamp.split_once(":").ok_or(PaymentProofError::Invalid)?;
+    let now = chrono::offset::Utc::now().timestamp();
+    let mut challenges = CHALLENGES.lock().unwrap_or_else(|e| e.into_inner());
+    let issued = challenges
+        .issued
+        .get(&requester.nmpk)
+        .ok_or(PaymentProofError::Missing)?;
+    if issued.challenge != challenge {
+        return Err(PaymentProofError::Invalid);
+    }
+    if is_expired(issued, now) {
+        return Err(PaymentProofError::Expired);
+    }
+    if !is_solved(challenge, destination, nonce, issued.difficulty) {
+        error!("unsolved pow stamp from: {}", destination);
+        return Err(PaymentProofError::Invalid);
+    }
+    challenges.issued.remove(&requester.nmpk);
+    Ok(())
+}
+
+/// Request a proof-of-work challenge from a contact
+async fn request_challenge(contact: &String) -> Result<reqres::PowChallenge, Box<dyn Error>> {
+    let host = utils::get_i2p_http_proxy();
+    let proxy = reqwest::Proxy::http(&host)?;
+    let client = reqwest::Client::builder().proxy(proxy).build();
+    match client?
+        .get(format!("http://{}/pow", contact))
+        .headers(peer::get_auth_headers(contact).await)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            let res = response.json::<reqres::PowChallenge>().await;
+            debug!("pow challenge response: {:?}", res);
+            match res {
+                Ok(r) => Ok(r),
+                _ => Ok(Default::default()),
+            }
+        }
+        Err(e) => {
+            error!("failed to request pow challenge due to: {:?}", e);
+            Ok(Default::default())
+        }
+    }
+}
+
+/// Fetch a challenge from a contact and solve it for a single call
+pub async fn request_stamp(contact: &String) -> Result<String, NevekoError> {
+    let res = request_challenge(contact)
+        .await
+        .map_err(|_| NevekoError::Contact)?;
+    if res.challenge.is_empty() {
+        info!("{} does not accept proof-of-work", contact);
+        return Err(NevekoError::Contact);
+    }
+    if res.difficulty > crate::POW_MAX_DIFFICULTY {
+        error!("pow difficulty {} from {} is too high", res.difficulty, contact);
+        return Err(NevekoError::Contact);
+    }
+    let destination =
+        i2p::get_destination(i2p::ServerTunnelType::App).map_err(|_| NevekoError::I2P)?;
+    let challenge = String::from(&res.challenge);
+    let difficulty = res.difficulty;
+    let nonce = tokio::task::spawn_blocking(move || solve(&challenge, &destination, difficulty))
+        .await
+        .map_err(|_| NevekoError::Unknown)?;
+    Ok(format!("{}:{}", &res.challenge, nonce))
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leading_zero_bits_test() {
+        assert_eq!(leading_zero_bits(&[0, 0, 1]), 23);
+        assert_eq!(leading_zero_bits(&[0x10]), 3);
+        assert_eq!(leading_zero_bits(&[0xff, 0]), 0);
+    }
+
+    #[test]
+    fn solve_test() {
+        let nonce = solve("challenge", "test.b32.i2p", 8);
+        assert!(is_solved("challenge", "test.b32.i2p", &nonce, 8));
+    }
+
+    #[test]
+    fn is_within_limits_test() {
+        let mut challenges: Challenges = Default::default();
+        challenges.requests = vec![1000; crate::POW_RATE_LIMIT];
+        assert!(!is_within_limits(&mut challenges, 1000));
+        // requests leave the window
+        assert!(is_within_limits(&mut challenges, 1000 + crate::POW_RATE_WINDOW_SECS));
+        let challenge = PowChallenge {
+            created: 1000,
+            ..Default::default()
+        };
+        for i in 0..crate::POW_OUTSTANDING_LIMIT {
+            challenges.issued.insert(format!("{}", i), challenge.clone());
+        }
+        assert!(!is_within_limits(&mut challenges, 1001));
+        // expired challenges are dropped
+        let expired = 1000 + crate::POW_CHALLENGE_EXPIRATION_SECS;
+        assert!(is_within_limits(&mut challenges, expired));
+        assert!(challenges.issued.is_empty());
+    }
+}
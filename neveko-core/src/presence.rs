@@ -0,0 +1,209 @@
+//! Contact presence monitor and reachability history
+
+use crate::{
+    contact,
+    db::{
+        self,
+        DATABASE_LOCK,
+    },
+    error::NevekoError,
+    i2p,
+    models::*,
+    utils,
+};
+use kn0sys_lmdb_rs::MdbError;
+use log::{
+    debug,
+    error,
+    info,
+};
+use std::error::Error;
+
+/// Upper bound on the exponent used for retry backoff
+const MAX_BACKOFF_EXP: u32 = 5;
+
+fn get_key(i2p_address: &String) -> String {
+    format!("{}-{}", crate::PRESENCE_DB_KEY, i2p_address)
+}
+
+/// Lookup the reachability history of a contact.
+///
+/// Contacts that have never been probed return an empty history.
+pub fn find(i2p_address: &String) -> Result<Presence, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = get_key(i2p_address);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        debug!("no presence history for: {}", i2p_address);
+        return Ok(Presence {
+            i2p_address: String::from(i2p_address),
+            ..Default::default()
+        });
+    }
+    let result: Presence = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+/// Ping the unprotected i2p status route of a contact and time it.
+pub async fn probe(contact: &String) -> Result<Probe, Box<dyn Error>> {
+    let host = utils::get_i2p_http_proxy();
+    let proxy = reqwest::Proxy::http(&host)?;
+    let client = reqwest::Client::builder().proxy(proxy).build();
+    let start = std::time::Instant::now();
+    let created = chrono::offset::Utc::now().timestamp();
+    match client?
+        .get(format!("http://{}/i2p/status", contact))
+        .send()
+        .await
+    {
+        Ok(response) => {
+            let latency = start.elapsed().as_millis() as u64;
+            let res = response.json::<i2p::HttpProxyStatus>().await;
+            debug!("presence probe response: {:?}", res);
+            let online = res.map(|r| r.open).unwrap_or(false);
+            Ok(Probe {
+                created,
+                latency,
+                online,
+            })
+        }
+        Err(e) => {
+            error!("failed to probe contact presence due to: {:?}", e);
+            Ok(Probe {
+                created,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// Append a probe to the contact's history, trimming the oldest entries.
+pub fn record(i2p_address: &String, p: &Probe) -> Result<Presence, NevekoError> {
+    let mut presence = find(i2p_address)?;
+    presence.last_probe = p.created;
+    if p.online {
+        presence.last_seen = p.created;
+        presence.failures = 0;
+    } else {
+        presence.failures += 1;
+    }
+    presence.history.push(p.clone());
+    if presence.history.len() > crate::PRESENCE_HISTORY_LIMIT {
+        let overflow = presence.history.len() - crate::PRESENCE_HISTORY_LIMIT;
+        presence.history.drain(..overflow);
+    }
+    let db = &DATABASE_LOCK;
+    let k = get_key(i2p_address);
+    let v = bincode::serialize(&presence).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(presence)
+}
+
+/// Probe a contact and record the result. Returns true when reachable.
+pub async fn check(i2p_address: &String) -> Result<bool, NevekoError> {
+    let p = probe(i2p_address).await.map_err(|_| NevekoError::I2P)?;
+    record(i2p_address, &p)?;
+    Ok(p.online)
+}
+
+/// Remove the reachability history of a contact
+pub fn delete(i2p_address: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = get_key(i2p_address);
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Average latency in milliseconds of the successful probes
+pub fn average_latency(presence: &Presence) -> u64 {
+    let online: Vec<&Probe> = presence.history.iter().filter(|p| p.online).collect();
+    if online.is_empty() {
+        return 0;
+    }
+    online.iter().map(|p| p.latency).sum::<u64>() / online.len() as u64
+}
+
+/// Seconds to wait before contacting a peer after `failures` consecutive
+///
+/// failed probes. Doubles from the fts retry interval up to a fixed cap.
+pub fn retry_backoff(failures: u32) -> i64 {
+    if failures == 0 {
+        return 0;
+    }
+    let base = (crate::FTS_RETRY_INTERVAL / 1000) as i64;
+    base * 2i64.pow(std::cmp::min(failures - 1, MAX_BACKOFF_EXP))
+}
+
+/// Used by the fts thread to skip contacts that have been unreachable
+///
+/// recently instead of hammering them on every tick.
+pub fn is_retry_due(i2p_address: &String) -> bool {
+    let presence = find(i2p_address).unwrap_or_default();
+    let now = chrono::offset::Utc::now().timestamp();
+    presence.last_probe + retry_backoff(presence.failures) <= now
+}
+
+/// Triggered on app startup, probes every contact on an interval
+pub async fn monitor() -> Result<(), NevekoError> {
+    let period = std::time::Duration::from_millis(crate::PRESENCE_CHECK_INTERVAL as u64);
+    // first probe after one period, i2p is still warming up at startup
+    let mut tick = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    loop {
+        debug!("running presence monitor thread");
+        tick.tick().await;
+        let contacts = contact::find_all().unwrap_or_default();
+        for c in contacts {
+            if c.i2p_address.is_empty() {
+                continue;
+            }
+            let is_online = check(&c.i2p_address).await.unwrap_or(false);
+            info!("contact {} online: {}", &c.i2p_address, is_online);
+        }
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_latency_test() {
+        let presence = Presence {
+            history: vec![
+                Probe {
+                    created: 1,
+                    latency: 100,
+                    online: true,
+                },
+                Probe {
+                    created: 2,
+                    latency: 0,
+                    online: false,
+                },
+                Probe {
+                    created: 3,
+                    latency: 300,
+                    online: true,
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(average_latency(&presence), 200);
+        assert_eq!(average_latency(&Default::default()), 0);
+    }
+
+    #[test]
+    fn retry_backoff_test() {
+        let base = (crate::FTS_RETRY_INTERVAL / 1000) as i64;
+        assert_eq!(retry_backoff(0), 0);
+        assert_eq!(retry_backoff(1), base);
+        assert_eq!(retry_backoff(3), base * 4);
+        assert_eq!(retry_backoff(100), base * 32);
+    }
+}
@@ -1,12 +1,15 @@
 //! Marketplace products upload, modification, etc module
 
 use crate::{
+    credit,
     db::{
         self,
         DATABASE_LOCK,
     },
     error::NevekoError,
     models::*,
+    peer,
+    pow,
     utils,
 };
 use kn0sys_lmdb_rs::MdbError;
@@ -125,6 +128,14 @@ fn validate_product(p: &Json<Product>) -> bool {
         && p.image.len() < utils::image_limit()
 }
 
+/// Vendors may admit browsing without a jwp or credit with proof-of-work
+async fn get_pow_stamp(contact: &String, jwp: &String) -> String {
+    if !jwp.is_empty() || !credit::find_token(contact).is_empty() {
+        return String::new();
+    }
+    pow::request_stamp(contact).await.unwrap_or_default()
+}
+
 /// Send the request to vendor for the products available
 pub async fn get_vendor_products(
     contact: String,
@@ -133,9 +144,13 @@ pub async fn get_vendor_products(
     let host = utils::get_i2p_http_proxy();
     let proxy = reqwest::Proxy::http(&host)?;
     let client = reqwest::Client::builder().proxy(proxy).build();
+    let stamp = get_pow_stamp(&contact, &jwp).await;
     match client?
         .get(format!("http://{}/market/products", contact))
         .header("proof", jwp)
+        .header("credit", credit::find_token(&contact))
+        .header("pow", stamp)
+        .headers(peer::get_auth_headers(&contact).await)
         .send()
         .await
     {
@@ -163,9 +178,13 @@ pub async fn get_vendor_product(
     let host = utils::get_i2p_http_proxy();
     let proxy = reqwest::Proxy::http(&host)?;
     let client = reqwest::Client::builder().proxy(proxy).build();
+    let stamp = get_pow_stamp(&contact, &jwp).await;
     match client?
         .get(format!("http://{}/market/{}", contact, pid))
         .header("proof", jwp)
+        .header("credit", credit::find_token(&contact))
+        .header("pow", stamp)
+        .headers(peer::get_auth_headers(&contact).await)
         .send()
         .await
     {
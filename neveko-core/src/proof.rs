@@ -1,17 +1,29 @@
 //! External authorization module via JWPs
 
 use crate::{
+    account,
+    contact,
+    credit,
     db::{
         self,
         DATABASE_LOCK,
     },
     error::NevekoError,
+    i2p,
+    ledger,
+    models::{
+        IssuedInvoice,
+        Redemption,
+    },
     monero,
+    peer,
+    pow,
     reqres,
     utils,
 };
 use kn0sys_lmdb_rs::MdbError;
 use log::{
+    debug,
     error,
     info,
 };
@@ -29,15 +41,34 @@ use hmac::{
     Mac,
 };
 use jwt::*;
+use lazy_static::lazy_static;
 use serde::{
     Deserialize,
     Serialize,
 };
 use sha2::Sha512;
 use std::collections::BTreeMap;
+use tokio::sync::Mutex;
+
+lazy_static! {
+    /// Recent invoice requests from peers outside the address book. The lock
+    ///
+    /// also serializes `create_invoice` so concurrent requests can't get past
+    ///
+    /// the limits.
+    static ref STRANGER_INVOICE_REQUESTS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+}
 
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct TxProof {
+    /// i2p destination of the contact redeeming the proof. Only informative
+    ///
+    /// on `/prove`, the jwp is issued to the authenticated `peer::Peer`.
+    #[serde(default)]
+    pub destination: String,
+    /// comma separated scopes requested, empty for all scopes
+    #[serde(default)]
+    pub scope: String,
     pub subaddress: String,
     pub confirmations: u64,
     pub hash: String,
@@ -45,26 +76,333 @@ pub struct TxProof {
     pub signature: String,
 }
 
+/// Groups of routes a JWP grants access to
+#[derive(Debug, PartialEq)]
+pub enum Scope {
+    Message,
+    MarketRead,
+    MarketOrder,
+}
+
+impl Scope {
+    pub fn value(&self) -> String {
+        match *self {
+            Scope::Message => String::from("message"),
+            Scope::MarketRead => String::from("market-read"),
+            Scope::MarketOrder => String::from("market-order"),
+        }
+    }
+    pub fn from_value(v: &str) -> Option<Scope> {
+        match v {
+            "message" => Some(Scope::Message),
+            "market-read" => Some(Scope::MarketRead),
+            "market-order" => Some(Scope::MarketOrder),
+            _ => None,
+        }
+    }
+    pub fn all() -> Vec<Scope> {
+        vec![Scope::Message, Scope::MarketRead, Scope::MarketOrder]
+    }
+}
+
+/// Parse a comma separated list of scopes. Empty selects every scope.
+pub fn parse_scopes(s: &str) -> Vec<Scope> {
+    if s.is_empty() {
+        return Scope::all();
+    }
+    s.split(",").filter_map(Scope::from_value).collect()
+}
+
+/// Price and expiration of a scope from the command line args
+fn get_default_tier(scope: &Scope) -> reqres::Tier {
+    let (pay_threshold, conf_threshold, call_price) = match *scope {
+        Scope::Message => (
+            utils::get_payment_threshold(),
+            utils::get_conf_threshold(),
+            utils::get_message_call_price(),
+        ),
+        Scope::MarketRead => (
+            utils::get_market_read_payment_threshold(),
+            utils::get_market_read_conf_threshold(),
+            utils::get_market_read_call_price(),
+        ),
+        Scope::MarketOrder => (
+            utils::get_market_order_payment_threshold(),
+            utils::get_market_order_conf_threshold(),
+            utils::get_market_order_call_price(),
+        ),
+    };
+    reqres::Tier {
+        scope: scope.value(),
+        pay_threshold,
+        conf_threshold,
+        call_price,
+    }
+}
+
+/// Per-contact tier overrides. A `pay_threshold` of zero makes the
+///
+/// scope free for that contact.
+pub fn find_tier_overrides(destination: &String) -> Result<Vec<reqres::Tier>, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::TIER_OVERRIDE_DB_KEY, destination);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let result: Vec<reqres::Tier> = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+/// Set the tier overrides for a contact, unknown scopes are dropped
+pub fn write_tier_overrides(
+    destination: &String,
+    tiers: &[reqres::Tier],
+) -> Result<Vec<reqres::Tier>, NevekoError> {
+    let valid: Vec<reqres::Tier> = tiers
+        .iter()
+        .filter(|t| Scope::from_value(&t.scope).is_some())
+        .cloned()
+        .collect();
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::TIER_OVERRIDE_DB_KEY, destination);
+    let v = bincode::serialize(&valid).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(valid)
+}
+
+/// Remove the tier overrides for a contact
+pub fn delete_tier_overrides(destination: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::TIER_OVERRIDE_DB_KEY, destination);
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Tiers offered to a contact with their overrides applied
+pub fn get_tiers(destination: &String) -> Vec<reqres::Tier> {
+    let overrides = find_tier_overrides(destination).unwrap_or_default();
+    Scope::all()
+        .iter()
+        .map(|s| {
+            overrides
+                .iter()
+                .find(|o| o.scope == s.value())
+                .cloned()
+                .unwrap_or(get_default_tier(s))
+        })
+        .collect()
+}
+
+/// Total price and shortest expiration of the selected scopes
+pub fn get_price(tiers: &[reqres::Tier], scopes: &[Scope]) -> (u128, u64) {
+    let selected: Vec<&reqres::Tier> = tiers
+        .iter()
+        .filter(|t| scopes.iter().any(|s| s.value() == t.scope))
+        .collect();
+    let pay_threshold = selected.iter().map(|t| t.pay_threshold).sum();
+    let conf_threshold = selected.iter().map(|t| t.conf_threshold).min().unwrap_or(0);
+    (pay_threshold, conf_threshold)
+}
+
+/// Price debited from credit for a call in a scope
+pub fn get_call_price(tiers: &[reqres::Tier], scope: &Scope) -> u128 {
+    tiers
+        .iter()
+        .find(|t| t.scope == scope.value())
+        .map(|t| t.call_price)
+        .unwrap_or(0)
+}
+
+/// Scopes are free for contacts in the address book with a zero priced
+///
+/// override. The peer must have signed with the contact's message key,
+///
+/// a destination that is only claimed always pays.
+fn is_free(peer: &peer::Peer, scopes: &[Scope]) -> bool {
+    let (pay_threshold, _) = get_price(&get_tiers(&peer.destination), scopes);
+    pay_threshold == 0 && peer.is_contact
+}
+
+#[derive(Debug)]
+pub enum InvoiceError {
+    RateLimited,
+    Unavailable,
+}
+
+/// Lookup the invoice issued to a destination
+pub fn find_issued_invoice(destination: &String) -> Result<IssuedInvoice, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::INVOICE_DB_KEY, destination);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        return Err(NevekoError::Database(MdbError::NotFound));
+    }
+    let result: IssuedInvoice = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+fn write_issued_invoice(i: &IssuedInvoice) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::INVOICE_DB_KEY, &i.destination);
+    let v = bincode::serialize(i).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+fn delete_issued_invoice(destination: &str) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::INVOICE_DB_KEY, destination);
+    db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Requests that still count against the rate limit
+fn get_recent_requests(requests: &[i64], now: i64) -> Vec<i64> {
+    requests
+        .iter()
+        .filter(|r| now - **r < crate::INVOICE_RATE_WINDOW_SECS)
+        .cloned()
+        .collect()
+}
+
+/// An invoice is open until it expires or its subaddress is redeemed
+fn is_invoice_open(i: &IssuedInvoice, now: i64) -> bool {
+    !i.address.is_empty() && now < i.expire && find_redemption(&i.address).is_err()
+}
+
+/// Count open invoices. Closed ones are dropped from the index and their
+///
+/// records are deleted, only issued invoices are ever written.
+fn count_outstanding_invoices(now: i64) -> Result<usize, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let list_key = crate::INVOICE_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let old: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let mut open: Vec<String> = Vec::new();
+    for d in old.split(",").filter(|d| !d.is_empty()) {
+        let i = find_issued_invoice(&String::from(d)).unwrap_or_default();
+        if is_invoice_open(&i, now) {
+            open.push(String::from(d));
+        } else {
+            debug!("pruning closed invoice for: {}", d);
+            let _ = delete_issued_invoice(d);
+        }
+    }
+    let invoice_list = open.join(",");
+    debug!("writing invoice index {} for key {}", invoice_list, list_key);
+    let s_invoice_list = bincode::serialize(&invoice_list).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_invoice_list)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(open.len())
+}
+
+fn add_outstanding_invoice(destination: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let list_key = crate::INVOICE_LIST_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let old: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    if old.split(",").any(|d| d == destination) {
+        return Ok(());
+    }
+    let invoice_list = [old, String::from(destination)].join(",");
+    let s_invoice_list = bincode::serialize(&invoice_list).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_invoice_list)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
 /// Provide neccessary information for contacts to
 ///
-/// provide proof of payment.
-pub async fn create_invoice() -> reqres::Invoice {
-    info!("creating invoice");
-    // create a new subaddress
-    let wallet_name = String::from(crate::APP_NAME);
-    let wallet_password =
-        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let c_address = monero::create_address().await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
-    let address = c_address.result.address;
-    let pay_threshold = utils::get_payment_threshold();
-    let conf_threshold = utils::get_conf_threshold();
-    reqres::Invoice {
-        address,
+/// provide proof of payment. The totals cover every scope,
+///
+/// `tiers` breaks the price down per scope.
+///
+/// A destination gets the same subaddress until it is paid or
+///
+/// the invoice expires. Requests are rate limited per destination.
+///
+/// Peers outside the address book can make up destinations, so their
+///
+/// requests also share `INVOICE_GLOBAL_RATE_LIMIT` and they only get
+///
+/// new subaddresses while the number of outstanding invoices is below
+///
+/// `INVOICE_OUTSTANDING_LIMIT`. Rejected requests are not persisted.
+pub async fn create_invoice(peer: &peer::Peer) -> Result<reqres::Invoice, InvoiceError> {
+    let destination = &peer.destination;
+    let mut stranger_requests = STRANGER_INVOICE_REQUESTS.lock().await;
+    let now = chrono::offset::Utc::now().timestamp();
+    let mut issued = find_issued_invoice(destination).unwrap_or_default();
+    issued.requests = get_recent_requests(&issued.requests, now);
+    if issued.requests.len() >= crate::INVOICE_RATE_LIMIT {
+        error!("invoice rate limit reached for: {}", destination);
+        return Err(InvoiceError::RateLimited);
+    }
+    if !peer.is_contact {
+        *stranger_requests = get_recent_requests(&stranger_requests, now);
+        if stranger_requests.len() >= crate::INVOICE_GLOBAL_RATE_LIMIT {
+            error!("invoice rate limit reached for peers outside the address book");
+            return Err(InvoiceError::RateLimited);
+        }
+        stranger_requests.push(now);
+    }
+    issued.requests.push(now);
+    issued.destination = String::from(destination);
+    if is_invoice_open(&issued, now) {
+        info!("reusing invoice for: {}", destination);
+    } else {
+        let outstanding = count_outstanding_invoices(now).map_err(|_| InvoiceError::Unavailable)?;
+        if !peer.is_contact && outstanding >= crate::INVOICE_OUTSTANDING_LIMIT {
+            error!("too many outstanding invoices");
+            return Err(InvoiceError::Unavailable);
+        }
+        info!("creating invoice");
+        // create a new subaddress
+        let wallet_name = String::from(crate::APP_NAME);
+        let wallet_password =
+            std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+        let wallet = monero::open_wallet(&wallet_name, &wallet_password)
+            .await
+            .map_err(|_| InvoiceError::Unavailable)?;
+        let r_address =
+            account::create_address(&wallet, account::AccountPurpose::Messaging, destination)
+                .await;
+        drop(wallet);
+        let c_address = match r_address {
+            Ok(a) => a,
+            Err(e) => {
+                error!("failed to create invoice subaddress: {:?}", e);
+                return Err(InvoiceError::Unavailable);
+            }
+        };
+        let _ = cache_subaddress(&c_address.address, c_address.address_index);
+        issued.address = c_address.address;
+        issued.created = now;
+        issued.expire = now + crate::INVOICE_EXPIRATION_SECS;
+        add_outstanding_invoice(destination).map_err(|_| InvoiceError::Unavailable)?;
+    }
+    write_issued_invoice(&issued).map_err(|_| InvoiceError::Unavailable)?;
+    let tiers = get_tiers(destination);
+    let (pay_threshold, conf_threshold) = get_price(&tiers, &Scope::all());
+    Ok(reqres::Invoice {
+        address: String::from(&issued.address),
         conf_threshold,
         pay_threshold,
-    }
+        tiers,
+        expire: issued.expire,
+        pow_difficulty: pow::get_difficulty(),
+        pow_scopes: pow::get_scopes()
+            .iter()
+            .map(|s| s.value())
+            .collect::<Vec<String>>()
+            .join(","),
+    })
 }
 
 /// Technically the same process as creating a JWT
@@ -78,12 +416,81 @@ pub async fn create_invoice() -> reqres::Invoice {
 /// payment values and lower confirmations works as a spam
 ///
 /// disincentivizing mechanism.
-pub async fn create_jwp(proof: &TxProof) -> String {
+///
+/// This is the only point where the payment is always validated
+///
+/// on-chain. The `expire` claim is the block height after which
+///
+/// the JWP is no longer accepted.
+///
+/// Each payment is redeemed only once and the JWP is bound to the
+///
+/// authenticated peer that redeemed it, see `is_bound_to`. Repeating the
+///
+/// same redemption returns the JWP already issued instead of minting a new one.
+///
+/// The `scope` claim is limited to the scopes paid for. Scopes that are
+///
+/// free for the contact are granted without a payment.
+///
+/// The payment is claimed with `credit::claim` before it is checked on-chain
+///
+/// so a concurrent credit top up with the same proof is refused.
+pub async fn create_jwp(peer: &peer::Peer, proof: &TxProof) -> String {
     info!("creating jwp");
+    let redemption = find_redemption(&proof.subaddress).unwrap_or_default();
+    match check_redemption(&redemption, peer, &proof.hash) {
+        RedemptionCheck::New => (),
+        RedemptionCheck::Reissue(jwp) => {
+            info!("jwp already issued for: {}", &proof.hash);
+            return jwp;
+        }
+        RedemptionCheck::Refused => return String::new(),
+    }
+    if contact::is_blocked(&peer.destination) {
+        error!("refusing jwp for blocked contact: {}", &peer.destination);
+        return String::new();
+    }
+    let scopes = parse_scopes(&proof.scope);
+    if scopes.is_empty() {
+        error!("invalid jwp scope: {}", &proof.scope);
+        return String::new();
+    }
+    match credit::claim(proof) {
+        Ok(true) => (),
+        _ => {
+            error!("payment to {} was already claimed", &proof.subaddress);
+            return String::new();
+        }
+    }
+    let jwp = redeem(peer, proof, &scopes).await;
+    // a written redemption refuses further claims from here on
+    credit::release(&proof.subaddress);
+    jwp
+}
+
+/// Check a claimed payment and write its redemption, empty when it doesn't hold up
+async fn redeem(peer: &peer::Peer, proof: &TxProof, scopes: &[Scope]) -> String {
+    let (pay_threshold, conf_threshold) = get_price(&get_tiers(&peer.destination), scopes);
     // validate the proof
-    let c_txp: TxProof = validate_proof(proof).await;
-    if c_txp.hash.is_empty() {
-        error!("invalid transaction proof");
+    let is_valid_subaddress = validate_subaddress(&proof.subaddress).await;
+    if !is_valid_subaddress {
+        error!("invalid subaddress");
+        return String::new();
+    }
+    let mut confirmations = 0;
+    let free = is_free(peer, scopes);
+    if !free {
+        let c_txp: TxProof = validate_proof(proof, pay_threshold, conf_threshold).await;
+        if c_txp.hash.is_empty() {
+            error!("invalid transaction proof");
+            return String::new();
+        }
+        confirmations = c_txp.confirmations;
+    }
+    let height = refresh_height().await.unwrap_or_default();
+    if height == 0 {
+        error!("unable to determine chain height");
         return String::new();
     }
     let jwp_secret_key = utils::get_jwp_secret_key().unwrap_or_default();
@@ -96,10 +503,21 @@ pub async fn create_jwp(proof: &TxProof) -> String {
     let address = &proof.subaddress;
     let created = chrono::Utc::now().timestamp();
     let created_str = format!("{}", created);
-    let hash = &proof.hash;
-    let expire = &format!("{}", utils::get_payment_threshold());
+    // free scopes are not tied to a payment
+    let no_hash = String::new();
+    let hash = if free { &no_hash } else { &proof.hash };
+    let expire_height = get_expire_height(height, confirmations, conf_threshold);
+    let expire = &format!("{}", expire_height);
+    let jid = &utils::generate_rnd();
+    let scope = &scopes.iter().map(|s| s.value()).collect::<Vec<String>>().join(",");
+    write_last_check(jid).unwrap_or_else(|_| error!("failed to write jwp check"));
     let message = &proof.message;
     let signature = &proof.signature;
+    let destination = &peer.destination;
+    claims.insert("destination", destination);
+    claims.insert("nmpk", &peer.nmpk);
+    claims.insert("id", jid);
+    claims.insert("scope", scope);
     claims.insert("subaddress", address);
     claims.insert("created", &created_str);
     claims.insert("hash", hash);
@@ -107,17 +525,172 @@ pub async fn create_jwp(proof: &TxProof) -> String {
     claims.insert("message", message);
     claims.insert("signature", signature);
     let token = Token::new(header, claims).sign_with_key(&key);
-    String::from(token.expect("expected token").as_str())
+    let jwp = String::from(token.expect("expected token").as_str());
+    let r = Redemption {
+        subaddress: String::from(address),
+        created,
+        destination: String::from(destination),
+        expire: expire_height,
+        hash: String::from(hash),
+        jid: String::from(jid),
+        jwp: String::from(&jwp),
+        revoked: false,
+        scope: String::from(scope),
+    };
+    if write_redemption(&r).is_err() {
+        error!("failed to write redemption for: {}", hash);
+        return String::new();
+    }
+    jwp
+}
+
+/// What `create_jwp` does with a payment that may already be redeemed
+#[derive(Debug, PartialEq)]
+enum RedemptionCheck {
+    /// The payment was never redeemed
+    New,
+    /// The same peer repeats its redemption, it gets the same jwp
+    Reissue(String),
+    /// Revoked, or redeemed by someone else
+    Refused,
+}
+
+fn check_redemption(r: &Redemption, peer: &peer::Peer, hash: &String) -> RedemptionCheck {
+    if r.jid.is_empty() {
+        return RedemptionCheck::New;
+    }
+    if r.revoked {
+        error!("jwp for {} was revoked", &r.subaddress);
+        return RedemptionCheck::Refused;
+    }
+    if r.hash == *hash && is_bound_to(&get_unverified_claims(&r.jwp), peer) {
+        return RedemptionCheck::Reissue(String::from(&r.jwp));
+    }
+    error!("payment to {} was already redeemed", &r.subaddress);
+    RedemptionCheck::Refused
+}
+
+/// A jwp is only accepted from the destination and message key that
+///
+/// redeemed it, so sharing it is useless without the NMSK.
+fn is_bound_to(claims: &BTreeMap<String, String>, peer: &peer::Peer) -> bool {
+    let destination = claims.get("destination").cloned().unwrap_or_default();
+    let nmpk = claims.get("nmpk").cloned().unwrap_or_default();
+    !nmpk.is_empty() && destination == peer.destination && nmpk == peer.nmpk
+}
+
+/// Lookup the redemption of the payment to a subaddress
+pub fn find_redemption(subaddress: &String) -> Result<Redemption, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::REDEEMED_DB_KEY, subaddress);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        return Err(NevekoError::Database(MdbError::NotFound));
+    }
+    let result: Redemption = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+fn write_redemption(r: &Redemption) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::REDEEMED_DB_KEY, &r.subaddress);
+    let v = bincode::serialize(r).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    // in order to retrieve jwps issued to a contact, write subaddresses to ijl
+    let list_key = format!("{}-{}", crate::ISSUED_JWP_LIST_DB_KEY, &r.destination);
+    let r_list = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let old: String = bincode::deserialize(&r_list[..]).unwrap_or_default();
+    if old.split(",").any(|s| s == r.subaddress) {
+        return Ok(());
+    }
+    let issued_list = [old, String::from(&r.subaddress)].join(",");
+    debug!("writing issued jwp index {} for key {}", issued_list, list_key);
+    let s_issued_list = bincode::serialize(&issued_list).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, list_key.as_bytes(), &s_issued_list)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// All jwps issued to a contact
+pub fn find_issued(destination: &String) -> Result<Vec<Redemption>, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let list_key = format!("{}-{}", crate::ISSUED_JWP_LIST_DB_KEY, destination);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &list_key.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    if r.is_empty() {
+        info!("no jwps issued to: {}", destination);
+        return Ok(Vec::new());
+    }
+    let str_r: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    let mut issued: Vec<Redemption> = Vec::new();
+    for subaddress in str_r.split(",") {
+        if !subaddress.is_empty() {
+            let redemption = find_redemption(&String::from(subaddress))?;
+            issued.push(redemption);
+        }
+    }
+    Ok(issued)
+}
+
+/// Ids and expiry of the jwps issued to a contact that are not revoked
+pub fn find_issued_jwps(destination: &String) -> Result<Vec<reqres::IssuedJwp>, NevekoError> {
+    let issued = find_issued(destination)?
+        .into_iter()
+        .filter(|r| !r.revoked)
+        .map(|r| reqres::IssuedJwp {
+            jid: r.jid,
+            expire: r.expire,
+        })
+        .collect();
+    Ok(issued)
+}
+
+/// Revoke a single jwp issued to a contact
+pub fn revoke(destination: &String, jid: &String) -> Result<(), NevekoError> {
+    let issued = find_issued(destination)?;
+    let mut r = issued
+        .into_iter()
+        .find(|r| &r.jid == jid)
+        .ok_or(NevekoError::Database(MdbError::NotFound))?;
+    info!("revoking jwp: {}", jid);
+    r.revoked = true;
+    write_redemption(&r)
+}
+
+/// Revoke every jwp issued to a contact
+pub fn revoke_all(destination: &String) -> Result<(), NevekoError> {
+    let issued = find_issued(destination)?;
+    for mut r in issued {
+        if !r.revoked {
+            info!("revoking jwp: {}", &r.jid);
+            r.revoked = true;
+            write_redemption(&r)?;
+        }
+    }
+    Ok(())
 }
 
 /// Send transaction proof to contact for JWP generation
 pub async fn prove_payment(contact: String, txp: &TxProof) -> Result<reqres::Jwp, Box<dyn Error>> {
+    let b_txp = TxProof {
+        destination: i2p::get_destination(i2p::ServerTunnelType::App)?,
+        scope: String::from(&txp.scope),
+        subaddress: String::from(&txp.subaddress),
+        confirmations: txp.confirmations,
+        hash: String::from(&txp.hash),
+        message: String::from(&txp.message),
+        signature: String::from(&txp.signature),
+    };
     let host = utils::get_i2p_http_proxy();
     let proxy = reqwest::Proxy::http(&host)?;
     let client = reqwest::Client::builder().proxy(proxy).build();
     match client?
         .post(format!("http://{}/prove", contact))
-        .json(txp)
+        .headers(peer::get_auth_headers(&contact).await)
+        .json(&b_txp)
         .send()
         .await
     {
@@ -129,14 +702,15 @@ pub async fn prove_payment(contact: String, txp: &TxProof) -> Result<reqres::Jwp
                     // cache the jwp for for fts
                     let db = &DATABASE_LOCK;
                     let k = format!("{}-{}", crate::FTS_JWP_DB_KEY, &contact);
-                    db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())?;
-                    db::write_chunks(
-                        &db.env,
-                        &db.handle,
-                        k.as_bytes(),
-                        r.jwp.as_bytes(),
-                    )
-                    .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+                    if !r.jwp.is_empty() {
+                        if !txp.hash.is_empty() {
+                            let _ = ledger::set_jwp(&txp.hash, &r.jwp);
+                        }
+                        let v = bincode::serialize(&r.jwp).unwrap_or_default();
+                        db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes())?;
+                        db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+                            .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+                    }
                     Ok(r)
                 }
                 _ => Ok(Default::default()),
@@ -149,23 +723,62 @@ pub async fn prove_payment(contact: String, txp: &TxProof) -> Result<reqres::Jwp
     }
 }
 
+/// Lookup the jwp cached for a contact by `prove_payment`
+pub fn find_cached_jwp(contact: &String) -> Result<String, NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::FTS_JWP_DB_KEY, contact);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    let result: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    Ok(result)
+}
+
+/// Read the claims of a jwp issued by a contact. The signature
+///
+/// can only be verified by the issuer.
+pub fn get_unverified_claims(jwp: &str) -> BTreeMap<String, String> {
+    let token: Result<Token<Header, BTreeMap<String, String>, _>, jwt::Error> =
+        Token::parse_unverified(jwp);
+    match token {
+        Ok(t) => t.claims().clone(),
+        Err(e) => {
+            error!("failed to parse jwp: {:?}", e);
+            Default::default()
+        }
+    }
+}
+
 /// # PaymentProof
 ///
 /// is a JWP (JSON Web Proof) with the contents:
 ///
+/// `destination`: i2p destination the proof was issued to
+///
+/// `nmpk`: message key of the destination. The request must be signed
+///         with it, see `peer::authenticate`.
+///
+/// `id`: identifier of the proof in the redemption ledger, used for revocation
+///
+/// `scope`: comma separated scopes the proof grants access to
+///
 /// `subaddress`: a subaddress belonging to this neveko instance
 ///
 /// `created`: UTC timestamp the proof was created.
-///           <i>Future use</i> Potential offline payments.
 ///
-/// `expire`: blocks approved for
-///         <i>Future use</i>. Potential offline payments.
+/// `expire`: block height at which the proof expires. Checked offline
+///         against the cached chain height.
 ///
-/// `hash`: hash of the payment
+/// `hash`: hash of the payment, empty for free scopes
 ///
 /// `message`: (optional) default: empty string
 ///
 /// `signature`: validates proof of payment
+///
+/// This guard accepts a valid JWP of any scope. Use the scoped
+///
+/// guards `MessageProof`, `MarketReadProof` and `MarketOrderProof`
+///
+/// to restrict a route.
 #[derive(Debug)]
 pub struct PaymentProof(String);
 
@@ -175,11 +788,45 @@ impl PaymentProof {
     }
 }
 
+/// JWP with the `message` scope
 #[derive(Debug)]
+pub struct MessageProof(String);
+
+/// JWP with the `market-read` scope
+#[derive(Debug)]
+pub struct MarketReadProof(String);
+
+/// JWP with the `market-order` scope
+#[derive(Debug)]
+pub struct MarketOrderProof(String);
+
+#[derive(Debug, PartialEq)]
 pub enum PaymentProofError {
+    Balance,
     Expired,
     Missing,
     Invalid,
+    Revoked,
+    Scope,
+}
+
+/// Prepaid credit token, checked without debiting the balance
+#[derive(Debug)]
+pub struct CreditToken(String);
+
+impl CreditToken {
+    pub fn get_token(self) -> String {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CreditToken {
+    type Error = PaymentProofError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        validate_credit(request, None).await.map(CreditToken)
+    }
 }
 
 #[rocket::async_trait]
@@ -187,118 +834,638 @@ impl<'r> FromRequest<'r> for PaymentProof {
     type Error = PaymentProofError;
 
     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let proof = request.headers().get_one("proof");
-        match proof {
-            Some(proof) => {
-                // check validity of address, payment amount and tx confirmations
-                let jwp_secret_key = utils::get_jwp_secret_key().unwrap_or_default();
-                let key: Hmac<Sha512> = Hmac::new_from_slice(jwp_secret_key.as_bytes()).expect("");
-                let jwp: Result<
-                    Token<jwt::Header, BTreeMap<std::string::String, std::string::String>, _>,
-                    jwt::Error,
-                > = proof.verify_with_key(&key);
-                return match jwp {
-                    Ok(j) => {
-                        let claims = j.claims();
-                        let subaddress = &claims["subaddress"];
-                        let is_valid_subaddress = validate_subaddress(subaddress).await;
-                        if !is_valid_subaddress {
-                            return Outcome::Error((
-                                Status::PaymentRequired,
-                                PaymentProofError::Invalid,
-                            ));
-                        }
-                        let hash = &claims["hash"];
-                        let message = &claims["message"];
-                        let signature = &claims["signature"];
-                        // verify proof
-                        let txp: TxProof = TxProof {
-                            subaddress: String::from(subaddress),
-                            hash: String::from(hash),
-                            confirmations: 0,
-                            message: String::from(message),
-                            signature: String::from(signature),
-                        };
-                        let c_txp = validate_proof(&txp).await;
-                        // verify expiration
-                        let expire = utils::get_conf_threshold();
-                        // TODO(c2m): offline verification from created and expire fields
-                        if c_txp.confirmations > expire {
-                            return Outcome::Error((
-                                Status::Unauthorized,
-                                PaymentProofError::Expired,
-                            ));
-                        }
-                        Outcome::Success(PaymentProof(String::from(proof)))
-                    }
-                    Err(e) => {
-                        error!("jwp error: {:?}", e);
-                        return Outcome::Error((
-                            Status::PaymentRequired,
-                            PaymentProofError::Invalid,
-                        ));
-                    }
-                };
+        validate_jwp(request, None).await.map(PaymentProof)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MessageProof {
+    type Error = PaymentProofError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        validate_jwp(request, Some(Scope::Message)).await.map(MessageProof)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MarketReadProof {
+    type Error = PaymentProofError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        validate_jwp(request, Some(Scope::MarketRead)).await.map(MarketReadProof)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MarketOrderProof {
+    type Error = PaymentProofError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        validate_jwp(request, Some(Scope::MarketOrder)).await.map(MarketOrderProof)
+    }
+}
+
+/// Shared validation for the payment proof guards. Pass `None` to accept
+///
+/// any scope.
+async fn validate_jwp(
+    request: &Request<'_>,
+    required: Option<Scope>,
+) -> request::Outcome<String, PaymentProofError> {
+    let proof = request.headers().get_one("proof").unwrap_or_default();
+    let stamp = request.headers().get_one("pow").unwrap_or_default();
+    let pow_only = pow::is_pow_only() && required.as_ref().map(pow::is_pow_scope).unwrap_or(false);
+    if !stamp.is_empty() || pow_only {
+        return validate_pow(request, required).await;
+    }
+    if proof.is_empty() {
+        // contacts with prepaid credit pay per call instead,
+        // which needs a scope to price the call
+        if required.is_none() {
+            return Outcome::Error((Status::PaymentRequired, PaymentProofError::Scope));
+        }
+        return validate_credit(request, required).await;
+    }
+    // check validity of address, payment amount and tx confirmations
+    let jwp_secret_key = utils::get_jwp_secret_key().unwrap_or_default();
+    let key: Hmac<Sha512> = Hmac::new_from_slice(jwp_secret_key.as_bytes()).expect("");
+    let jwp: Result<
+        Token<jwt::Header, BTreeMap<std::string::String, std::string::String>, _>,
+        jwt::Error,
+    > = proof.verify_with_key(&key);
+    let j = match jwp {
+        Ok(j) => j,
+        Err(e) => {
+            error!("jwp error: {:?}", e);
+            return Outcome::Error((Status::PaymentRequired, PaymentProofError::Invalid));
+        }
+    };
+    let claims = j.claims();
+    let requester = match peer::authenticate(request).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("jwp presented without peer auth: {:?}", e);
+            return Outcome::Error((Status::PaymentRequired, PaymentProofError::Invalid));
+        }
+    };
+    if !is_bound_to(claims, &requester) {
+        error!("jwp presented by the wrong peer");
+        return Outcome::Error((Status::PaymentRequired, PaymentProofError::Invalid));
+    }
+    let destination = String::from(&requester.destination);
+    let subaddress = &claims["subaddress"];
+    let jid = claims.get("id").cloned().unwrap_or_default();
+    match check_ledger(subaddress, &jid) {
+        Ok(_) => (),
+        Err(PaymentProofError::Revoked) => {
+            return Outcome::Error((Status::Unauthorized, PaymentProofError::Revoked));
+        }
+        Err(e) => return Outcome::Error((Status::PaymentRequired, e)),
+    }
+    let scopes = parse_scopes(&claims.get("scope").cloned().unwrap_or_default());
+    if let Some(scope) = required {
+        if !scopes.contains(&scope) {
+            error!("jwp missing scope: {}", scope.value());
+            return Outcome::Error((Status::PaymentRequired, PaymentProofError::Scope));
+        }
+    }
+    let hash = &claims["hash"];
+    let message = &claims["message"];
+    let signature = &claims["signature"];
+    let expire = claims["expire"].parse::<u64>().unwrap_or(0);
+    let height = get_cached_height();
+    // offline verification is only possible with a warm height
+    // cache, otherwise fallback to the blockchain
+    let is_offline = height > 0 && !is_recheck_due(&jid);
+    if !is_offline {
+        if hash.is_empty() {
+            // free scopes are only valid while the override is in place
+            if !is_free(&requester, &scopes) {
+                return Outcome::Error((Status::PaymentRequired, PaymentProofError::Invalid));
             }
-            None => Outcome::Error((Status::PaymentRequired, PaymentProofError::Missing)),
+        } else {
+            let is_valid_subaddress = validate_subaddress(subaddress).await;
+            if !is_valid_subaddress {
+                return Outcome::Error((Status::PaymentRequired, PaymentProofError::Invalid));
+            }
+            let (pay_threshold, conf_threshold) = get_price(&get_tiers(&destination), &scopes);
+            // verify proof
+            let txp: TxProof = TxProof {
+                destination: String::from(&destination),
+                scope: String::new(),
+                subaddress: String::from(subaddress),
+                hash: String::from(hash),
+                confirmations: 0,
+                message: String::from(message),
+                signature: String::from(signature),
+            };
+            let c_txp = validate_proof(&txp, pay_threshold, conf_threshold).await;
+            if c_txp.hash.is_empty() {
+                return Outcome::Error((Status::Unauthorized, PaymentProofError::Expired));
+            }
+        }
+        let _ = write_last_check(&jid);
+    }
+    // verify expiration
+    if is_expired(expire, get_cached_height()) {
+        return Outcome::Error((Status::Unauthorized, PaymentProofError::Expired));
+    }
+    Outcome::Success(String::from(proof))
+}
+
+/// Check the redemption ledger for the jwp and its revocation
+fn check_ledger(subaddress: &String, jid: &String) -> Result<(), PaymentProofError> {
+    let redemption = find_redemption(subaddress).unwrap_or_default();
+    if jid.is_empty() || redemption.jid != *jid {
+        error!("jwp not found in redemption ledger");
+        return Err(PaymentProofError::Invalid);
+    }
+    if redemption.revoked {
+        return Err(PaymentProofError::Revoked);
+    }
+    Ok(())
+}
+
+/// Admit a call with a proof-of-work stamp
+async fn validate_pow(
+    request: &Request<'_>,
+    required: Option<Scope>,
+) -> request::Outcome<String, PaymentProofError> {
+    let stamp = request.headers().get_one("pow").unwrap_or_default();
+    if stamp.is_empty() {
+        return Outcome::Error((Status::PaymentRequired, PaymentProofError::Missing));
+    }
+    let requester = match peer::authenticate(request).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("pow stamp presented without peer auth: {:?}", e);
+            return Outcome::Error((Status::PaymentRequired, PaymentProofError::Invalid));
+        }
+    };
+    match pow::validate(stamp, &requester, required) {
+        Ok(_) => Outcome::Success(String::from(stamp)),
+        Err(PaymentProofError::Expired) => {
+            Outcome::Error((Status::Unauthorized, PaymentProofError::Expired))
+        }
+        Err(e) => Outcome::Error((Status::PaymentRequired, e)),
+    }
+}
+
+/// Debit the price of a call from the requester's prepaid credit.
+///
+/// `None` only checks the token.
+async fn validate_credit(
+    request: &Request<'_>,
+    required: Option<Scope>,
+) -> request::Outcome<String, PaymentProofError> {
+    let token = request.headers().get_one("credit").unwrap_or_default();
+    if token.is_empty() {
+        return Outcome::Error((Status::PaymentRequired, PaymentProofError::Missing));
+    }
+    let requester = match peer::authenticate(request).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("credit presented without peer auth: {:?}", e);
+            return Outcome::Error((Status::PaymentRequired, PaymentProofError::Invalid));
+        }
+    };
+    let (price, reference) = match required {
+        Some(scope) => (
+            get_call_price(&get_tiers(&requester.destination), &scope),
+            scope.value(),
+        ),
+        None => (0, String::new()),
+    };
+    match credit::validate(token, &requester, price, reference) {
+        Ok(_) => Outcome::Success(String::from(token)),
+        Err(PaymentProofError::Revoked) => {
+            Outcome::Error((Status::Unauthorized, PaymentProofError::Revoked))
         }
+        Err(e) => Outcome::Error((Status::PaymentRequired, e)),
     }
 }
 
-// TODO(c2m): alternative logic for offline payment validations
-//            jwp creation, however, will always require blockchain validation?
-//            future validations not so much
-async fn validate_proof(txp: &TxProof) -> TxProof {
+/// On-chain check of a transaction proof. Returns the confirmations
+///
+/// and the amount received, `None` when the proof doesn't hold up.
+pub async fn check_proof(txp: &TxProof) -> Option<(u64, u128)> {
     let wallet_name = String::from(crate::APP_NAME);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
+    let wallet = monero::open_wallet(&wallet_name, &wallet_password).await.ok()?;
     // verify unlock time isn't something funky (e.g. > 20)
-    let tx: reqres::XmrRpcGetTxByIdResponse = monero::get_transfer_by_txid(&txp.hash).await;
+    let tx: reqres::XmrRpcGetTxByIdResponse =
+        monero::get_transfer_by_txid(&wallet, &txp.hash).await.ok()?;
     let unlock_time = tx.result.transfer.unlock_time;
     let tx_type = tx.result.transfer.r#type;
     let propgated = monero::TransactionType::propagated(tx_type);
     if !propgated {
-        monero::close_wallet(&wallet_name, &wallet_password).await;
-        return Default::default();
-    }
-    let p = monero::check_tx_proof(txp).await;
-    let cth = utils::get_conf_threshold();
-    let pth = utils::get_payment_threshold();
-    let lgtm = p.result.good
-        && unlock_time < monero::LockTimeLimit::Blocks.value()
-        && p.result.confirmations < cth
-        && p.result.received >= pth;
+        return None;
+    }
+    let p = monero::check_tx_proof(&wallet, txp).await.ok()?;
+    drop(wallet);
+    let lgtm = p.result.good && unlock_time < monero::LockTimeLimit::Blocks.value();
     if lgtm {
-        monero::close_wallet(&wallet_name, &wallet_password).await;
-        return TxProof {
+        return Some((p.result.confirmations, p.result.received));
+    }
+    None
+}
+
+/// Full on-chain validation of a transaction proof. Only ran at jwp
+///
+/// creation and periodic re-checks.
+async fn validate_proof(txp: &TxProof, pth: u128, cth: u64) -> TxProof {
+    match check_proof(txp).await {
+        Some((confirmations, received)) if confirmations < cth && received >= pth => TxProof {
+            destination: String::from(&txp.destination),
+            scope: String::from(&txp.scope),
             subaddress: String::from(&txp.subaddress),
             hash: String::from(&txp.hash),
-            confirmations: p.result.confirmations,
+            confirmations,
             message: String::from(&txp.message),
             signature: String::from(&txp.signature),
-        };
+        },
+        _ => Default::default(),
+    }
+}
+
+/// Block height at which a jwp expires given the current height, the
+///
+/// confirmations on the payment and the confirmation threshold.
+pub fn get_expire_height(height: u64, confirmations: u64, conf_threshold: u64) -> u64 {
+    height.saturating_sub(confirmations) + conf_threshold
+}
+
+/// A jwp is expired once the chain reaches the `expire` claim
+pub fn is_expired(expire: u64, height: u64) -> bool {
+    expire == 0 || height >= expire
+}
+
+/// Last known chain height, zero when never fetched
+pub fn get_cached_height() -> u64 {
+    let db = &DATABASE_LOCK;
+    let r = db::DatabaseEnvironment::read(
+        &db.env,
+        &db.handle,
+        &crate::CHAIN_HEIGHT_DB_KEY.as_bytes().to_vec(),
+    )
+    .unwrap_or_default();
+    bincode::deserialize(&r[..]).unwrap_or_default()
+}
+
+/// Fetch the chain height from the daemon and cache it
+async fn refresh_height() -> Result<u64, NevekoError> {
+    let height = if utils::is_using_remote_node() {
+        monero::p_get_height()
+            .await
+            .map_err(|_| NevekoError::MoneroDaemon)?
+            .height
+    } else {
+        monero::get_height().await.height
+    };
+    if height == 0 {
+        return Err(NevekoError::MoneroDaemon);
+    }
+    let db = &DATABASE_LOCK;
+    let v = bincode::serialize(&height).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, crate::CHAIN_HEIGHT_DB_KEY.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(height)
+}
+
+/// Triggered on app startup, keeps the chain height cache warm
+pub async fn cache_height() -> Result<(), NevekoError> {
+    let period = std::time::Duration::from_millis(crate::CHAIN_HEIGHT_REFRESH_INTERVAL as u64);
+    let mut tick = tokio::time::interval(period);
+    loop {
+        tick.tick().await;
+        debug!("running chain height cache thread");
+        let _ = refresh_height().await;
     }
-    monero::close_wallet(&wallet_name, &wallet_password).await;
-    Default::default()
+}
+
+fn write_last_check(jid: &String) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::JWP_CHECK_DB_KEY, jid);
+    let now = chrono::offset::Utc::now().timestamp();
+    let v = bincode::serialize(&now).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Check if the payment behind a jwp is due for on-chain validation
+fn is_recheck_due(jid: &String) -> bool {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::JWP_CHECK_DB_KEY, jid);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    let last_check: i64 = bincode::deserialize(&r[..]).unwrap_or_default();
+    let now = chrono::offset::Utc::now().timestamp();
+    last_check + (crate::JWP_RECHECK_INTERVAL / 1000) as i64 <= now
+}
+
+/// Remember a subaddress created by us along with its minor index
+fn cache_subaddress(subaddress: &String, minor: u64) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::SUBADDRESS_DB_KEY, subaddress);
+    let v = bincode::serialize(&minor).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+fn is_cached_subaddress(subaddress: &String) -> bool {
+    let db = &DATABASE_LOCK;
+    let k = format!("{}-{}", crate::SUBADDRESS_DB_KEY, subaddress);
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    !r.is_empty()
 }
 
 /// Validate that the subaddress in the proof was
 ///
-/// created by us. TODO(?): Use xmr rpc call `get_address_index`
+/// created by us. Subaddresses handed out with invoices are
+///
+/// cached so the wallet is only opened for ones created before
 ///
-/// for faster lookups (check minor > 0)
-async fn validate_subaddress(subaddress: &String) -> bool {
+/// the cache existed, which are looked up with `get_address_index`.
+pub async fn validate_subaddress(subaddress: &String) -> bool {
+    if is_cached_subaddress(subaddress) {
+        return true;
+    }
     let wallet_name = String::from(crate::APP_NAME);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let m_address = monero::get_address().await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
-    let all_address = m_address.result.addresses;
-    let mut address_list: Vec<String> = Vec::new();
-    for s_address in all_address {
-        address_list.push(s_address.address);
-    }
-    address_list.contains(subaddress)
+    let r_index = match monero::open_wallet(&wallet_name, &wallet_password).await {
+        Ok(wallet) => monero::get_address_index(&wallet, subaddress).await,
+        Err(e) => Err(e),
+    };
+    let index = match r_index {
+        Ok(i) => i.result.index,
+        Err(e) => {
+            error!("subaddress lookup failed: {}", e);
+            return false;
+        }
+    };
+    // the account base address is minor 0, invoices used to be in account 0
+    let messaging = account::find_index(account::AccountPurpose::Messaging);
+    let is_invoice_account = index.major == 0 || messaging == Some(index.major as u32);
+    let is_valid = is_invoice_account && index.minor > 0;
+    if is_valid {
+        let _ = cache_subaddress(subaddress, index.minor);
+    }
+    is_valid
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_expire_height_test() {
+        assert_eq!(get_expire_height(1000, 2, 720), 1718);
+        assert_eq!(get_expire_height(1, 5, 720), 720);
+    }
+
+    #[test]
+    fn parse_scopes_test() {
+        assert_eq!(parse_scopes(""), Scope::all());
+        assert_eq!(
+            parse_scopes("message,market-order,bogus"),
+            vec![Scope::Message, Scope::MarketOrder]
+        );
+    }
+
+    #[test]
+    fn get_price_test() {
+        let tiers = vec![
+            reqres::Tier {
+                scope: Scope::Message.value(),
+                pay_threshold: 10,
+                conf_threshold: 720,
+                call_price: 0,
+            },
+            reqres::Tier {
+                scope: Scope::MarketRead.value(),
+                pay_threshold: 0,
+                conf_threshold: 100,
+                call_price: 0,
+            },
+            reqres::Tier {
+                scope: Scope::MarketOrder.value(),
+                pay_threshold: 50,
+                conf_threshold: 360,
+                call_price: 0,
+            },
+        ];
+        assert_eq!(get_price(&tiers, &Scope::all()), (60, 100));
+        assert_eq!(get_price(&tiers, &[Scope::Message]), (10, 720));
+        assert_eq!(get_price(&tiers, &[]), (0, 0));
+    }
+
+    #[test]
+    fn get_call_price_test() {
+        let tiers = vec![reqres::Tier {
+            scope: Scope::Message.value(),
+            pay_threshold: 10,
+            conf_threshold: 720,
+            call_price: 2,
+        }];
+        assert_eq!(get_call_price(&tiers, &Scope::Message), 2);
+        assert_eq!(get_call_price(&tiers, &Scope::MarketRead), 0);
+    }
+
+    #[test]
+    fn is_expired_test() {
+        assert!(!is_expired(1718, 1717));
+        assert!(is_expired(1718, 1718));
+        assert!(is_expired(0, 1));
+    }
+
+    #[test]
+    fn get_recent_requests_test() {
+        let now = 1000;
+        let requests = vec![now - crate::INVOICE_RATE_WINDOW_SECS, now - 1, now];
+        assert_eq!(get_recent_requests(&requests, now), vec![now - 1, now]);
+    }
+
+    fn test_peer(destination: &str, nmpk: &str) -> peer::Peer {
+        peer::Peer {
+            destination: String::from(destination),
+            nmpk: String::from(nmpk),
+            is_contact: false,
+        }
+    }
+
+    fn test_redemption(destination: &str, nmpk: &str) -> Redemption {
+        let key: Hmac<Sha512> = Hmac::new_from_slice(b"test").expect("hash");
+        let mut claims = BTreeMap::new();
+        claims.insert("destination", destination);
+        claims.insert("nmpk", nmpk);
+        Redemption {
+            subaddress: String::from("subaddress"),
+            destination: String::from(destination),
+            hash: String::from("hash"),
+            jid: String::from("jid"),
+            jwp: claims.sign_with_key(&key).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn is_bound_to_test() {
+        let r = test_redemption("a.b32.i2p", "nmpk-a");
+        let claims = get_unverified_claims(&r.jwp);
+        assert!(is_bound_to(&claims, &test_peer("a.b32.i2p", "nmpk-a")));
+        // a shared jwp presented with the same destination header
+        assert!(!is_bound_to(&claims, &test_peer("a.b32.i2p", "nmpk-b")));
+        assert!(!is_bound_to(&claims, &test_peer("b.b32.i2p", "nmpk-a")));
+        // jwps issued before the nmpk claim are not bound to anyone
+        let r_old = test_redemption("a.b32.i2p", "");
+        let old_claims = get_unverified_claims(&r_old.jwp);
+        assert!(!is_bound_to(&old_claims, &test_peer("a.b32.i2p", "")));
+    }
+
+    #[test]
+    fn check_redemption_test() {
+        let hash = String::from("hash");
+        let owner = test_peer("a.b32.i2p", "nmpk-a");
+        assert_eq!(
+            check_redemption(&Default::default(), &owner, &hash),
+            RedemptionCheck::New
+        );
+        // replaying the redemption returns the jwp already issued
+        let mut r = test_redemption("a.b32.i2p", "nmpk-a");
+        assert_eq!(
+            check_redemption(&r, &owner, &hash),
+            RedemptionCheck::Reissue(String::from(&r.jwp))
+        );
+        // the payment can't be reused by another peer or with another tx
+        let other = test_peer("b.b32.i2p", "nmpk-b");
+        assert_eq!(check_redemption(&r, &other, &hash), RedemptionCheck::Refused);
+        let spoofed = test_peer("a.b32.i2p", "nmpk-b");
+        assert_eq!(check_redemption(&r, &spoofed, &hash), RedemptionCheck::Refused);
+        let other_hash = String::from("other");
+        assert_eq!(
+            check_redemption(&r, &owner, &other_hash),
+            RedemptionCheck::Refused
+        );
+        r.revoked = true;
+        assert_eq!(check_redemption(&r, &owner, &hash), RedemptionCheck::Refused);
+    }
+
+    fn cleanup_redemption(r: &Redemption) {
+        let db = &DATABASE_LOCK;
+        let k = format!("{}-{}", crate::REDEEMED_DB_KEY, &r.subaddress);
+        let list_key = format!("{}-{}", crate::ISSUED_JWP_LIST_DB_KEY, &r.destination);
+        let _ = db::DatabaseEnvironment::delete(&db.env, &db.handle, k.as_bytes());
+        let _ = db::DatabaseEnvironment::delete(&db.env, &db.handle, list_key.as_bytes());
+    }
+
+    #[test]
+    fn revoke_test() {
+        let r = Redemption {
+            subaddress: String::from("revoke-test-subaddress"),
+            destination: String::from("revoke-test.b32.i2p"),
+            jid: String::from("revoke-test-jid"),
+            expire: 1000,
+            ..Default::default()
+        };
+        write_redemption(&r).expect("write redemption");
+        let issued = find_issued_jwps(&r.destination).unwrap_or_default();
+        assert_eq!(issued.len(), 1);
+        assert_eq!(issued[0].jid, r.jid);
+        assert_eq!(issued[0].expire, 1000);
+        assert!(check_ledger(&r.subaddress, &r.jid).is_ok());
+        assert_eq!(
+            check_ledger(&r.subaddress, &String::from("other")),
+            Err(PaymentProofError::Invalid)
+        );
+        assert!(revoke(&r.destination, &String::from("other")).is_err());
+        revoke(&r.destination, &r.jid).expect("revoke");
+        assert!(find_redemption(&r.subaddress).unwrap_or_default().revoked);
+        assert_eq!(
+            check_ledger(&r.subaddress, &r.jid),
+            Err(PaymentProofError::Revoked)
+        );
+        assert!(find_issued_jwps(&r.destination).unwrap_or_default().is_empty());
+        cleanup_redemption(&r);
+    }
+
+    #[test]
+    fn claim_redemption_test() {
+        let rt = tokio::runtime::Runtime::new().expect("Unable to create Runtime for test");
+        let peer = peer::Peer {
+            is_contact: true,
+            ..test_peer("claim-redemption-test.b32.i2p", "nmpk")
+        };
+        let txp = TxProof {
+            subaddress: String::from("claim-redemption-test-subaddress"),
+            hash: String::from("claim-redemption-test-hash"),
+            scope: Scope::Message.value(),
+            ..Default::default()
+        };
+        // a payment claimed by a credit top up can't be redeemed
+        assert!(credit::claim(&txp).expect("claim"));
+        assert!(rt.block_on(create_jwp(&peer, &txp)).is_empty());
+        credit::release(&txp.subaddress);
+        // and a redeemed payment can't be credited
+        let r = Redemption {
+            subaddress: String::from(&txp.subaddress),
+            destination: String::from(&peer.destination),
+            hash: String::from(&txp.hash),
+            jid: String::from("claim-redemption-test-jid"),
+            ..Default::default()
+        };
+        write_redemption(&r).expect("write redemption");
+        assert!(rt.block_on(credit::top_up(&peer, &txp)).is_err());
+        assert!(!credit::is_credited(&txp.subaddress));
+        cleanup_redemption(&r);
+    }
+
+    #[test]
+    fn is_free_test() {
+        let mut peer = test_peer("is-free-test.b32.i2p", "nmpk");
+        let tiers: Vec<reqres::Tier> = Scope::all()
+            .iter()
+            .map(|s| reqres::Tier {
+                scope: s.value(),
+                ..Default::default()
+            })
+            .collect();
+        write_tier_overrides(&peer.destination, &tiers).expect("write overrides");
+        // zero priced overrides need a destination authenticated by the address book
+        assert!(!is_free(&peer, &[Scope::Message]));
+        peer.is_contact = true;
+        assert!(is_free(&peer, &[Scope::Message]));
+        delete_tier_overrides(&peer.destination).expect("delete overrides");
+        // default tiers have a price
+        assert!(!is_free(&peer, &[Scope::Message]));
+    }
+
+    #[test]
+    fn is_invoice_open_test() {
+        let mut i = IssuedInvoice {
+            expire: 1000,
+            ..Default::default()
+        };
+        assert!(!is_invoice_open(&i, 1));
+        i.address = String::from("open-invoice-test-subaddress");
+        assert!(is_invoice_open(&i, 999));
+        assert!(!is_invoice_open(&i, 1000));
+        // paid invoices are closed
+        let r = Redemption {
+            subaddress: String::from(&i.address),
+            destination: String::from("open-invoice-test.b32.i2p"),
+            jid: String::from("open-invoice-test-jid"),
+            ..Default::default()
+        };
+        write_redemption(&r).expect("write redemption");
+        assert!(!is_invoice_open(&i, 999));
+        cleanup_redemption(&r);
+    }
 }
@@ -0,0 +1,294 @@
+//! Background renewal of cached JWPs before they expire
+
+use crate::{
+    args,
+    contact,
+    db::{
+        self,
+        DATABASE_LOCK,
+    },
+    error::NevekoError,
+    ledger,
+    monero,
+    proof,
+    reqres,
+};
+use clap::Parser;
+use kn0sys_lmdb_rs::MdbError;
+use log::{
+    debug,
+    error,
+    info,
+    warn,
+};
+
+const SECONDS_IN_DAY: i64 = 60 * 60 * 24;
+
+/// Daily budget in piconeros for automatic renewals. The value set
+///
+/// from the gui takes precedence over `--jwp-renewal-budget`.
+///
+/// Zero disables renewal.
+pub fn get_budget() -> u128 {
+    let db = &DATABASE_LOCK;
+    let r = db::DatabaseEnvironment::read(
+        &db.env,
+        &db.handle,
+        &crate::JWP_RENEWAL_BUDGET_DB_KEY.as_bytes().to_vec(),
+    )
+    .unwrap_or_default();
+    if r.is_empty() {
+        let args = args::Args::parse();
+        return args.jwp_renewal_budget;
+    }
+    bincode::deserialize(&r[..]).unwrap_or_default()
+}
+
+/// Set the daily budget for automatic renewals
+pub fn set_budget(budget: u128) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let v = bincode::serialize(&budget).unwrap_or_default();
+    db::write_chunks(
+        &db.env,
+        &db.handle,
+        crate::JWP_RENEWAL_BUDGET_DB_KEY.as_bytes(),
+        &v,
+    )
+    .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+fn get_spent_key() -> String {
+    let day = chrono::offset::Utc::now().timestamp() / SECONDS_IN_DAY;
+    format!("{}-{}", crate::JWP_RENEWAL_SPENT_DB_KEY, day)
+}
+
+/// Piconeros spent on renewals today
+pub fn get_spent() -> u128 {
+    let db = &DATABASE_LOCK;
+    let k = get_spent_key();
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    bincode::deserialize(&r[..]).unwrap_or_default()
+}
+
+fn add_spent(amount: u128) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = get_spent_key();
+    let v = bincode::serialize(&(get_spent() + amount)).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Blocks left on a jwp issued by a contact, zero when expired or unknown
+pub fn get_remaining_blocks(jwp: &str, height: u64) -> u64 {
+    let claims = proof::get_unverified_claims(jwp);
+    let expire = claims
+        .get("expire")
+        .and_then(|e| e.parse::<u64>().ok())
+        .unwrap_or(0);
+    expire.saturating_sub(height)
+}
+
+/// A jwp is renewed once it is within `JWP_RENEWAL_LEAD_BLOCKS` of expiring
+pub fn is_renewal_due(jwp: &str, height: u64) -> bool {
+    get_remaining_blocks(jwp, height) <= crate::JWP_RENEWAL_LEAD_BLOCKS
+}
+
+/// Whether a renewal fits in what is left of the daily budget
+fn is_within_budget(spent: u128, amount: u128, budget: u128) -> bool {
+    spent.saturating_add(amount) <= budget
+}
+
+/// Price of renewing the scopes of a jwp. Falls back to the invoice
+///
+/// total for contacts that don't advertise tiers.
+pub fn get_renewal_price(invoice: &reqres::Invoice, scope: &str) -> u128 {
+    if invoice.tiers.is_empty() {
+        return invoice.pay_threshold;
+    }
+    let (pay_threshold, _) = proof::get_price(&invoice.tiers, &proof::parse_scopes(scope));
+    pay_threshold
+}
+
+/// Pay for and prove a new jwp for the same scopes as the current one
+async fn renew(contact: &String, jwp: &str) -> Result<(), NevekoError> {
+    let scope = proof::get_unverified_claims(jwp)
+        .get("scope")
+        .cloned()
+        .unwrap_or_default();
+    let invoice = contact::request_invoice(String::from(contact))
+        .await
+        .map_err(|_| NevekoError::Contact)?;
+    if invoice.address.is_empty() {
+        error!("failed to get renewal invoice from: {}", contact);
+        return Err(NevekoError::Contact);
+    }
+    let amount = get_renewal_price(&invoice, &scope);
+    let budget = get_budget();
+    let spent = get_spent();
+    if !is_within_budget(spent, amount, budget) {
+        warn!(
+            "renewal for {} of {} piconero(s) exceeds the daily budget",
+            contact, amount
+        );
+        return Ok(());
+    }
+    let mut txp = proof::TxProof {
+        scope,
+        subaddress: String::from(&invoice.address),
+        ..Default::default()
+    };
+    if amount > 0 {
+        let wallet_name = String::from(crate::APP_NAME);
+        let wallet_password =
+            std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+        let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+        let d = reqres::Destination {
+            address: String::from(&invoice.address),
+            amount,
+        };
+        let transfer = match ledger::transfer(&wallet, contact, d).await {
+            Ok(t) => t,
+            Err(e) => {
+                error!("renewal payment to {} failed", contact);
+                return Err(e);
+            }
+        };
+        // don't hold the wallet while the payment propagates
+        drop(wallet);
+        add_spent(amount)?;
+        txp.hash = String::from(&transfer.result.tx_hash);
+        // give the payment time to reach the contact's daemon
+        tokio::time::sleep(std::time::Duration::from_secs(
+            crate::JWP_RENEWAL_PROPAGATION_SECS,
+        ))
+        .await;
+        let wallet = monero::open_wallet(&wallet_name, &wallet_password).await?;
+        let get_txp = monero::get_tx_proof(
+            &wallet,
+            proof::TxProof {
+                subaddress: String::from(&txp.subaddress),
+                hash: String::from(&txp.hash),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_or_default();
+        drop(wallet);
+        txp.signature = get_txp.result.signature;
+    }
+    let r_jwp = proof::prove_payment(String::from(contact), &txp)
+        .await
+        .map_err(|_| NevekoError::Contact)?;
+    if r_jwp.jwp.is_empty() {
+        error!("failed to renew jwp for: {}", contact);
+        return Err(NevekoError::Contact);
+    }
+    info!("renewed jwp for: {}", contact);
+    Ok(())
+}
+
+/// Triggered on app startup, renews cached jwps that are about to expire
+///
+/// as long as the daily budget allows it.
+pub async fn renew_jwps() -> Result<(), NevekoError> {
+    let period = std::time::Duration::from_millis(crate::JWP_RENEWAL_INTERVAL as u64);
+    let mut tick = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    loop {
+        debug!("running jwp renewal thread");
+        tick.tick().await;
+        if get_budget() == 0 {
+            debug!("jwp renewal is disabled");
+            continue;
+        }
+        let height = proof::get_cached_height();
+        if height == 0 {
+            continue;
+        }
+        let contacts = contact::find_all().unwrap_or_default();
+        for c in contacts {
+            let jwp = proof::find_cached_jwp(&c.i2p_address).unwrap_or_default();
+            if jwp.is_empty() {
+                continue;
+            }
+            let remaining = get_remaining_blocks(&jwp, height);
+            debug!("jwp for {} has {} blocks left", &c.i2p_address, remaining);
+            if is_renewal_due(&jwp, height) {
+                let _ = renew(&c.i2p_address, &jwp).await;
+            }
+        }
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_renewal_price_test() {
+        let mut invoice = reqres::Invoice {
+            pay_threshold: 30,
+            ..Default::default()
+        };
+        assert_eq!(get_renewal_price(&invoice, "message"), 30);
+        invoice.tiers = vec![
+            reqres::Tier {
+                scope: proof::Scope::Message.value(),
+                pay_threshold: 10,
+                conf_threshold: 720,
+                call_price: 0,
+            },
+            reqres::Tier {
+                scope: proof::Scope::MarketRead.value(),
+                pay_threshold: 20,
+                conf_threshold: 720,
+                call_price: 0,
+            },
+        ];
+        assert_eq!(get_renewal_price(&invoice, "message"), 10);
+        assert_eq!(get_renewal_price(&invoice, ""), 30);
+    }
+
+    fn test_jwp(expire: u64) -> String {
+        use hmac::{
+            Hmac,
+            Mac,
+        };
+        use jwt::SignWithKey;
+        let key: Hmac<sha2::Sha512> = Hmac::new_from_slice(b"test").expect("hash");
+        let mut claims = std::collections::BTreeMap::new();
+        claims.insert("expire", format!("{}", expire));
+        claims.insert("scope", proof::Scope::Message.value());
+        claims.sign_with_key(&key).unwrap_or_default()
+    }
+
+    #[test]
+    fn get_remaining_blocks_test() {
+        assert_eq!(get_remaining_blocks("not a jwp", 100), 0);
+        assert_eq!(get_remaining_blocks(&test_jwp(1000), 900), 100);
+        assert_eq!(get_remaining_blocks(&test_jwp(1000), 1200), 0);
+    }
+
+    #[test]
+    fn is_renewal_due_test() {
+        let jwp = test_jwp(1000);
+        let lead = crate::JWP_RENEWAL_LEAD_BLOCKS;
+        assert!(!is_renewal_due(&jwp, 1000 - lead - 1));
+        assert!(is_renewal_due(&jwp, 1000 - lead));
+        assert!(is_renewal_due(&jwp, 1000));
+        // unreadable jwps are renewed rather than left to expire
+        assert!(is_renewal_due("not a jwp", 1));
+    }
+
+    #[test]
+    fn is_within_budget_test() {
+        assert!(is_within_budget(0, 10, 10));
+        assert!(!is_within_budget(5, 10, 10));
+        assert!(!is_within_budget(u128::MAX, 1, u128::MAX));
+    }
+}
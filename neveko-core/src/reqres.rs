@@ -38,6 +38,30 @@ pub struct XmrRpcCreateWalletParams {
     pub password: String,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct XmrRpcRestoreDeterministicWalletParams {
+    pub restore_height: u64,
+    pub filename: String,
+    pub seed: String,
+    pub seed_offset: String,
+    pub password: String,
+    pub language: String,
+    pub autosave_current: bool,
+}
+
+/// Leaving out the spend key generates a view-only wallet
+#[derive(Deserialize, Serialize, Debug)]
+pub struct XmrRpcGenerateFromKeysParams {
+    pub restore_height: u64,
+    pub filename: String,
+    pub address: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub spendkey: String,
+    pub viewkey: String,
+    pub password: String,
+    pub autosave_current: bool,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct XmrRpcOpenWalletParams {
     pub filename: String,
@@ -82,7 +106,9 @@ pub struct XmrRpcBalanceParams {
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct XmrRpcAddressParams {
-    pub account_index: u8,
+    pub account_index: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub address_index: Vec<u32>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -112,6 +138,18 @@ pub struct XmrRpcGetTxByIdParams {
     pub txid: String,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct XmrRpcGetTransfersParams {
+    pub r#in: bool,
+    pub out: bool,
+    pub pending: bool,
+    pub failed: bool,
+    pub pool: bool,
+    pub account_index: u32,
+    pub subaddr_indices: Vec<u32>,
+    pub all_accounts: bool,
+}
+
 #[derive(Default, Deserialize, Serialize, Debug, PartialEq)]
 pub struct Destination {
     pub address: String,
@@ -126,6 +164,13 @@ pub struct XmrRpcTransferParams {
     pub priority: u8,
     pub ring_size: u32,
     pub get_tx_key: bool,
+    pub do_not_relay: bool,
+    pub get_tx_metadata: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct XmrRpcRelayTxParams {
+    pub hex: String,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -136,11 +181,29 @@ pub struct XmrRpcDescribeTransferParams {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct XmrRpcSweepAllParams {
     pub address: String,
+    pub account_index: u32,
+    pub subaddr_indices_all: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct XmrRpcCreateAddressParams {
-    pub account_index: u8,
+    pub account_index: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct XmrRpcCreateAccountParams {
+    pub label: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct XmrRpcLabelAddressParams {
+    pub index: SubAddressIndex,
+    pub label: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct XmrRpcGetAddressIndexParams {
+    pub address: String,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -154,34 +217,27 @@ pub struct XmrRpcChangePasswordParams {
     pub new_password: String,
 }
 
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct XmrRpcSetDaemonParams {
+    pub address: String,
+    pub trusted: bool,
+    pub ssl_allow_any_cert: bool,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct XmrRpcQueryKeyParams {
     pub key_type: String,
 }
 
 // requests
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcValidateAddressRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcValidateAddressParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcCreateRequest {
+/// JSON-RPC request for any monero-wallet-rpc method
+#[derive(Serialize, Debug)]
+pub struct XmrRpcJsonRequest<P> {
     pub jsonrpc: String,
     pub id: String,
     pub method: String,
-    pub params: XmrRpcCreateWalletParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcOpenRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcOpenWalletParams,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<P>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -205,151 +261,24 @@ pub struct XmrDaemonGetTransactionsRequest {
     pub decode_as_json: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcAddressRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcAddressParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcBalanceRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcBalanceParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcPrepareRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcPrepareParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcMakeRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcMakeParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcImportRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcImportParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcSignMultisigRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcSignMultisigParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcExchangeMultisigKeysRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcExchangeMultisigKeysParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcSignRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcSignParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcVerifyRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcVerifyParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcCheckTxProofRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcCheckTxProofParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcGetTxProofRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcGetTxProofParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcGetTxByIdRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcGetTxByIdParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcTransfrerRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcTransferParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcDescribeTransfrerRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcDescribeTransferParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcSweepAllRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcSweepAllParams,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct XmrRpcCreateAddressRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcCreateAddressParams,
+// results
+/// JSON-RPC response, exactly one of `result` or `error` is set
+#[derive(Debug, Deserialize)]
+pub struct XmrRpcJsonResponse<R> {
+    pub result: Option<R>,
+    pub error: Option<XmrRpcErrorObject>,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
-pub struct XmrRpcChangePasswordRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcChangePasswordParams,
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcErrorObject {
+    pub code: i64,
+    pub message: String,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
-pub struct XmrRpcQueryKeyRequest {
-    pub jsonrpc: String,
-    pub id: String,
-    pub method: String,
-    pub params: XmrRpcQueryKeyParams,
-}
+/// Result of methods that only report success, e.g. `open_wallet`
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcEmptyResult {}
 
-// results
 #[derive(Debug, Default, Deserialize)]
 pub struct XmrRpcValidateAddressResult {
     pub integrated: bool,
@@ -462,19 +391,22 @@ pub struct XmrRpcGetTxProofResult {
     pub signature: String,
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct SubAddressIndex {
     pub major: u64,
     pub minor: u64,
 }
 
 #[derive(Debug, Default, Deserialize)]
+#[serde(default)]
 pub struct Transfer {
     pub address: String,
     pub amount: u128,
     pub amounts: Vec<u128>,
     /// On zero conf this field is missing
     pub confirmations: Option<u64>,
+    /// Only known for outgoing transfers made by this wallet
+    pub destinations: Vec<Destination>,
     pub double_spend_seen: bool,
     pub fee: u128,
     pub height: u64,
@@ -496,6 +428,17 @@ pub struct XmrRpcGetTxByIdResult {
     pub transfers: Vec<Transfer>,
 }
 
+/// Each list is left out of the response when empty
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmrRpcGetTransfersResult {
+    pub r#in: Vec<Transfer>,
+    pub out: Vec<Transfer>,
+    pub pending: Vec<Transfer>,
+    pub failed: Vec<Transfer>,
+    pub pool: Vec<Transfer>,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct XmrRpcTranferResult {
     pub amount: u128,
@@ -543,6 +486,34 @@ pub struct XmrRpcSweepAllResult {
     pub weight_list: Vec<u128>,
 }
 
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct XmrRpcRelayTxResult {
+    pub tx_hash: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SubaddressAccount {
+    pub account_index: u32,
+    pub balance: u128,
+    pub base_address: String,
+    pub label: String,
+    pub tag: String,
+    pub unlocked_balance: u128,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct XmrRpcGetAccountsResult {
+    pub subaddress_accounts: Vec<SubaddressAccount>,
+    pub total_balance: u128,
+    pub total_unlocked_balance: u128,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct XmrRpcCreateAccountResult {
+    pub account_index: u32,
+    pub address: String,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct XmrRpcCreateAddressResult {
     pub address: String,
@@ -551,6 +522,11 @@ pub struct XmrRpcCreateAddressResult {
     pub addresses: Vec<String>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcGetAddressIndexResult {
+    pub index: SubAddressIndex,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct XmrRpcRefreshResult {
     pub blocks_fetched: u64,
@@ -575,6 +551,32 @@ pub struct XmrRpcQueryKeyResult {
     pub key: String,
 }
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmrRpcRestoreDeterministicWalletResult {
+    pub address: String,
+    pub info: String,
+    pub seed: String,
+    pub was_deprecated: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmrRpcGenerateFromKeysResult {
+    pub address: String,
+    pub info: String,
+}
+
+/// Fees are per byte, `fees` holds one for each priority tier
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct XmrDaemonGetFeeEstimateResult {
+    pub fee: u64,
+    #[serde(default)]
+    pub fees: Vec<u64>,
+    pub quantization_mask: u64,
+    pub status: String,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct XmrDaemonGetInfoResult {
     pub adjusted_time: u64,
@@ -677,6 +679,11 @@ pub struct XmrDaemonGetInfoResponse {
     pub result: XmrDaemonGetInfoResult,
 }
 
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct XmrDaemonGetFeeEstimateResponse {
+    pub result: XmrDaemonGetFeeEstimateResult,
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct XmrDaemonGetBlockResponse {
     pub result: XmrDaemonGetBlockResult,
@@ -768,6 +775,11 @@ pub struct XmrRpcGetTxByIdResponse {
     pub result: XmrRpcGetTxByIdResult,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcGetTransfersResponse {
+    pub result: XmrRpcGetTransfersResult,
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct XmrRpcTransferResponse {
     pub result: XmrRpcTranferResult,
@@ -783,11 +795,31 @@ pub struct XmrRpcSweepAllResponse {
     pub result: XmrRpcSweepAllResult,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcRelayTxResponse {
+    pub result: XmrRpcRelayTxResult,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcGetAccountsResponse {
+    pub result: XmrRpcGetAccountsResult,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcCreateAccountResponse {
+    pub result: XmrRpcCreateAccountResult,
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct XmrRpcCreateAddressResponse {
     pub result: XmrRpcCreateAddressResult,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct XmrRpcGetAddressIndexResponse {
+    pub result: XmrRpcGetAddressIndexResult,
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct XmrRpcRefreshResponse {
     pub result: XmrRpcRefreshResult,
@@ -822,8 +854,49 @@ pub struct DecipheredMessageBody {
 #[serde(crate = "rocket::serde")]
 pub struct Invoice {
     pub address: String,
+    /// total price of all scopes
     pub pay_threshold: u128,
+    /// shortest expiration of all scopes
     pub conf_threshold: u64,
+    #[serde(default)]
+    pub tiers: Vec<Tier>,
+    /// unix timestamp after which an unpaid invoice is replaced
+    #[serde(default)]
+    pub expire: i64,
+    /// leading zero bits of proof-of-work challenges, zero when the node only accepts payment
+    #[serde(default)]
+    pub pow_difficulty: u32,
+    /// scopes that accept proof-of-work admission
+    #[serde(default)]
+    pub pow_scopes: String,
+}
+
+/// Proof-of-work challenge response for host.b32.i2p/pow
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PowChallenge {
+    pub challenge: String,
+    pub difficulty: u32,
+}
+
+/// Price and expiration in blocks of a JWP scope
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Tier {
+    pub scope: String,
+    pub pay_threshold: u128,
+    pub conf_threshold: u64,
+    /// price debited from a prepaid credit balance per call
+    #[serde(default)]
+    pub call_price: u128,
+}
+
+/// Response to a credit top up
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Credit {
+    pub balance: u128,
+    pub token: String,
 }
 
 /// Not to be confused with the PaymentProof guard.
@@ -835,6 +908,15 @@ pub struct Jwp {
     pub jwp: String,
 }
 
+/// Admin view of a jwp issued to a contact, the token itself is left out
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct IssuedJwp {
+    pub jid: String,
+    /// block height the jwp expires at
+    pub expire: u64,
+}
+
 /// For handling 402, 404 and 500 error responses
 #[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
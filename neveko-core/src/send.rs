@@ -0,0 +1,145 @@
+//! Send to any address with a fee preview and password confirmation
+
+use crate::{
+    history,
+    models::*,
+    monero,
+    reqres,
+    utils,
+};
+use log::{
+    debug,
+    error,
+    info,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum SendError {
+    /// Not a valid address on this network
+    Address,
+    Amount,
+    /// Entered password does not match the wallet password
+    Password,
+    /// The dry run failed, e.g. not enough unlocked balance
+    Transfer,
+    Relay,
+    Wallet,
+    /// The app wallet is view-only
+    ReadOnly,
+}
+
+/// Subaddress indices from a comma separated list, invalid entries are skipped
+pub fn parse_indices(s: &str) -> Vec<u32> {
+    s.split(",")
+        .filter_map(|i| i.trim().parse::<u32>().ok())
+        .collect()
+}
+
+/// Validate the address and sign a transfer without relaying it. No
+///
+/// subaddress indices means any subaddress of the account can be spent from.
+pub async fn preview(
+    wallet: &monero::WalletSession,
+    address: &String,
+    amount: u128,
+    priority: monero::FeePriority,
+    account_index: u32,
+    subaddr_indices: Vec<u32>,
+) -> Result<SendPreview, SendError> {
+    if utils::is_read_only() {
+        return Err(SendError::ReadOnly);
+    }
+    if amount == 0 {
+        return Err(SendError::Amount);
+    }
+    if !utils::is_network_address(address, utils::get_network()) {
+        return Err(SendError::Address);
+    }
+    let validation = monero::validate_address(wallet, address)
+        .await
+        .map_err(|e| {
+            error!("failed to validate address: {}", e);
+            SendError::Wallet
+        })?;
+    if !validation.result.valid {
+        return Err(SendError::Address);
+    }
+    let d = reqres::Destination {
+        address: String::from(address),
+        amount,
+    };
+    let r = monero::transfer_dry_run(
+        wallet,
+        d,
+        priority,
+        account_index,
+        subaddr_indices.clone(),
+    )
+    .await
+    .map_err(|e| {
+        error!("failed to build transfer: {}", e);
+        SendError::Transfer
+    })?;
+    if r.result.tx_metadata.is_empty() {
+        return Err(SendError::Transfer);
+    }
+    debug!("previewed transfer with fee {}", r.result.fee);
+    Ok(SendPreview {
+        address: String::from(address),
+        amount: r.result.amount,
+        fee: r.result.fee,
+        priority: priority.value(),
+        account_index,
+        subaddr_indices,
+        tx_hash: r.result.tx_hash,
+        tx_metadata: r.result.tx_metadata,
+    })
+}
+
+/// Relay a previewed transfer once the wallet password is entered again.
+///
+/// Returns the hash of the relayed tx.
+pub async fn confirm(preview: &SendPreview, password: &String) -> Result<String, SendError> {
+    if utils::is_read_only() {
+        return Err(SendError::ReadOnly);
+    }
+    let wallet_password =
+        std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+    if *password != wallet_password {
+        error!("wrong password, transfer to {} not sent", preview.address);
+        return Err(SendError::Password);
+    }
+    let wallet_name = String::from(crate::APP_NAME);
+    let wallet = monero::open_wallet(&wallet_name, password)
+        .await
+        .map_err(|_| SendError::Password)?;
+    let r = monero::relay_tx(&wallet, &preview.tx_metadata)
+        .await
+        .map_err(|e| {
+            error!("failed to relay transfer: {}", e);
+            SendError::Relay
+        })?;
+    let tx_hash = if r.result.tx_hash.is_empty() {
+        String::from(&preview.tx_hash)
+    } else {
+        r.result.tx_hash
+    };
+    info!("sent {} piconero(s) to {}", preview.amount, preview.address);
+    history::annotate(&tx_hash, history::Purpose::Send, &preview.address)
+        .unwrap_or_else(|_| error!("failed to annotate send"));
+    Ok(tx_hash)
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_indices_test() {
+        assert_eq!(parse_indices("0, 2,x,,3"), vec![0, 2, 3]);
+        assert!(parse_indices("").is_empty());
+    }
+}
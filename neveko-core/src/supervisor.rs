@@ -0,0 +1,319 @@
+//! Supervisor owning the monerod, monero-wallet-rpc and core child processes
+
+use crate::error::NevekoError;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    error,
+    info,
+    warn,
+};
+use std::{
+    collections::HashMap,
+    fs::{
+        File,
+        OpenOptions,
+    },
+    process::{
+        Child,
+        Command,
+        Stdio,
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+/// How to start a supervised child
+#[derive(Clone, Debug, Default)]
+pub struct ChildSpec {
+    /// Unique name of the child, also names its log file
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// Called with the name of the child after it was restarted, e.g. to
+    ///
+    /// reset state that belonged to the old process
+    pub on_restart: Option<fn(&str)>,
+}
+
+struct Supervised {
+    spec: ChildSpec,
+    child: Option<Child>,
+    started: Instant,
+    restarts: u32,
+    restart_at: Option<Instant>,
+}
+
+lazy_static! {
+    static ref CHILDREN: Mutex<HashMap<String, Supervised>> = Mutex::new(HashMap::new());
+}
+
+static IS_WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Log file for a child, older logs are kept as `{name}.log.1` and up
+pub fn get_log_path(name: &str) -> String {
+    format!(
+        "/home/{}/.neveko/logs/{}.log",
+        std::env::var("USER").unwrap_or(String::from("user")),
+        name
+    )
+}
+
+/// Delay before the next restart, doubling per restart up to the maximum
+pub fn get_backoff(restarts: u32) -> Duration {
+    let factor = 2u64.checked_pow(restarts).unwrap_or(u64::MAX);
+    let millis = crate::SUPERVISOR_BACKOFF_BASE_MILLIS.saturating_mul(factor);
+    Duration::from_millis(millis.min(crate::SUPERVISOR_BACKOFF_MAX_MILLIS))
+}
+
+/// Copy and truncate the log once it is too large, the child keeps writing
+///
+/// to the same file handle.
+fn rotate_log(path: &str, max_bytes: u64, keep: usize) {
+    let len = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if len < max_bytes || keep == 0 {
+        return;
+    }
+    for i in (1..keep).rev() {
+        let _ = std::fs::rename(format!("{}.{}", path, i), format!("{}.{}", path, i + 1));
+    }
+    if let Err(e) = std::fs::copy(path, format!("{}.1", path)) {
+        error!("failed to rotate {}: {}", path, e);
+        return;
+    }
+    if let Ok(f) = OpenOptions::new().write(true).open(path) {
+        let _ = f.set_len(0);
+    }
+}
+
+fn open_log(name: &str) -> std::io::Result<File> {
+    let path = get_log_path(name);
+    if let Some(dir) = std::path::Path::new(&path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    rotate_log(
+        &path,
+        crate::SUPERVISOR_LOG_MAX_BYTES,
+        crate::SUPERVISOR_LOG_KEEP,
+    );
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn launch(spec: &ChildSpec) -> std::io::Result<Child> {
+    let log = open_log(&spec.name)?;
+    let child = Command::new(&spec.program)
+        .args(&spec.args)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .spawn()?;
+    info!("started {} with pid {}", spec.name, child.id());
+    Ok(child)
+}
+
+/// Start a child and keep it running until `stop` or `shutdown`. A child
+///
+/// already running under the same name is stopped first.
+pub fn spawn(spec: ChildSpec) -> Result<(), NevekoError> {
+    stop(&spec.name);
+    let child = launch(&spec).map_err(|e| {
+        error!("failed to start {}: {}", spec.name, e);
+        NevekoError::Process
+    })?;
+    let supervised = Supervised {
+        spec: spec.clone(),
+        child: Some(child),
+        started: Instant::now(),
+        restarts: 0,
+        restart_at: None,
+    };
+    CHILDREN
+        .lock()
+        .map_err(|_| NevekoError::Process)?
+        .insert(spec.name, supervised);
+    start_watch();
+    Ok(())
+}
+
+fn start_watch() {
+    if IS_WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn(async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(crate::SUPERVISOR_POLL_MILLIS)).await;
+                    check();
+                }
+            });
+        }
+        Err(_) => {
+            warn!("no async runtime, children will not be restarted");
+            IS_WATCHING.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Rotate logs and restart children that exited once their backoff has passed
+fn check() {
+    let mut children = match CHILDREN.lock() {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    let now = Instant::now();
+    for s in children.values_mut() {
+        if let Some(child) = s.child.as_mut() {
+            match child.try_wait() {
+                Ok(None) => {
+                    rotate_log(
+                        &get_log_path(&s.spec.name),
+                        crate::SUPERVISOR_LOG_MAX_BYTES,
+                        crate::SUPERVISOR_LOG_KEEP,
+                    );
+                    // forget old crashes once the child has been stable for a while
+                    let stable = Duration::from_millis(crate::SUPERVISOR_BACKOFF_MAX_MILLIS);
+                    if s.restarts > 0 && now.duration_since(s.started) >= stable {
+                        s.restarts = 0;
+                    }
+                    continue;
+                }
+                Ok(Some(status)) => {
+                    let backoff = get_backoff(s.restarts);
+                    error!(
+                        "{} exited with {}, restarting in {:?}",
+                        s.spec.name, status, backoff
+                    );
+                    s.child = None;
+                    s.restart_at = Some(now + backoff);
+                }
+                Err(e) => {
+                    error!("failed to poll {}: {}", s.spec.name, e);
+                    continue;
+                }
+            }
+        }
+        if s.restart_at.map(|t| now < t).unwrap_or(false) {
+            continue;
+        }
+        s.restarts += 1;
+        s.started = now;
+        match launch(&s.spec) {
+            Ok(child) => {
+                s.child = Some(child);
+                s.restart_at = None;
+                if let Some(on_restart) = s.spec.on_restart {
+                    on_restart(&s.spec.name);
+                }
+            }
+            Err(e) => {
+                let backoff = get_backoff(s.restarts);
+                error!("failed to restart {}: {}, retrying in {:?}", s.spec.name, e, backoff);
+                s.restart_at = Some(now + backoff);
+            }
+        }
+    }
+}
+
+/// Ask a child to exit with SIGTERM, then kill it if it does not in time.
+///
+/// Blocks for up to `SUPERVISOR_STOP_TIMEOUT_SECS`, keep it off event loops.
+fn terminate(name: &str, mut child: Child) {
+    let pid = child.id();
+    info!("stopping {} with pid {}", name, pid);
+    let output = Command::new("kill")
+        .args(["-TERM", &format!("{}", pid)])
+        .output();
+    debug!("{:?}", output);
+    let deadline = Instant::now() + Duration::from_secs(crate::SUPERVISOR_STOP_TIMEOUT_SECS);
+    while Instant::now() < deadline {
+        match child.try_wait() {
+            Ok(Some(_)) => return,
+            Ok(None) => std::thread::sleep(Duration::from_millis(100)),
+            Err(_) => break,
+        }
+    }
+    warn!("{} did not stop in time, killing it", name);
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Stop a child by name without restarting it
+pub fn stop(name: &str) {
+    let removed = match CHILDREN.lock() {
+        Ok(mut c) => c.remove(name),
+        Err(_) => None,
+    };
+    if let Some(child) = removed.and_then(|s| s.child) {
+        terminate(name, child);
+    }
+}
+
+/// Stop every child this process started
+pub fn shutdown() {
+    info!("stopping child processes");
+    let children: Vec<Supervised> = match CHILDREN.lock() {
+        Ok(mut c) => c.drain().map(|(_, s)| s).collect(),
+        Err(_) => Vec::new(),
+    };
+    for s in children {
+        if let Some(child) = s.child {
+            terminate(&s.spec.name, child);
+        }
+    }
+}
+
+/// Whether a child is currently up, `false` while waiting on a restart
+pub fn is_running(name: &str) -> bool {
+    match CHILDREN.lock() {
+        Ok(c) => c.get(name).map(|s| s.child.is_some()).unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+// Tests
+//-------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_backoff_test() {
+        let base = crate::SUPERVISOR_BACKOFF_BASE_MILLIS;
+        assert_eq!(get_backoff(0), Duration::from_millis(base));
+        assert_eq!(get_backoff(2), Duration::from_millis(base * 4));
+        let max = Duration::from_millis(crate::SUPERVISOR_BACKOFF_MAX_MILLIS);
+        assert_eq!(get_backoff(30), max);
+        assert_eq!(get_backoff(100), max);
+    }
+
+    #[test]
+    fn rotate_log_test() {
+        let dir = std::env::temp_dir().join(format!("neveko-rotate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.log");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "first").unwrap();
+        rotate_log(path, 4, 2);
+        std::fs::write(path, "second").unwrap();
+        rotate_log(path, 4, 2);
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(format!("{}.1", path)).unwrap(), "second");
+        assert_eq!(std::fs::read_to_string(format!("{}.2", path)).unwrap(), "first");
+        // small logs are left alone
+        std::fs::write(path, "abc").unwrap();
+        rotate_log(path, 4, 2);
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "abc");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,7 @@
 //! Generic functions for startup and convenience
 
 use crate::{
+    account,
     args,
     contact,
     db::{
@@ -8,7 +9,10 @@ use crate::{
         DATABASE_LOCK,
     },
     dispute,
-    error::NevekoError,
+    error::{
+        NevekoError,
+        WalletRpcError,
+    },
     i2p::{
         self,
         ProxyStatus,
@@ -17,7 +21,12 @@ use crate::{
     models,
     monero,
     neveko25519,
+    node,
+    presence,
+    proof,
+    renewal,
     reqres,
+    supervisor,
 };
 use clap::Parser;
 use kn0sys_lmdb_rs::MdbError;
@@ -31,9 +40,20 @@ use rand_core::RngCore;
 use rocket::serde::json::Json;
 extern crate rpassword;
 use rpassword::read_password;
-use std::io::Write;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+};
 
 const ESTIMATE_FEE_FAILURE: u128 = 0;
+const STANDARD_ADDRESS_LENGTH: usize = 95;
+const INTEGRATED_ADDRESS_LENGTH: usize = 106;
+/// Base fee multipliers for each priority tier used by monero-wallet-rpc
+const FEE_MULTIPLIERS: [u64; 4] = [1, 5, 25, 1000];
 
 /// Struct for the vendor / contact status window
 pub struct ContactStatus {
@@ -47,6 +67,10 @@ pub struct ContactStatus {
     pub is_vendor: bool,
     /// JSON Web Proof of current status check
     pub jwp: String,
+    /// average latency in milliseconds from the presence monitor
+    pub latency: u64,
+    /// human readable date the presence monitor last reached the contact
+    pub last_seen: String,
     /// Alias for contact
     pub nick: String,
     /// transaction proof signature of current status check
@@ -61,13 +85,29 @@ impl Default for ContactStatus {
             i2p: String::new(),
             is_vendor: false,
             jwp: String::new(),
+            latency: 0,
+            last_seen: String::new(),
             nick: String::from("anon"),
             txp: String::new(),
         }
     }
 }
 
+/// Seed or keys the app wallet is restored from on first start. A missing
+///
+/// spend key restores a view-only wallet.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WalletRestore {
+    pub seed: String,
+    pub address: String,
+    pub view_key: String,
+    pub spend_key: String,
+    pub restore_height: u64,
+}
+
 /// Handles the state for the connection manager popup
+#[derive(Clone)]
 pub struct Connections {
     pub blockchain_dir: String,
     pub daemon_host: String,
@@ -75,11 +115,15 @@ pub struct Connections {
     pub i2p_socks_host: String,
     pub is_remote_node: bool,
     pub is_i2p_advanced: bool,
-    pub mainnet: bool,
+    pub network: Network,
     pub monero_location: String,
+    /// Comma separated remote nodes to fail over to
+    pub remote_nodes: String,
     pub rpc_credential: String,
     pub rpc_username: String,
     pub rpc_host: String,
+    /// monero-wallet-rpc workers on consecutive ports from `rpc_host`
+    pub rpc_workers: u16,
 }
 
 impl Default for Connections {
@@ -91,11 +135,13 @@ impl Default for Connections {
             i2p_socks_host: String::from("http://127.0.0.1:9056"),
             is_remote_node: false,
             is_i2p_advanced: false,
-            mainnet: true,
+            network: Network::Mainnet,
             monero_location: String::from("monero-x86_64-linux-gnu-v0.18.3.4"),
+            remote_nodes: String::new(),
             rpc_credential: String::from("pass"),
             rpc_username: String::from("user"),
             rpc_host: String::from("http://127.0.0.1:18083"),
+            rpc_workers: 1,
         }
     }
 }
@@ -130,9 +176,80 @@ impl ReleaseEnvironment {
     }
 }
 
+/// Monero network monerod, monero-wallet-rpc and LMDB are set up for
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Network {
+    Mainnet,
+    Stagenet,
+    Testnet,
+}
+
+impl Network {
+    pub const ALL: [Network; 3] = [
+        Network::Mainnet,
+        Network::Stagenet,
+        Network::Testnet,
+    ];
+
+    pub fn value(&self) -> String {
+        match *self {
+            Network::Mainnet => String::from("mainnet"),
+            Network::Stagenet => String::from("stagenet"),
+            Network::Testnet => String::from("testnet"),
+        }
+    }
+
+    pub fn from_value(s: &str) -> Option<Network> {
+        Network::ALL.into_iter().find(|n| n.value() == s)
+    }
+
+    /// Flag for monerod and monero-wallet-rpc, mainnet has none
+    pub fn flag(&self) -> Option<&'static str> {
+        match *self {
+            Network::Mainnet => None,
+            Network::Stagenet => Some("--stagenet"),
+            Network::Testnet => Some("--testnet"),
+        }
+    }
+
+    /// LMDB directory under `~/.neveko`. Mainnet and stagenet keep the
+    ///
+    /// `production` and `development` directories of earlier releases.
+    pub fn data_dir(&self) -> String {
+        match *self {
+            Network::Mainnet => ReleaseEnvironment::Production.value(),
+            Network::Stagenet => ReleaseEnvironment::Development.value(),
+            Network::Testnet => String::from("testnet"),
+        }
+    }
+
+    /// Wallet directory relative to the home directory
+    pub fn wallet_dir(&self) -> String {
+        match *self {
+            Network::Mainnet => String::from(".neveko/wallet/"),
+            Network::Stagenet => String::from(".neveko/stagenet/wallet/"),
+            Network::Testnet => String::from(".neveko/testnet/wallet/"),
+        }
+    }
+
+    /// First base58 character of standard, integrated and subaddresses
+    fn address_prefixes(&self) -> [char; 3] {
+        match *self {
+            Network::Mainnet => ['4', '4', '8'],
+            Network::Stagenet => ['5', '5', '7'],
+            Network::Testnet => ['9', 'A', 'B'],
+        }
+    }
+}
+
 /// start core module from gui
 pub fn start_core(conn: &Connections) {
-    let env = if !conn.mainnet { "dev" } else { "prod" };
+    let env = if conn.network != Network::Mainnet {
+        "dev"
+    } else {
+        "prod"
+    };
+    let network = conn.network.value();
     let remote_node = if !conn.is_remote_node {
         "--full-node"
     } else {
@@ -143,6 +260,7 @@ pub fn start_core(conn: &Connections) {
     } else {
         "--i2p-advanced"
     };
+    let rpc_workers = format!("{}", conn.rpc_workers);
     let args = [
         "--monero-location",
         &conn.monero_location,
@@ -150,14 +268,20 @@ pub fn start_core(conn: &Connections) {
         &conn.blockchain_dir,
         "--monero-rpc-host",
         &conn.rpc_host,
+        "--monero-rpc-workers",
+        &rpc_workers,
         "--monero-rpc-daemon",
         &conn.daemon_host,
+        "--monero-remote-nodes",
+        &conn.remote_nodes,
         "--monero-rpc-username",
         &conn.rpc_username,
         "--monero-rpc-cred",
         &conn.rpc_credential,
         "-r",
         env,
+        "--network",
+        &network,
         remote_node,
         i2p_advanced,
         "--i2p-proxy-host",
@@ -165,6 +289,8 @@ pub fn start_core(conn: &Connections) {
         "--i2p-socks-proxy-host",
         &conn.i2p_socks_host,
     ];
+    // wallets are pinned to workers by their count, the gui must agree with core
+    std::env::set_var(crate::MONERO_WALLET_RPC_WORKERS, &rpc_workers);
     if conn.is_i2p_advanced {
         // set the i2p proxy host for advanced user re-use
         std::env::set_var(crate::NEVEKO_I2P_PROXY_HOST, conn.i2p_proxy_host.clone());
@@ -175,11 +301,15 @@ pub fn start_core(conn: &Connections) {
         std::env::set_var(crate::MONERO_WALLET_RPC_HOST, conn.rpc_host.clone());
         std::env::set_var(crate::GUI_REMOTE_NODE, crate::GUI_SET_REMOTE_NODE)
     }
-    let output = std::process::Command::new("./neveko")
-        .args(args)
-        .spawn()
-        .expect("core module failed to start");
-    debug!("{:?}", output.stdout);
+    let spec = supervisor::ChildSpec {
+        name: String::from("neveko"),
+        program: String::from("./neveko"),
+        args: args.into_iter().map(String::from).collect(),
+        ..Default::default()
+    };
+    if supervisor::spawn(spec).is_err() {
+        error!("core module failed to start");
+    }
 }
 
 /// Using remote node?
@@ -210,6 +340,29 @@ pub fn get_release_env() -> ReleaseEnvironment {
     }
 }
 
+/// Monero network from `--network`, falling back to stagenet for dev
+///
+/// and mainnet for prod.
+pub fn get_network() -> Network {
+    let args = args::Args::parse();
+    args.network.unwrap_or(match get_release_env() {
+        ReleaseEnvironment::Development => Network::Stagenet,
+        ReleaseEnvironment::Production => Network::Mainnet,
+    })
+}
+
+/// Whether an address belongs to the network. Only the prefix and length
+///
+/// are checked, `monero::validate_address` decodes the rest.
+pub fn is_network_address(address: &str, network: Network) -> bool {
+    let is_valid_length =
+        address.len() == STANDARD_ADDRESS_LENGTH || address.len() == INTEGRATED_ADDRESS_LENGTH;
+    match address.chars().next() {
+        Some(prefix) => is_valid_length && network.address_prefixes().contains(&prefix),
+        None => false,
+    }
+}
+
 /// app port
 pub fn get_app_port() -> u16 {
     let args = args::Args::parse();
@@ -269,6 +422,48 @@ pub fn get_payment_threshold() -> u128 {
     args.payment_threshold
 }
 
+/// market-read scope jwp payment threshold
+pub fn get_market_read_payment_threshold() -> u128 {
+    let args = args::Args::parse();
+    args.market_read_payment_threshold
+}
+
+/// market-read scope jwp expiration in blocks
+pub fn get_market_read_conf_threshold() -> u64 {
+    let args = args::Args::parse();
+    args.market_read_confirmation_threshold
+}
+
+/// market-order scope jwp payment threshold
+pub fn get_market_order_payment_threshold() -> u128 {
+    let args = args::Args::parse();
+    args.market_order_payment_threshold
+}
+
+/// market-order scope jwp expiration in blocks
+pub fn get_market_order_conf_threshold() -> u64 {
+    let args = args::Args::parse();
+    args.market_order_confirmation_threshold
+}
+
+/// message scope price debited from credit per call
+pub fn get_message_call_price() -> u128 {
+    let args = args::Args::parse();
+    args.message_call_price
+}
+
+/// market-read scope price debited from credit per call
+pub fn get_market_read_call_price() -> u128 {
+    let args = args::Args::parse();
+    args.market_read_call_price
+}
+
+/// market-order scope price debited from credit per call
+pub fn get_market_order_call_price() -> u128 {
+    let args = args::Args::parse();
+    args.market_order_call_price
+}
+
 /// convert contact to json so only core module does the work
 pub fn contact_to_json(c: &models::Contact) -> Json<models::Contact> {
     let r_contact: models::Contact = models::Contact {
@@ -349,39 +544,196 @@ pub const fn image_limit() -> usize {
 /// Handles panic! for missing wallet directory
 fn create_wallet_dir() {
     let file_path = format!(
-        "/home/{}/.neveko",
-        std::env::var("USER").unwrap_or(String::from("user"))
+        "/home/{}/{}",
+        std::env::var("USER").unwrap_or(String::from("user")),
+        get_network().wallet_dir()
     );
-    let s_output = std::process::Command::new("mkdir")
-        .args(["-p", &format!("{}/stagenet/wallet", file_path)])
-        .spawn()
-        .expect("failed to create dir");
-    debug!("{:?}", s_output);
-    let m_output = std::process::Command::new("mkdir")
-        .args(["-p", &format!("{}/wallet", file_path)])
+    let output = std::process::Command::new("mkdir")
+        .args(["-p", &file_path])
         .spawn()
         .expect("failed to create dir");
-    debug!("{:?}", m_output);
+    debug!("{:?}", output);
 }
 
-/// Generate application wallet at startup if none exist
-async fn gen_app_wallet(password: &String) {
+/// Generate application wallet at startup if none exist. Fails with
+///
+/// `NevekoError::Network` when the wallet belongs to another network.
+async fn gen_app_wallet(password: &String) -> Result<(), NevekoError> {
     info!("fetching application wallet");
     let filename = String::from(crate::APP_NAME);
-    let mut m_wallet = monero::open_wallet(&filename, password).await;
-    if !m_wallet {
-        m_wallet = monero::create_wallet(&filename, password).await;
-        if !m_wallet {
-            error!("failed to create wallet")
-        } else {
-            m_wallet = monero::open_wallet(&filename, password).await;
-            if m_wallet {
-                let m_address: reqres::XmrRpcAddressResponse = monero::get_address().await;
-                info!("app wallet address: {}", m_address.result.address)
+    // secrets never outlive startup even when the wallet already exists
+    let restore = take_wallet_restore();
+    let wallet = match monero::open_wallet(&filename, password).await {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            debug!("failed to open wallet: {}", e);
+            match create_app_wallet(&filename, password, &restore).await {
+                Ok(wallet) => {
+                    let m_address = monero::get_address(&wallet).await.unwrap_or_default();
+                    info!("app wallet address: {}", m_address.result.address);
+                    wallet
+                }
+                Err(e) => {
+                    error!("failed to create wallet: {}", e);
+                    return Err(NevekoError::MoneroRpc);
+                }
             }
         }
+    };
+    let is_view_only = monero::is_view_only(&wallet).await.unwrap_or_else(|e| {
+        error!("failed to check for spend key: {}", e);
+        false
+    });
+    let network = get_network();
+    match monero::get_address(&wallet).await {
+        Ok(address) if !is_network_address(&address.result.address, network) => {
+            error!("app wallet is not a {} wallet", network.value());
+            return Err(NevekoError::Network);
+        }
+        Ok(_) => (),
+        // not a mismatch, the rest of the setup still applies
+        Err(e) => error!("failed to check the app wallet network: {}", e),
+    }
+    account::init(&wallet)
+        .await
+        .unwrap_or_else(|_| error!("failed to set up wallet accounts"));
+    drop(wallet);
+    if is_view_only {
+        warn!("app wallet is view-only, running in read-only mode");
     }
-    monero::close_wallet(&filename, password).await;
+    set_read_only(is_view_only).unwrap_or_else(|_| error!("failed to set read-only mode"));
+    Ok(())
+}
+
+/// Create the app wallet on first run, restoring it from a seed or keys
+///
+/// when a restore file was left for it.
+async fn create_app_wallet(
+    filename: &String,
+    password: &String,
+    restore: &WalletRestore,
+) -> Result<monero::WalletSession, WalletRpcError> {
+    let height = restore.restore_height;
+    if !restore.seed.is_empty() {
+        info!("restoring app wallet from seed at height {}", height);
+        monero::restore_deterministic_wallet(filename, password, &restore.seed, height).await
+    } else if !restore.address.is_empty() && !restore.view_key.is_empty() {
+        info!("restoring app wallet from keys at height {}", height);
+        monero::generate_from_keys(
+            filename,
+            password,
+            &restore.address,
+            &restore.view_key,
+            &restore.spend_key,
+            height,
+        )
+        .await
+    } else {
+        monero::create_wallet(filename, password).await
+    }
+}
+
+fn get_wallet_restore_path() -> String {
+    format!(
+        "/home/{}/.neveko/{}",
+        std::env::var("USER").unwrap_or(String::from("user")),
+        crate::WALLET_RESTORE_FILE
+    )
+}
+
+/// Leave restore secrets for core in a file only the user can read. Unlike
+///
+/// the environment they do not show up in `/proc/<pid>/environ`.
+pub fn set_wallet_restore(restore: &WalletRestore) -> Result<(), NevekoError> {
+    clear_wallet_restore();
+    let path = get_wallet_restore_path();
+    let v = serde_json::to_vec(restore).map_err(|_| NevekoError::Unknown)?;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|e| {
+            error!("failed to create {}: {}", path, e);
+            NevekoError::Unknown
+        })?;
+    file.write_all(&v).map_err(|_| NevekoError::Unknown)?;
+    Ok(())
+}
+
+/// Read and remove the restore file, empty when there is none
+fn take_wallet_restore() -> WalletRestore {
+    let path = get_wallet_restore_path();
+    let r = match std::fs::read(&path) {
+        Ok(r) => r,
+        Err(_) => return Default::default(),
+    };
+    clear_wallet_restore();
+    serde_json::from_slice(&r).unwrap_or_else(|e| {
+        error!("invalid wallet restore file: {}", e);
+        Default::default()
+    })
+}
+
+/// Remove wallet restore secrets once they are used
+pub fn clear_wallet_restore() {
+    let path = get_wallet_restore_path();
+    if let Err(e) = std::fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            error!("failed to remove {}: {}", path, e);
+        }
+    }
+}
+
+fn set_read_only(is_read_only: bool) -> Result<(), NevekoError> {
+    let db = &DATABASE_LOCK;
+    let k = crate::READ_ONLY_DB_KEY;
+    let v = bincode::serialize(&is_read_only).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(())
+}
+
+/// Read-only mode is on when the app wallet is view-only
+pub fn is_read_only() -> bool {
+    let db = &DATABASE_LOCK;
+    let k = crate::READ_ONLY_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    bincode::deserialize(&r[..]).unwrap_or(false)
+}
+
+/// Tag LMDB with the network on first start and refuse a database that
+///
+/// was set up for another network.
+fn check_network() -> Result<(), NevekoError> {
+    let network = get_network();
+    let db = &DATABASE_LOCK;
+    let k = crate::NETWORK_DB_KEY;
+    let r = db::DatabaseEnvironment::read(&db.env, &db.handle, &k.as_bytes().to_vec())
+        .unwrap_or_default();
+    if r.is_empty() {
+        info!("tagging database with network: {}", network.value());
+        let v = bincode::serialize(&network.value()).unwrap_or_default();
+        db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
+            .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+        return Ok(());
+    }
+    let tag: String = bincode::deserialize(&r[..]).unwrap_or_default();
+    if tag != network.value() {
+        error!("database is tagged {} but network is {}", tag, network.value());
+        return Err(NevekoError::Network);
+    }
+    Ok(())
+}
+
+/// Refuse functions that need spend keys in read-only mode
+pub fn check_spend_keys() -> Result<(), NevekoError> {
+    if is_read_only() {
+        error!("app wallet is view-only, spend keys are not available");
+        return Err(NevekoError::ReadOnly);
+    }
+    Ok(())
 }
 
 /// Secret keys for signing internal/external auth tokens
@@ -421,9 +773,9 @@ fn gen_signing_keys() -> Result<(), NevekoError> {
     Ok(())
 }
 
-/// TODO(c2m): add a button to gui to call this
+/// Delete the signing keys, every issued JWP, credit and bearer token
 ///
-/// dont' forget to generate new keys as well
+/// becomes invalid. Call `regenerate_signing_keys` to replace them.
 pub fn revoke_signing_keys() -> Result<(), NevekoError> {
     let db = &DATABASE_LOCK;
     db::DatabaseEnvironment::delete(&db.env, &db.handle, crate::NEVEKO_JWT_SECRET_KEY.as_bytes())
@@ -434,6 +786,13 @@ pub fn revoke_signing_keys() -> Result<(), NevekoError> {
     Ok(())
 }
 
+/// Revoke the current signing keys and generate new ones
+pub fn regenerate_signing_keys() -> Result<(), NevekoError> {
+    info!("regenerating signing keys");
+    revoke_signing_keys()?;
+    gen_signing_keys()
+}
+
 pub fn get_jwt_secret_key() -> Result<String, NevekoError> {
     let db = &DATABASE_LOCK;
     let r = db::DatabaseEnvironment::read(
@@ -514,7 +873,9 @@ pub async fn start_up() -> Result<(), NevekoError> {
     .map_err(|_| NevekoError::Database(MdbError::Panic))?;
 
     info!("neveko is starting up");
+    check_network()?;
     reset_i2p_status()?;
+    node::reset()?;
     warn!("monero multisig is experimental and usage of neveko may lead to loss of funds");
     let args = args::Args::parse();
     if args.clear_fts {
@@ -524,16 +885,14 @@ pub async fn start_up() -> Result<(), NevekoError> {
         clear_disputes()?;
     }
     gen_signing_keys()?;
-    if !is_using_remote_node() {
-        let _ = monero::start_daemon();
+    if !is_using_remote_node() && monero::start_daemon().is_ok() {
+        monero::wait_for_daemon().await;
     }
     create_wallet_dir();
-    // wait for daemon for a bit
-    tokio::time::sleep(std::time::Duration::new(5, 0)).await;
     monero::start_rpc();
-    // wait for rpc server for a bit
-    tokio::time::sleep(std::time::Duration::new(5, 0)).await;
-    monero::check_rpc_connection().await;
+    if !monero::wait_for_rpc().await {
+        monero::check_rpc_connection().await;
+    }
     let mut wallet_password = std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or_default();
     if wallet_password.is_empty() {
         print!(
@@ -552,6 +911,20 @@ pub async fn start_up() -> Result<(), NevekoError> {
     }
     // start async background tasks here
     {
+        tokio::spawn(async move {
+            let _ = presence::monitor().await;
+        });
+        if is_using_remote_node() {
+            tokio::spawn(async move {
+                let _ = node::monitor().await;
+            });
+        }
+        tokio::spawn(async move {
+            let _ = proof::cache_height().await;
+        });
+        tokio::spawn(async move {
+            let _ = renewal::renew_jwps().await;
+        });
         tokio::spawn(async move {
             let _ = message::retry_fts().await;
             // wait for the i2p http proxy tunnel since remote nodes are forced over i2p
@@ -567,45 +940,36 @@ pub async fn start_up() -> Result<(), NevekoError> {
                     std::thread::sleep(std::time::Duration::from_secs(60));
                 }
             }
-            gen_app_wallet(&wallet_password).await;
+            match gen_app_wallet(&wallet_password).await {
+                Err(NevekoError::Network) => {
+                    error!("stopping neveko, the app wallet is on another network");
+                    let _ = tokio::task::spawn_blocking(kill_child_processes).await;
+                    std::process::exit(1);
+                }
+                Err(_) => error!("failed to set up the app wallet"),
+                Ok(_) => (),
+            }
             generate_nmpk()
                 .await
                 .unwrap_or_else(|_| log::debug!("unable to generate neveko message keys"));
             let _ = dispute::settle_dispute().await;
         });
     }
-    info!("{} - neveko is online", env);
+    info!("{} {} - neveko is online", env, get_network().value());
     Ok(())
 }
 
 /// TODO(?): get rid of this after implementing monero bindings
 ///
-/// Called by gui for cleaning up monerod, rpc, etc.
+/// Called by gui and core for cleaning up monerod, rpc, etc.
+///
+/// Only children started by this process are stopped, core stops its own
 ///
-/// pass true from gui connection manager so not to kill neveko
-pub fn kill_child_processes(cm: bool) {
-    info!("stopping child processes");
+/// monerod and monero-wallet-rpc when the gui stops it.
+pub fn kill_child_processes() {
     // TODO(c2m): prompt on gui letting user determine what background
     //            services to keep running
-    if cm {
-        let xmrd_output = std::process::Command::new("pkill")
-            .arg("monerod")
-            .spawn()
-            .expect("monerod failed to stop");
-        debug!("{:?}", xmrd_output.stdout);
-    }
-    if !cm {
-        let neveko_output = std::process::Command::new("pkill")
-            .arg("neveko")
-            .spawn()
-            .expect("neveko failed to stop");
-        debug!("{:?}", neveko_output.stdout);
-    }
-    let rpc_output = std::process::Command::new("killall")
-        .arg("monero-wallet-rpc")
-        .spawn()
-        .expect("monero-wallet-rpc failed to stop");
-    debug!("{:?}", rpc_output.stdout);
+    supervisor::shutdown();
 }
 
 /// We can restart fts from since it gets terminated when empty
@@ -640,103 +1004,119 @@ fn clear_disputes() -> Result<(), NevekoError> {
     Ok(())
 }
 
-/// ### The highly ineffecient fee estimator.
-///
-/// Get the current height. Start fetching blocks
-///
-/// and checking the number of transactions. If
-///
-/// there were non-coinbase transactions in the block
-///
-/// extract the `txnFee` from the `as_json` field.
+/// Absolute fee for a typical transfer from a per byte fee, rounded up to
 ///
-/// Once we have accumulated n>=30 fees paid return the
-///
-/// average fee paid from the most recent 30 transactions.
-///
-/// Note, it may take more than one block to do this,
+/// the quantization mask the daemon reports.
+fn get_tx_fee(per_byte_fee: u64, quantization_mask: u64) -> u128 {
+    let fee = per_byte_fee as u128 * crate::FEE_ESTIMATE_TX_WEIGHT as u128;
+    let mask = std::cmp::max(quantization_mask, 1) as u128;
+    fee.div_ceil(mask) * mask
+}
+
+fn is_fee_estimate_fresh(estimate: &models::FeeEstimate, height: u64, now: i64) -> bool {
+    !estimate.fees.is_empty()
+        && estimate.height == height
+        && now - estimate.created < crate::FEE_ESTIMATE_TTL_SECS
+}
+
+fn find_fee_estimate() -> models::FeeEstimate {
+    let db = &DATABASE_LOCK;
+    let r = db::DatabaseEnvironment::read(
+        &db.env,
+        &db.handle,
+        &crate::FEE_ESTIMATE_DB_KEY.as_bytes().to_vec(),
+    )
+    .unwrap_or_default();
+    bincode::deserialize(&r[..]).unwrap_or_default()
+}
+
+/// Fee estimates for each `monero::FeePriority` from the daemon's
 ///
-/// especially on stagenet. Over i2p let's cheat and just FFE
+/// `get_fee_estimate`. Served from the db while the chain height is
 ///
-/// (find first fee).
-pub async fn estimate_fee() -> u128 {
-    // loop intializer
-    let mut height: u64 = 0;
-    let mut count: u64 = 1;
-    let mut v_fee: Vec<u128> = Vec::new();
-    let mut r_height: reqres::XmrDaemonGetHeightResponse = Default::default();
+/// unchanged and the estimate is younger than the ttl.
+pub async fn get_fee_estimate() -> Result<models::FeeEstimate, NevekoError> {
     let remote_var = std::env::var(crate::GUI_REMOTE_NODE).unwrap_or_default();
     let remote_set = remote_var == *crate::GUI_SET_REMOTE_NODE;
-    if remote_set {
-        let p_height = monero::p_get_height().await;
-        r_height = p_height.unwrap_or(r_height);
-    } else {
-        r_height = monero::get_height().await;
+    let mut height = proof::get_cached_height();
+    if height == 0 {
+        height = if remote_set {
+            monero::p_get_height().await.unwrap_or_default().height
+        } else {
+            monero::get_height().await.height
+        };
     }
-    if r_height.height == ESTIMATE_FEE_FAILURE as u64 {
-        error!("error fetching height");
-        return ESTIMATE_FEE_FAILURE;
+    let now = chrono::offset::Utc::now().timestamp();
+    let cached = find_fee_estimate();
+    if is_fee_estimate_fresh(&cached, height, now) {
+        debug!("using cached fee estimate from height {}", height);
+        return Ok(cached);
     }
-    loop {
-        debug!("current height: {}", height);
-        if v_fee.len() >= 30 {
-            break;
-        }
-        // TODO(?): determine a more effecient fix than this for slow fee estimation
-        // over i2p
-        if !v_fee.is_empty() && remote_set {
-            break;
-        }
-        height = r_height.height - count;
-        let mut block: reqres::XmrDaemonGetBlockResponse = Default::default();
-        if remote_var == *crate::GUI_SET_REMOTE_NODE {
-            let p_block = monero::p_get_block(height).await;
-            block = p_block.unwrap_or(block);
-        } else {
-            block = monero::get_block(height).await;
-        }
-        if block.result.block_header.num_txes > 0 {
-            let tx_hashes: Option<Vec<String>> = block.result.tx_hashes;
-            let mut transactions: reqres::XmrDaemonGetTransactionsResponse = Default::default();
-            if remote_set {
-                let p_txs = monero::p_get_transactions(tx_hashes.unwrap()).await;
-                transactions = p_txs.unwrap_or(transactions);
-            } else {
-                transactions = monero::get_transactions(tx_hashes.unwrap()).await;
-            }
-            for tx in transactions.txs_as_json {
-                let pre_fee_split = tx.split("txnFee\":");
-                let mut v1: Vec<String> = pre_fee_split.map(String::from).collect();
-                let fee_split = v1.remove(1);
-                let post_fee_split = fee_split.split(",");
-                let mut v2: Vec<String> = post_fee_split.map(String::from).collect();
-                let fee: u128 = v2.remove(0).trim().parse::<u128>().unwrap_or_default();
-                v_fee.push(fee);
-            }
-        }
-        count += 1;
+    let r = if remote_set {
+        monero::p_get_fee_estimate().await.unwrap_or_default()
+    } else {
+        monero::get_fee_estimate().await
+    };
+    if r.result.fee == 0 {
+        error!("error fetching fee estimate");
+        return Err(NevekoError::MoneroDaemon);
+    }
+    // older daemons only report the base fee, scale it like the wallet does
+    let per_byte: Vec<u64> = if r.result.fees.len() >= monero::FeePriority::ALL.len() {
+        r.result.fees
+    } else {
+        FEE_MULTIPLIERS.iter().map(|m| r.result.fee * m).collect()
+    };
+    let estimate = models::FeeEstimate {
+        created: now,
+        height,
+        fees: per_byte
+            .iter()
+            .take(monero::FeePriority::ALL.len())
+            .map(|f| get_tx_fee(*f, r.result.quantization_mask))
+            .collect(),
+    };
+    let db = &DATABASE_LOCK;
+    let v = bincode::serialize(&estimate).unwrap_or_default();
+    db::write_chunks(&db.env, &db.handle, crate::FEE_ESTIMATE_DB_KEY.as_bytes(), &v)
+        .map_err(|_| NevekoError::Database(MdbError::Panic))?;
+    Ok(estimate)
+}
+
+/// Estimated fee in piconeros of a typical transfer at a priority
+pub async fn estimate_fee(priority: monero::FeePriority) -> u128 {
+    match get_fee_estimate().await {
+        Ok(estimate) => estimate
+            .fees
+            .get(priority.value() as usize - 1)
+            .cloned()
+            .unwrap_or(ESTIMATE_FEE_FAILURE),
+        Err(_) => ESTIMATE_FEE_FAILURE,
     }
-    v_fee.iter().sum::<u128>() / v_fee.len() as u128
 }
 
 /// Combine the results `estimate_fee()` and `get_balance()` to
 ///
 /// determine whether or not a transfer for a given invoice is possible.
+///
+/// A transfer spends from one account so one of them has to cover it.
 pub async fn can_transfer(invoice: u128) -> bool {
     let wallet_name = String::from(crate::APP_NAME);
     let wallet_password =
         std::env::var(crate::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-    monero::open_wallet(&wallet_name, &wallet_password).await;
-    let balance = monero::get_balance().await;
-    monero::close_wallet(&wallet_name, &wallet_password).await;
-    let fee = estimate_fee().await;
+    let balance = match monero::open_wallet(&wallet_name, &wallet_password).await {
+        Ok(wallet) => monero::get_balance(&wallet).await.unwrap_or_default(),
+        Err(_) => return false,
+    };
+    let fee = estimate_fee(monero::FeePriority::Normal).await;
     if fee == ESTIMATE_FEE_FAILURE {
         return false;
     }
+    let balances = account::get_unlocked_balances(&balance.result);
     debug!("fee estimated to: {}", fee);
-    debug!("balance: {}", balance.result.unlocked_balance);
+    debug!("balances: {:?}", balances);
     debug!("fee + invoice = {}", invoice + fee);
-    balance.result.unlocked_balance > (fee + invoice)
+    account::select_source(&balances, fee + invoice).is_some()
 }
 
 /// Gui toggle for vendor mode
@@ -823,6 +1203,21 @@ mod tests {
         assert_eq!(expected, actual)
     }
 
+    #[test]
+    fn network_address_test() {
+        let stagenet = format!("5{}", "A".repeat(STANDARD_ADDRESS_LENGTH - 1));
+        let subaddress = format!("8{}", "A".repeat(STANDARD_ADDRESS_LENGTH - 1));
+        let integrated = format!("A{}", "A".repeat(INTEGRATED_ADDRESS_LENGTH - 1));
+        assert!(is_network_address(&stagenet, Network::Stagenet));
+        assert!(!is_network_address(&stagenet, Network::Mainnet));
+        assert!(is_network_address(&subaddress, Network::Mainnet));
+        assert!(is_network_address(&integrated, Network::Testnet));
+        assert!(!is_network_address(&stagenet[1..], Network::Stagenet));
+        assert!(!is_network_address("", Network::Mainnet));
+        assert_eq!(Network::from_value("testnet"), Some(Network::Testnet));
+        assert!(Network::from_value("regtest").is_none());
+    }
+
     #[test]
     fn app_port_test() {
         let actual: u16 = get_app_port();
@@ -887,6 +1282,28 @@ mod tests {
         assert_eq!(expected.mid, actual.mid)
     }
 
+    #[test]
+    fn get_tx_fee_test() {
+        let weight = crate::FEE_ESTIMATE_TX_WEIGHT as u128;
+        assert_eq!(get_tx_fee(20, 1), 20 * weight);
+        assert_eq!(get_tx_fee(20, 0), 20 * weight);
+        assert_eq!(get_tx_fee(1, 10000) % 10000, 0);
+        assert!(get_tx_fee(1, 10000) >= weight);
+    }
+
+    #[test]
+    fn is_fee_estimate_fresh_test() {
+        let estimate = models::FeeEstimate {
+            created: 100,
+            height: 10,
+            fees: vec![1, 2, 3, 4],
+        };
+        assert!(is_fee_estimate_fresh(&estimate, 10, 101));
+        assert!(!is_fee_estimate_fresh(&estimate, 11, 101));
+        assert!(!is_fee_estimate_fresh(&estimate, 10, 100 + crate::FEE_ESTIMATE_TTL_SECS));
+        assert!(!is_fee_estimate_fresh(&Default::default(), 0, 0));
+    }
+
     #[test]
     fn can_transfer_test() {
         use tokio::runtime::Runtime;
@@ -41,8 +41,8 @@ pub struct AddressBookApp {
     approve_payment: bool,
     added: bool,
     can_transfer: bool,
-    can_transfer_tx: Sender<bool>,
-    can_transfer_rx: Receiver<bool>,
+    can_transfer_tx: Sender<(bool, models::FeeEstimate)>,
+    can_transfer_rx: Receiver<(bool, models::FeeEstimate)>,
     compose: Compose,
     contact: String,
     find_contact: String,
@@ -74,6 +74,7 @@ pub struct AddressBookApp {
     s_contact: models::Contact,
     s_invoice: reqres::Invoice,
     s_added_contact: models::Contact,
+    s_fee_estimate: models::FeeEstimate,
 }
 
 impl Default for AddressBookApp {
@@ -124,6 +125,7 @@ impl Default for AddressBookApp {
             s_contact: Default::default(),
             s_added_contact: Default::default(),
             s_invoice: Default::default(),
+            s_fee_estimate: Default::default(),
         }
     }
 }
@@ -174,8 +176,8 @@ impl eframe::App for AddressBookApp {
 
         if let Ok(payment) = self.payment_rx.try_recv() {
             self.is_payment_processed = payment;
+            self.is_loading = false;
             if self.is_payment_processed {
-                self.is_loading = false;
                 self.approve_payment = false;
                 self.showing_status = false;
             }
@@ -190,8 +192,9 @@ impl eframe::App for AddressBookApp {
             }
         }
 
-        if let Ok(can_transfer) = self.can_transfer_rx.try_recv() {
+        if let Ok((can_transfer, fee_estimate)) = self.can_transfer_rx.try_recv() {
             self.can_transfer = can_transfer;
+            self.s_fee_estimate = fee_estimate;
             self.is_estimating_fee = false;
         }
 
@@ -264,12 +267,37 @@ impl eframe::App for AddressBookApp {
                 ui.label(format!("pay to: {}", address));
                 ui.label(format!("amount: {} piconero(s)", amount));
                 ui.label(format!("expiration: {} blocks", expire));
+                if amount > 0 {
+                    for (priority, fee) in
+                        monero::FeePriority::ALL.iter().zip(&self.s_fee_estimate.fees)
+                    {
+                        ui.label(format!(
+                            "estimated {} fee: {} piconero(s)",
+                            priority.label(),
+                            fee
+                        ));
+                    }
+                }
+                for tier in &self.s_invoice.tiers {
+                    ui.label(format!(
+                        "{}: {} piconero(s), {} blocks, {} piconero(s) per call on credit",
+                        tier.scope, tier.pay_threshold, tier.conf_threshold, tier.call_price
+                    ));
+                }
+                if self.s_invoice.pow_difficulty > 0 {
+                    ui.label(format!(
+                        "proof-of-work accepted for: {} (difficulty {})",
+                        self.s_invoice.pow_scopes, self.s_invoice.pow_difficulty
+                    ));
+                }
+                // free invoices don't need a transfer
                 let show_approve = !self.s_invoice.address.is_empty()
-                    && self.can_transfer
+                    && (self.can_transfer || amount == 0)
                     && !self.is_estimating_fee;
                 if !self.is_loading {
                     if show_approve {
-                        if ui.button("Approve").clicked() {
+                        let approve = egui::Button::new("Approve");
+                        if ui.add_enabled(!utils::is_read_only(), approve).clicked() {
                             // activate xmr "transfer", check the hash, update db and refresh
                             // Note it is simply disabled on insufficient funds as calcd by fee
                             // estimator
@@ -321,6 +349,8 @@ impl eframe::App for AddressBookApp {
                     "offline"
                 };
                 ui.label(format!("status: {}", status));
+                ui.label(format!("last seen: {}", self.status.last_seen));
+                ui.label(format!("latency: {} ms", self.status.latency));
                 ui.label(format!("nick: {}", self.status.nick));
                 ui.label(format!("tx proof: {}", self.status.txp));
                 ui.label(format!("jwp: {}", self.status.jwp));
@@ -559,6 +589,14 @@ impl eframe::App for AddressBookApp {
                                             String::from(&c.i2p_address),
                                         )
                                         .unwrap_or_default();
+                                        // pick up jwps renewed in the background
+                                        let cached_jwp = proof::find_cached_jwp(&c.i2p_address)
+                                            .unwrap_or_default();
+                                        if !cached_jwp.is_empty() && cached_jwp != self.status.jwp
+                                        {
+                                            sync_renewed_jwp(&c.i2p_address, &cached_jwp);
+                                            self.status.jwp = cached_jwp;
+                                        }
                                         let r_exp = utils::search_gui_db(
                                             String::from(crate::GUI_EXP_DB_KEY),
                                             String::from(&c.i2p_address),
@@ -573,6 +611,17 @@ impl eframe::App for AddressBookApp {
                                             chrono::DateTime::from_timestamp(expire, 0)
                                                 .unwrap()
                                                 .to_string();
+                                        // get the reachability history
+                                        let presence =
+                                            presence::find(&c.i2p_address).unwrap_or_default();
+                                        self.status.latency = presence::average_latency(&presence);
+                                        self.status.last_seen = if presence.last_seen == 0 {
+                                            String::from("never")
+                                        } else {
+                                            chrono::DateTime::from_timestamp(presence.last_seen, 0)
+                                                .unwrap()
+                                                .to_string()
+                                        };
                                         send_contact_info_req(
                                             self.contact_info_tx.clone(),
                                             ctx.clone(),
@@ -673,8 +722,24 @@ fn send_payment_req(
             let wallet_name = String::from(neveko_core::APP_NAME);
             let wallet_password = std::env::var(neveko_core::MONERO_WALLET_PASSWORD)
                 .unwrap_or(String::from("password"));
-            monero::open_wallet(&wallet_name, &wallet_password).await;
-            let transfer: reqres::XmrRpcTransferResponse = monero::transfer(d).await;
+            let is_free = d.amount == 0;
+            let transfer: reqres::XmrRpcTransferResponse = if is_free {
+                Default::default()
+            } else {
+                let r_transfer = match monero::open_wallet(&wallet_name, &wallet_password).await {
+                    Ok(wallet) => ledger::transfer(&wallet, &contact, d).await.ok(),
+                    Err(_) => None,
+                };
+                match r_transfer {
+                    Some(t) => t,
+                    None => {
+                        log::error!("payment to {} was refused or failed", &contact);
+                        let _ = tx.send(false);
+                        ctx.request_repaint();
+                        return;
+                    }
+                }
+            };
             // in order to keep the jwp creation process transparent to the user
             // we will process all logic in one shot here.
 
@@ -682,6 +747,8 @@ fn send_payment_req(
             let ptxp_hash = String::from(&transfer.result.tx_hash);
             let ftxp_hash = String::from(&transfer.result.tx_hash);
             let ptxp: proof::TxProof = proof::TxProof {
+                destination: String::new(),
+                scope: String::new(),
                 subaddress: ptxp_address,
                 confirmations: 0,
                 hash: ptxp_hash,
@@ -691,7 +758,9 @@ fn send_payment_req(
             log::debug!("creating transaction proof for: {}", &ptxp.hash);
             // if we made it this far we can now request a JWP from our friend
             // wait a bit for the tx to propogate, i2p takes longer
-            let wait = if std::env::var(neveko_core::GUI_REMOTE_NODE).unwrap_or(String::new())
+            let wait = if is_free {
+                0
+            } else if std::env::var(neveko_core::GUI_REMOTE_NODE).unwrap_or(String::new())
                 == String::from(neveko_core::GUI_SET_REMOTE_NODE)
             {
                 crate::I2P_PROPAGATION_TIME_IN_SECS_EST
@@ -699,10 +768,19 @@ fn send_payment_req(
                 crate::PROPAGATION_TIME_IN_SECS_EST
             };
             tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
-            let get_txp: reqres::XmrRpcGetTxProofResponse = monero::get_tx_proof(ptxp).await;
+            let get_txp: reqres::XmrRpcGetTxProofResponse = if is_free {
+                Default::default()
+            } else {
+                match monero::open_wallet(&wallet_name, &wallet_password).await {
+                    Ok(wallet) => monero::get_tx_proof(&wallet, ptxp).await.unwrap_or_default(),
+                    Err(_) => Default::default(),
+                }
+            };
             // TODO(c2m): error handling on failed tx proof generation
             // use the signature to create the FINALIZED transaction proof
             let ftxp: proof::TxProof = proof::TxProof {
+                destination: String::new(),
+                scope: String::new(),
                 subaddress: ftxp_address,
                 confirmations: 0,
                 hash: ftxp_hash,
@@ -759,7 +837,6 @@ fn send_payment_req(
                 }
                 _ => log::error!("failed to obtain jwp"),
             }
-            monero::close_wallet(&wallet_name, &wallet_password).await;
         }
         if retry {
             let k_hash = String::from(crate::GUI_TX_HASH_DB_KEY);
@@ -770,6 +847,8 @@ fn send_payment_req(
             let subaddress =
                 utils::search_gui_db(k_subaddress, String::from(&contact)).unwrap_or_default();
             let ftxp: proof::TxProof = proof::TxProof {
+                destination: String::new(),
+                scope: String::new(),
                 subaddress,
                 confirmations: 0,
                 hash: String::from(&hash),
@@ -822,6 +901,28 @@ fn send_message_req(tx: Sender<bool>, ctx: egui::Context, body: String, to: Stri
     });
 }
 
+/// Update the gui jwp and estimated expiration with a renewed jwp
+fn sync_renewed_jwp(contact: &String, jwp: &String) {
+    log::debug!("syncing renewed jwp");
+    let remaining = renewal::get_remaining_blocks(jwp, proof::get_cached_height());
+    let seconds = (remaining * crate::BLOCK_TIME_IN_SECS_EST) as i64;
+    let unix: i64 = chrono::offset::Utc::now().timestamp() + seconds;
+    utils::clear_gui_db(String::from(crate::GUI_JWP_DB_KEY), String::from(contact)).unwrap();
+    utils::clear_gui_db(String::from(crate::GUI_EXP_DB_KEY), String::from(contact)).unwrap();
+    utils::write_gui_db(
+        String::from(crate::GUI_JWP_DB_KEY),
+        String::from(contact),
+        String::from(jwp),
+    )
+    .unwrap();
+    utils::write_gui_db(
+        String::from(crate::GUI_EXP_DB_KEY),
+        String::from(contact),
+        format!("{}", unix),
+    )
+    .unwrap();
+}
+
 fn change_nick_req(contact: String, nick: String) {
     log::debug!("change nick");
     utils::clear_gui_db(String::from(crate::GUI_NICK_DB_KEY), String::from(&contact)).unwrap();
@@ -833,12 +934,18 @@ fn change_nick_req(contact: String, nick: String) {
     .unwrap();
 }
 
-fn send_can_transfer_req(tx: Sender<bool>, ctx: egui::Context, invoice: u128) {
+fn send_can_transfer_req(
+    tx: Sender<(bool, models::FeeEstimate)>,
+    ctx: egui::Context,
+    invoice: u128,
+) {
     log::debug!("async send_can_transfer_req");
     tokio::spawn(async move {
         let can_transfer = utils::can_transfer(invoice).await;
         log::debug!("can transfer: {}", can_transfer);
-        let _ = tx.send(can_transfer);
+        // cached by can_transfer, so this doesn't hit the daemon again
+        let fee_estimate = utils::get_fee_estimate().await.unwrap_or_default();
+        let _ = tx.send((can_transfer, fee_estimate));
         ctx.request_repaint();
     });
 }
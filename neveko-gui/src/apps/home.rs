@@ -39,6 +39,8 @@ pub struct HomeApp {
     i2p_status_rx: Receiver<i2p::ProxyStatus>,
     xmrd_get_info_tx: Sender<reqres::XmrDaemonGetInfoResponse>,
     xmrd_get_info_rx: Receiver<reqres::XmrDaemonGetInfoResponse>,
+    remote_nodes_tx: Sender<(Vec<models::RemoteNode>, String)>,
+    remote_nodes_rx: Receiver<(Vec<models::RemoteNode>, String)>,
     xmr_address_tx: Sender<reqres::XmrRpcAddressResponse>,
     xmr_address_rx: Receiver<reqres::XmrRpcAddressResponse>,
     xmr_balance_tx: Sender<reqres::XmrRpcBalanceResponse>,
@@ -57,6 +59,9 @@ pub struct HomeApp {
     s_xmr_balance: reqres::XmrRpcBalanceResponse,
     s_xmr_rpc_ver: reqres::XmrRpcVersionResponse,
     s_xmrd_get_info: reqres::XmrDaemonGetInfoResponse,
+    /// remote node health and the one wallet rpc currently uses
+    s_remote_nodes: Vec<models::RemoteNode>,
+    s_current_node: String,
     s_i2p_status: i2p::ProxyStatus,
     s_can_refresh: bool,
     // logos
@@ -81,6 +86,7 @@ impl Default for HomeApp {
         let is_updated = false;
         let (core_timeout_tx, core_timeout_rx) = std::sync::mpsc::channel();
         let (xmrd_get_info_tx, xmrd_get_info_rx) = std::sync::mpsc::channel();
+        let (remote_nodes_tx, remote_nodes_rx) = std::sync::mpsc::channel();
         let (xmr_rpc_ver_tx, xmr_rpc_ver_rx) = std::sync::mpsc::channel();
         let (xmr_address_tx, xmr_address_rx) = std::sync::mpsc::channel();
         let (xmr_balance_tx, xmr_balance_rx) = std::sync::mpsc::channel();
@@ -93,6 +99,8 @@ impl Default for HomeApp {
         let s_xmr_address = Default::default();
         let s_xmr_balance = Default::default();
         let s_xmrd_get_info = Default::default();
+        let s_remote_nodes = Vec::new();
+        let s_current_node = String::new();
         let s_i2p_status = i2p::ProxyStatus::Opening;
         let s_can_refresh = false;
         let c_xmr_logo = std::fs::read("./assets/xmr.png").unwrap_or(Vec::new());
@@ -119,6 +127,8 @@ impl Default for HomeApp {
             is_updated,
             xmrd_get_info_tx,
             xmrd_get_info_rx,
+            remote_nodes_tx,
+            remote_nodes_rx,
             xmr_rpc_ver_tx,
             xmr_rpc_ver_rx,
             xmr_address_tx,
@@ -140,6 +150,8 @@ impl Default for HomeApp {
             s_xmr_balance,
             s_xmr_rpc_ver,
             s_xmrd_get_info,
+            s_remote_nodes,
+            s_current_node,
             s_i2p_status,
             // misc state
             s_can_refresh,
@@ -176,6 +188,10 @@ impl eframe::App for HomeApp {
         if let Ok(info) = self.xmrd_get_info_rx.try_recv() {
             self.s_xmrd_get_info = info;
         }
+        if let Ok((nodes, current)) = self.remote_nodes_rx.try_recv() {
+            self.s_remote_nodes = nodes;
+            self.s_current_node = current;
+        }
         if let Ok(timeout) = self.core_timeout_rx.try_recv() {
             self.is_timeout = true;
             if timeout {
@@ -233,11 +249,28 @@ impl eframe::App for HomeApp {
                     ui.text_edit_singleline(&mut self.connections.daemon_host)
                         .labelled_by(cm_daemon_label.id);
                 });
+                if self.connections.is_remote_node {
+                    ui.horizontal(|ui| {
+                        let cm_nodes_label = ui.label("fallback nodes:\t");
+                        ui.text_edit_singleline(&mut self.connections.remote_nodes)
+                            .labelled_by(cm_nodes_label.id)
+                            .on_hover_text("comma separated remote nodes to fail over to");
+                    });
+                }
                 ui.horizontal(|ui| {
                     let cm_rpc_label = ui.label("rpc host:  \t\t\t");
                     ui.text_edit_singleline(&mut self.connections.rpc_host)
                         .labelled_by(cm_rpc_label.id);
                 });
+                ui.horizontal(|ui| {
+                    let cm_workers_label = ui.label("rpc workers:\t\t");
+                    ui.add(egui::Slider::new(
+                        &mut self.connections.rpc_workers,
+                        1..=neveko_core::MONERO_RPC_WORKERS_MAX,
+                    ))
+                    .labelled_by(cm_workers_label.id)
+                    .on_hover_text("monero-wallet-rpc workers on consecutive ports");
+                });
                 ui.horizontal(|ui| {
                     let cm_user_label = ui.label("rpc user:  \t\t\t");
                     ui.text_edit_singleline(&mut self.connections.rpc_username)
@@ -283,17 +316,28 @@ impl eframe::App for HomeApp {
                     self.connections.is_i2p_advanced = !self.connections.is_i2p_advanced;
                     log::debug!("is i2p advanced mode: {}", self.connections.is_i2p_advanced);
                 }
-                let mut is_mainnet = self.connections.mainnet;
-                if ui.checkbox(&mut is_mainnet, "mainnet").changed() {
-                    self.connections.mainnet = !self.connections.mainnet;
-                    log::debug!("is mainnet: {}", self.connections.mainnet);
-                }
+                ui.horizontal(|ui| {
+                    for network in utils::Network::ALL {
+                        if ui
+                            .radio_value(&mut self.connections.network, network, network.value())
+                            .changed()
+                        {
+                            log::debug!("network: {}", network.value());
+                        }
+                    }
+                });
                 if ui.button("Start/Restart").clicked() {
                     self.is_editing_connections = false;
-                    utils::kill_child_processes(true);
-                    utils::start_core(&self.connections);
+                    // stopping children blocks until they exit, keep the ui responsive
+                    let connections = self.connections.clone();
+                    let core_timeout_tx = self.core_timeout_tx.clone();
+                    let t_ctx = ctx.clone();
+                    tokio::task::spawn_blocking(move || {
+                        utils::kill_child_processes();
+                        utils::start_core(&connections);
+                        start_core_timeout(core_timeout_tx, t_ctx);
+                    });
                     self.is_loading = true;
-                    start_core_timeout(self.core_timeout_tx.clone(), ctx.clone());
                 }
                 if ui.button("Exit").clicked() {
                     self.is_editing_connections = false;
@@ -315,6 +359,7 @@ impl eframe::App for HomeApp {
                     );
                     send_i2p_status_req(self.i2p_status_tx.clone(), ctx.clone());
                     send_xmrd_get_info_req(self.xmrd_get_info_tx.clone(), ctx.clone());
+                    send_remote_nodes_req(self.remote_nodes_tx.clone(), ctx.clone());
                 }
                 self.is_updated = true;
                 let is_initializing = self.is_init;
@@ -374,6 +419,30 @@ impl eframe::App for HomeApp {
                     unlock_time, xmrd_info.nettype, xmrd_info.top_block_hash, xmrd_info.height, xmrd_info.synchronized,
                     db_size, free_space, xmrd_info.version));
             });
+            let balances = account::get_unlocked_balances(&self.s_xmr_balance.result);
+            if balances.len() > 1 {
+                ui.label("account balances:");
+                for (index, balance) in &balances {
+                    let label = account::get_account_label(*index);
+                    ui.label(format!("\t- {} {}: {} piconero(s)", index, label, balance));
+                }
+            }
+            if !self.s_remote_nodes.is_empty() {
+                ui.label("remote nodes:");
+                for n in &self.s_remote_nodes {
+                    let marker = if n.address == self.s_current_node { "*" } else { "-" };
+                    let color = if n.online && n.synchronized {
+                        Color32::from_rgb(0, 255, 0)
+                    } else {
+                        Color32::from_rgb(255, 0, 0)
+                    };
+                    let status = format!(
+                        "\t{} {}: online: {}, synced: {}, height: {}, latency: {} ms",
+                        marker, n.address, n.online, n.synchronized, n.height, n.latency
+                    );
+                    ui.label(RichText::new(status).color(color));
+                }
+            }
             ui.label("____________________________________________________________________\n");
             ui.label("\n");
             if self.is_loading {
@@ -409,9 +478,19 @@ fn send_xmrd_get_info_req(tx: Sender<reqres::XmrDaemonGetInfoResponse>, ctx: egu
     });
 }
 
+fn send_remote_nodes_req(tx: Sender<(Vec<models::RemoteNode>, String)>, ctx: egui::Context) {
+    tokio::spawn(async move {
+        let nodes = node::find_all().unwrap_or_default();
+        let current = node::get_current()
+            .unwrap_or(nodes.first().map(|n| String::from(&n.address)).unwrap_or_default());
+        let _ = tx.send((nodes, current));
+        ctx.request_repaint();
+    });
+}
+
 fn send_ver_req(tx: Sender<reqres::XmrRpcVersionResponse>, ctx: egui::Context) {
     tokio::spawn(async move {
-        let ver: reqres::XmrRpcVersionResponse = monero::get_version().await;
+        let ver: reqres::XmrRpcVersionResponse = monero::get_version().await.unwrap_or_default();
         let _ = tx.send(ver);
         ctx.request_repaint();
     });
@@ -428,12 +507,22 @@ fn send_wallet_req(
         let wallet_name = String::from(neveko_core::APP_NAME);
         let wallet_password =
             std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-        monero::open_wallet(&wallet_name, &wallet_password).await;
-        let address: reqres::XmrRpcAddressResponse = monero::get_address().await;
-        let refresh: reqres::XmrRpcRefreshResponse = monero::refresh().await;
-        let balance: reqres::XmrRpcBalanceResponse = monero::get_balance().await;
-        let wallet_height: reqres::XmrRpcGetHeightResponse = monero::get_wallet_height().await;
-        monero::close_wallet(&wallet_name, &wallet_password).await;
+        let wallet = match monero::open_wallet(&wallet_name, &wallet_password).await {
+            Ok(w) => w,
+            Err(e) => {
+                log::error!("failed to open wallet: {}", e);
+                return;
+            }
+        };
+        let address: reqres::XmrRpcAddressResponse =
+            monero::get_address(&wallet).await.unwrap_or_default();
+        let refresh: reqres::XmrRpcRefreshResponse =
+            monero::refresh(&wallet).await.unwrap_or_default();
+        let balance: reqres::XmrRpcBalanceResponse =
+            monero::get_balance(&wallet).await.unwrap_or_default();
+        let wallet_height: reqres::XmrRpcGetHeightResponse =
+            monero::get_wallet_height(&wallet).await.unwrap_or_default();
+        drop(wallet);
         let _ = address_tx.send(address);
         let _ = balance_tx.send(balance);
         let _ = wallet_refresh_tx.send(refresh);
@@ -493,7 +493,8 @@ impl eframe::App for MarketApp {
                 if !self.msig.completed_prepare {
                     ui.horizontal(|ui| {
                         ui.label("Prepare:  \t\t\t\t\t");
-                        if ui.button("Prepare").clicked() {
+                        let prepare = egui::Button::new("Prepare");
+                        if ui.add_enabled(!utils::is_read_only(), prepare).clicked() {
                             self.is_loading = true;
                             let adjudicator_prefix =
                                 String::from(crate::GUI_MSIG_ADJUDICATOR_DB_KEY);
@@ -538,7 +539,8 @@ impl eframe::App for MarketApp {
                 if self.msig.completed_prepare && !self.msig.completed_make {
                     ui.horizontal(|ui| {
                         ui.label("Make:   \t\t\t\t\t\t");
-                        if ui.button("Make").clicked() {
+                        let make = egui::Button::new("Make");
+                        if ui.add_enabled(!utils::is_read_only(), make).clicked() {
                             self.is_loading = true;
                             let adjudicator_prefix =
                                 String::from(crate::GUI_MSIG_ADJUDICATOR_DB_KEY);
@@ -583,7 +585,8 @@ impl eframe::App for MarketApp {
                 if self.msig.completed_make && !self.msig.completed_kex_init {
                     ui.horizontal(|ui| {
                         ui.label("Key Exchange Initial:  \t\t\t");
-                        if ui.button("KEX-INIT").clicked() {
+                        let kex_init = egui::Button::new("KEX-INIT");
+                        if ui.add_enabled(!utils::is_read_only(), kex_init).clicked() {
                             self.is_loading = true;
                             let adjudicator_prefix =
                                 String::from(crate::GUI_MSIG_ADJUDICATOR_DB_KEY);
@@ -628,7 +631,8 @@ impl eframe::App for MarketApp {
                 if self.msig.completed_kex_init && !self.msig.completed_kex_final {
                     ui.horizontal(|ui| {
                         ui.label("Key Exchange Final:  \t\t\t");
-                        if ui.button("KEX-FINAL").clicked() {
+                        let kex_final = egui::Button::new("KEX-FINAL");
+                        if ui.add_enabled(!utils::is_read_only(), kex_final).clicked() {
                             self.is_loading = true;
                             let adjudicator_prefix =
                                 String::from(crate::GUI_MSIG_ADJUDICATOR_DB_KEY);
@@ -673,7 +677,8 @@ impl eframe::App for MarketApp {
                 if self.msig.completed_kex_final && !self.msig.completed_funding {
                     ui.horizontal(|ui| {
                         ui.label("Fund:\t\t\t\t\t\t\t");
-                        if ui.button("Fund").clicked() {
+                        let fund = egui::Button::new("Fund");
+                        if ui.add_enabled(!utils::is_read_only(), fund).clicked() {
                             set_order_address(
                                 &self.m_order.orid,
                                 self.order_xmr_address_tx.clone(),
@@ -701,7 +706,8 @@ impl eframe::App for MarketApp {
                 if self.msig.completed_funding && !self.msig.completed_export {
                     ui.horizontal(|ui| {
                         ui.label("Export Info: \t\t\t\t");
-                        if ui.button("Export").clicked() {
+                        let export = egui::Button::new("Export");
+                        if ui.add_enabled(!utils::is_read_only(), export).clicked() {
                             self.is_loading = true;
                             let vendor_prefix = String::from(crate::GUI_OVL_DB_KEY);
                             let vendor =
@@ -777,7 +783,8 @@ impl eframe::App for MarketApp {
                 if self.msig.completed_shipping_request && !self.msig.completed_payment_release {
                     ui.horizontal(|ui| {
                         ui.label("Release Payment: \t");
-                        if ui.button("Submit Txset").clicked() {
+                        let submit = egui::Button::new("Submit Txset");
+                        if ui.add_enabled(!utils::is_read_only(), submit).clicked() {
                             self.is_loading = true;
                             let vendor_prefix = String::from(crate::GUI_OVL_DB_KEY);
                             let vendor =
@@ -942,7 +949,11 @@ impl eframe::App for MarketApp {
                                 row.col(|ui| {
                                     ui.style_mut().wrap = Some(false);
                                     ui.horizontal(|ui| {
-                                        if ui.button("Cancel").clicked() {
+                                        let cancel = egui::Button::new("Cancel");
+                                        if ui
+                                            .add_enabled(!utils::is_read_only(), cancel)
+                                            .clicked()
+                                        {
                                             let vendor_prefix = String::from(crate::GUI_OVL_DB_KEY);
                                             let vendor = utils::search_gui_db(
                                                 vendor_prefix,
@@ -962,7 +973,11 @@ impl eframe::App for MarketApp {
                                 row.col(|ui| {
                                     ui.style_mut().wrap = Some(false);
                                     ui.horizontal(|ui| {
-                                        if ui.button("Dispute").clicked() {
+                                        let dispute = egui::Button::new("Dispute");
+                                        if ui
+                                            .add_enabled(!utils::is_read_only(), dispute)
+                                            .clicked()
+                                        {
                                             let vendor_prefix = String::from(crate::GUI_OVL_DB_KEY);
                                             let vendor = utils::search_gui_db(
                                                 vendor_prefix,
@@ -1031,7 +1046,8 @@ impl eframe::App for MarketApp {
                     }
                 }
                 if qty <= p_qty && qty > 0 {
-                    if ui.button("Submit Order").clicked() {
+                    let submit = egui::Button::new("Submit Order");
+                    if ui.add_enabled(!utils::is_read_only(), submit).clicked() {
                         // encipher the shipping address
                         cipher_req(
                             self.new_order.cid.clone(),
@@ -1872,17 +1888,17 @@ fn send_prepare_info_req(
             utils::search_gui_db(String::from(crate::GUI_JWP_DB_KEY), String::from(&vendor))
                 .unwrap_or_default();
         let wallet_password = String::new();
-        monero::create_wallet(&w_orid, &wallet_password).await;
-        let m_wallet = monero::open_wallet(&w_orid, &wallet_password).await;
-        if !m_wallet {
-            log::error!("failed to open wallet");
-            monero::close_wallet(&w_orid, &wallet_password).await;
-            let _ = tx.send(String::new());
-            return;
-        }
-        monero::close_wallet(&w_orid, &wallet_password).await;
-        monero::open_wallet(&w_orid, &wallet_password).await;
-        let prepare_info = monero::prepare_wallet().await;
+        let _ = monero::create_wallet(&w_orid, &wallet_password).await;
+        let wallet = match monero::open_wallet(&w_orid, &wallet_password).await {
+            Ok(w) => w,
+            Err(e) => {
+                log::error!("failed to open wallet: {}", e);
+                let _ = tx.send(String::new());
+                return;
+            }
+        };
+        let prepare_info = monero::prepare_wallet(&wallet).await.unwrap_or_default();
+        drop(wallet);
         let ref_prepare_info: &String = &prepare_info.result.multisig_info;
         utils::write_gui_db(
             String::from(crate::GUI_MSIG_PREPARE_DB_KEY),
@@ -1967,13 +1983,14 @@ fn send_make_info_req(
             utils::search_gui_db(String::from(crate::GUI_JWP_DB_KEY), String::from(&vendor))
                 .unwrap_or_default();
         let wallet_password = String::new();
-        let m_wallet = monero::open_wallet(&w_orid, &wallet_password).await;
-        if !m_wallet {
-            monero::close_wallet(&w_orid, &wallet_password).await;
-            log::error!("failed to open wallet");
-            let _ = tx.send(String::new());
-            return;
-        }
+        let wallet = match monero::open_wallet(&w_orid, &wallet_password).await {
+            Ok(w) => w,
+            Err(e) => {
+                log::error!("failed to open wallet: {}", e);
+                let _ = tx.send(String::new());
+                return;
+            }
+        };
         let mut prepare_info_prep = Vec::new();
         let mut m_prepare_info_send = Vec::new();
         let mut v_prepare_info_send = Vec::new();
@@ -2017,8 +2034,8 @@ fn send_make_info_req(
         )
         .unwrap_or_default();
         if local_make.is_empty() {
-            let make_info = monero::make_wallet(prepare_info_prep).await;
-            monero::close_wallet(&w_orid, &wallet_password).await;
+            let make_info =
+                monero::make_wallet(&wallet, prepare_info_prep).await.unwrap_or_default();
             let ref_make_info: &String = &make_info.result.multisig_info;
             if !String::from(ref_make_info).is_empty() {
                 utils::write_gui_db(
@@ -2029,6 +2046,7 @@ fn send_make_info_req(
                 .unwrap();
             }
         }
+        drop(wallet);
         // Request adjudicator and vendor while we're at it
         // Will coordinating send this on make requests next
         let m_msig_key = format!(
@@ -2102,13 +2120,14 @@ fn send_kex_initial_req(
             utils::search_gui_db(String::from(crate::GUI_JWP_DB_KEY), String::from(&vendor))
                 .unwrap_or_default();
         let wallet_password = String::new();
-        let m_wallet = monero::open_wallet(&w_orid, &wallet_password).await;
-        if !m_wallet {
-            monero::close_wallet(&w_orid, &wallet_password).await;
-            log::error!("failed to open wallet");
-            let _ = tx.send(String::new());
-            return;
-        }
+        let wallet = match monero::open_wallet(&w_orid, &wallet_password).await {
+            Ok(w) => w,
+            Err(e) => {
+                log::error!("failed to open wallet: {}", e);
+                let _ = tx.send(String::new());
+                return;
+            }
+        };
         let mut kex_init_prep = Vec::new();
         let mut m_kex_init_send = Vec::new();
         let mut v_kex_init_send = Vec::new();
@@ -2153,8 +2172,9 @@ fn send_kex_initial_req(
         .unwrap_or_default();
         if local_kex_init.is_empty() {
             let kex_out =
-                monero::exchange_multisig_keys(false, kex_init_prep, &wallet_password).await;
-            monero::close_wallet(&w_orid, &wallet_password).await;
+                monero::exchange_multisig_keys(&wallet, false, kex_init_prep, &wallet_password)
+                    .await
+                    .unwrap_or_default();
             let ref_kex_info: &String = &kex_out.result.multisig_info;
             if !String::from(ref_kex_info).is_empty() {
                 utils::write_gui_db(
@@ -2165,6 +2185,7 @@ fn send_kex_initial_req(
                 .unwrap();
             }
         }
+        drop(wallet);
         // Request adjudicator and vendor while we're at it
         // Will coordinating send this on kex round two next
         let m_msig_key = format!(
@@ -2241,13 +2262,14 @@ fn send_kex_final_req(
             utils::search_gui_db(String::from(crate::GUI_JWP_DB_KEY), String::from(&vendor))
                 .unwrap_or_default();
         let wallet_password = String::new();
-        let m_wallet = monero::open_wallet(&w_orid, &wallet_password).await;
-        if !m_wallet {
-            monero::close_wallet(&w_orid, &wallet_password).await;
-            log::error!("failed to open wallet");
-            let _ = tx.send(String::new());
-            return;
-        }
+        let wallet = match monero::open_wallet(&w_orid, &wallet_password).await {
+            Ok(w) => w,
+            Err(e) => {
+                log::error!("failed to open wallet: {}", e);
+                let _ = tx.send(String::new());
+                return;
+            }
+        };
         let mut kex_final_prep = Vec::new();
         let mut m_kex_final_send = Vec::new();
         let mut v_kex_final_send = Vec::new();
@@ -2290,8 +2312,9 @@ fn send_kex_final_req(
         .unwrap_or_default();
         if local_kex_final.is_empty() {
             let kex_out =
-                monero::exchange_multisig_keys(false, kex_final_prep, &wallet_password).await;
-            monero::close_wallet(&w_orid, &wallet_password).await;
+                monero::exchange_multisig_keys(&wallet, false, kex_final_prep, &wallet_password)
+                    .await
+                    .unwrap_or_default();
             let ref_kex_info: &String = &kex_out.result.address;
             if !String::from(ref_kex_info).is_empty() {
                 utils::write_gui_db(
@@ -2302,6 +2325,7 @@ fn send_kex_final_req(
                 .unwrap_or_default();
             }
         }
+        drop(wallet);
         // we can verify all good if the senders all send back the correct wallet address
         let m_msig_key = format!(
             "{}-{}-{}",
@@ -2361,9 +2385,11 @@ fn set_order_address(orid: &String, tx: Sender<reqres::XmrRpcAddressResponse>, c
     let order_id = String::from(orid);
     tokio::spawn(async move {
         let wallet_password = String::new();
-        monero::open_wallet(&order_id, &wallet_password).await;
-        let address: reqres::XmrRpcAddressResponse = monero::get_address().await;
-        monero::close_wallet(&order_id, &wallet_password).await;
+        let address: reqres::XmrRpcAddressResponse =
+            match monero::open_wallet(&order_id, &wallet_password).await {
+                Ok(wallet) => monero::get_address(&wallet).await.unwrap_or_default(),
+                Err(_) => Default::default(),
+            };
         let _ = tx.send(address);
         ctx.request_repaint();
     });
@@ -2380,15 +2406,28 @@ fn verify_order_wallet_funded(
     tokio::spawn(async move {
         log::info!("executing verify_order_wallet_funded");
         let wallet_password = String::new();
-        monero::open_wallet(&order_id, &wallet_password).await;
-        let _ = monero::refresh().await;
-        let pre_bal = monero::get_balance().await;
-        let is_msig_res = monero::is_multisig().await;
+        let wallet = match monero::open_wallet(&order_id, &wallet_password).await {
+            Ok(w) => w,
+            Err(_) => {
+                let _ = tx.send(false);
+                return;
+            }
+        };
+        let _ = monero::refresh(&wallet).await;
+        let (pre_bal, is_msig_res) =
+            match (monero::get_balance(&wallet).await, monero::is_multisig(&wallet).await) {
+                (Ok(b), Ok(m)) => (b, m),
+                _ => {
+                    log::error!("failed to check order wallet: {}", &order_id);
+                    let _ = tx.send(false);
+                    return;
+                }
+            };
+        drop(wallet);
         if !is_msig_res.result.multisig || !is_msig_res.result.ready {
             let _ = tx.send(false);
             return;
         }
-        monero::close_wallet(&order_id, &wallet_password).await;
         let order = order::find(&order_id).unwrap_or_default();
         let vendor = String::from(&l_contact);
         let v_jwp: String =
@@ -2421,15 +2460,17 @@ fn send_import_info_req(tx: Sender<String>, ctx: egui::Context, orid: &String, v
             utils::search_gui_db(String::from(crate::GUI_JWP_DB_KEY), String::from(&vendor))
                 .unwrap_or_default();
         let wallet_password = String::new();
-        let m_wallet = monero::open_wallet(&w_orid, &wallet_password).await;
-        if !m_wallet {
-            log::error!("failed to open wallet");
-            monero::close_wallet(&w_orid, &wallet_password).await;
-            let _ = tx.send(String::new());
-            return;
-        }
+        let wallet = match monero::open_wallet(&w_orid, &wallet_password).await {
+            Ok(w) => w,
+            Err(e) => {
+                log::error!("failed to open wallet: {}", e);
+                let _ = tx.send(String::new());
+                return;
+            }
+        };
         let mut info: Vec<String> = Vec::new();
-        let export_info = monero::export_multisig_info().await;
+        let export_info = monero::export_multisig_info(&wallet).await.unwrap_or_default();
+        drop(wallet);
         info.push(String::from(&export_info.result.info));
         let ref_export_info: &String = &export_info.result.info;
         utils::write_gui_db(
@@ -2565,18 +2606,32 @@ fn create_dispute_req(
     let a_contact: String = String::from(contact);
     tokio::spawn(async move {
         log::info!("async create_dispute_req");
+        if utils::check_spend_keys().is_err() {
+            let _ = tx.send(Default::default());
+            ctx.request_repaint();
+            return;
+        }
         // generate address for refund
         let wallet_password =
             std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
         let wallet_name = String::from(neveko_core::APP_NAME);
-        monero::open_wallet(&wallet_name, &wallet_password).await;
-        let address_res = monero::get_address().await;
-        monero::close_wallet(&wallet_name, &wallet_password).await;
+        let refund_address = match monero::open_wallet(&wallet_name, &wallet_password).await {
+            Ok(wallet) => {
+                account::create_address(&wallet, account::AccountPurpose::Refunds, &d_orid)
+                    .await
+                    .unwrap_or_default()
+                    .address
+            }
+            Err(_) => String::new(),
+        };
         // generate a txset for the adjudicator
         let wallet_password = String::new();
-        monero::open_wallet(&d_orid, &wallet_password).await;
-        let transfer = monero::sweep_all(String::from(address_res.result.address)).await;
-        monero::close_wallet(&d_orid, &wallet_password).await;
+        let transfer = match monero::open_wallet(&d_orid, &wallet_password).await {
+            Ok(wallet) => monero::sweep_all(&wallet, String::from(&refund_address), 0)
+                .await
+                .unwrap_or_default(),
+            Err(_) => Default::default(),
+        };
         if transfer.result.multisig_txset.is_empty() {
             log::error!("could not create txset");
             let _ = tx.send(Default::default());
@@ -2594,10 +2649,15 @@ fn create_dispute_req(
             // cancel the order and write the dispute to the db
             let wallet_password = std::env::var(neveko_core::MONERO_WALLET_PASSWORD)
                 .unwrap_or(String::from("password"));
-            monero::open_wallet(&String::from(neveko_core::APP_NAME), &wallet_password).await;
-            let pre_sign = monero::sign(String::from(&d_orid)).await;
-            monero::close_wallet(&String::from(neveko_core::APP_NAME), &wallet_password).await;
-            let _ = order::cancel_order(&d_orid, &pre_sign.result.signature).await;
+            let challenge = order::create_challenge(&d_orid, &order::ChallengeAction::Cancel)
+                .unwrap_or_default();
+            let data = order::get_challenge_data(&challenge);
+            let pre_sign = match monero::open_wallet(&wallet_name, &wallet_password).await {
+                Ok(wallet) => monero::sign(&wallet, data).await.unwrap_or_default(),
+                Err(_) => Default::default(),
+            };
+            let _ =
+                order::cancel_order(&d_orid, &challenge.nonce, &pre_sign.result.signature).await;
             let j_dispute = utils::dispute_to_json(&res);
             let _ = dispute::create(j_dispute);
             let _ = tx.send(res);
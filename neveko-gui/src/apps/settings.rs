@@ -13,6 +13,9 @@ use crate::CREDENTIAL_KEY;
 
 pub struct SettingsApp {
     credential: String,
+    renewal_budget: String,
+    daily_spending_cap: String,
+    contact_spending_cap: String,
     change_wallet_password_tx: Sender<bool>,
     change_wallet_password_rx: Receiver<bool>,
     is_loading: bool,
@@ -24,6 +27,9 @@ impl Default for SettingsApp {
         let (change_wallet_password_tx, change_wallet_password_rx) = std::sync::mpsc::channel();
         SettingsApp {
             credential: String::new(),
+            renewal_budget: format!("{}", renewal::get_budget()),
+            daily_spending_cap: format!("{}", ledger::get_daily_cap()),
+            contact_spending_cap: format!("{}", ledger::get_contact_cap()),
             change_wallet_password_rx,
             change_wallet_password_tx,
             is_loading: false,
@@ -87,6 +93,60 @@ impl eframe::App for SettingsApp {
                     self.credential = String::new();
                 }
             });
+            ui.label("\n\n");
+            ui.heading("JWP Renewal");
+            ui.label(
+                "____________________________________________________________________________\n",
+            );
+            ui.label(format!("spent today: {} piconero(s)", renewal::get_spent()));
+            ui.horizontal(|ui| {
+                let budget_label = ui.label("daily budget (piconeros): \t");
+                ui.text_edit_singleline(&mut self.renewal_budget)
+                    .labelled_by(budget_label.id);
+                if ui.button("Set").clicked() {
+                    // zero disables automatic renewal
+                    let budget = self.renewal_budget.parse::<u128>().unwrap_or(0);
+                    renewal::set_budget(budget)
+                        .unwrap_or_else(|_| log::error!("failed to set renewal budget"));
+                    self.renewal_budget = format!("{}", budget);
+                }
+            });
+            ui.label("\n\n");
+            ui.heading("Spending Caps");
+            ui.label(
+                "____________________________________________________________________________\n",
+            );
+            ui.horizontal(|ui| {
+                let daily_label = ui.label("daily cap (piconeros): \t");
+                ui.text_edit_singleline(&mut self.daily_spending_cap)
+                    .labelled_by(daily_label.id);
+            });
+            ui.horizontal(|ui| {
+                let contact_label = ui.label("per contact cap (piconeros): \t");
+                ui.text_edit_singleline(&mut self.contact_spending_cap)
+                    .labelled_by(contact_label.id);
+            });
+            if ui.button("Set").clicked() {
+                // zero means no cap
+                let daily_cap = self.daily_spending_cap.parse::<u128>().unwrap_or(0);
+                let contact_cap = self.contact_spending_cap.parse::<u128>().unwrap_or(0);
+                ledger::set_daily_cap(daily_cap)
+                    .unwrap_or_else(|_| log::error!("failed to set daily spending cap"));
+                ledger::set_contact_cap(contact_cap)
+                    .unwrap_or_else(|_| log::error!("failed to set contact spending cap"));
+                self.daily_spending_cap = format!("{}", daily_cap);
+                self.contact_spending_cap = format!("{}", contact_cap);
+            }
+            ui.label("\n\n");
+            ui.heading("Signing Keys");
+            ui.label(
+                "____________________________________________________________________________\n",
+            );
+            ui.label("revoking invalidates every issued JWP, credit token and session");
+            if ui.button("Revoke and Regenerate").clicked() {
+                utils::regenerate_signing_keys()
+                    .unwrap_or_else(|_| log::error!("failed to regenerate signing keys"));
+            }
         });
     }
 }
@@ -101,12 +161,15 @@ fn change_wallet_password(
         let wallet_name = String::from(neveko_core::APP_NAME);
         let wallet_password =
             std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-        monero::open_wallet(&wallet_name, &wallet_password).await;
-        let is_changed: bool = monero::change_wallet_password(&update_password).await;
+        let is_changed = match monero::open_wallet(&wallet_name, &wallet_password).await {
+            Ok(mut wallet) => monero::change_wallet_password(&mut wallet, &update_password)
+                .await
+                .is_ok(),
+            Err(_) => false,
+        };
         if is_changed {
             std::env::set_var(neveko_core::MONERO_WALLET_PASSWORD, update_password);
         }
-        monero::close_wallet(&wallet_name, &wallet_password).await;
         let _ = change_wallet_password_tx.send(is_changed);
         ctx.request_repaint();
     });
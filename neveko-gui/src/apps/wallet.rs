@@ -7,17 +7,46 @@ use std::sync::mpsc::{
 };
 
 pub struct WalletApp {
+    pub accounts: Vec<reqres::SubaddressAccount>,
+    pub accounts_tx: Sender<Vec<reqres::SubaddressAccount>>,
+    pub accounts_rx: Receiver<Vec<reqres::SubaddressAccount>>,
     pub init: bool,
+    pub history: Vec<models::WalletTransfer>,
+    pub history_filter: monero::TransferFilter,
+    pub history_subaddress: String,
+    pub history_sync_tx: Sender<bool>,
+    pub history_sync_rx: Receiver<bool>,
     pub is_loading: bool,
+    pub is_syncing_history: bool,
     pub is_qr_set: bool,
     pub is_showing_qr: bool,
     pub is_showing_sweep_result: bool,
+    pub is_previewing_send: bool,
+    pub is_sending: bool,
+    pub is_showing_send_confirmation: bool,
+    pub payments: Vec<models::Payment>,
+    pub payments_export: String,
     pub qr: egui_extras::RetainedImage,
+    pub send_account: u32,
+    pub send_address: String,
+    pub send_amount: String,
+    pub send_password: String,
+    pub send_priority: monero::FeePriority,
+    pub send_status: String,
+    pub send_subaddress: String,
+    pub send_confirm_tx: Sender<Result<String, send::SendError>>,
+    pub send_confirm_rx: Receiver<Result<String, send::SendError>>,
+    pub send_preview_tx: Sender<Result<models::SendPreview, send::SendError>>,
+    pub send_preview_rx: Receiver<Result<models::SendPreview, send::SendError>>,
+    pub s_send_preview: models::SendPreview,
     pub sweep_address: String,
     pub xmr_address_tx: Sender<reqres::XmrRpcAddressResponse>,
     pub xmr_address_rx: Receiver<reqres::XmrRpcAddressResponse>,
     pub xmr_sweep_all_tx: Sender<reqres::XmrRpcSweepAllResponse>,
     pub xmr_sweep_all_rx: Receiver<reqres::XmrRpcSweepAllResponse>,
+    pub fee_estimate_tx: Sender<models::FeeEstimate>,
+    pub fee_estimate_rx: Receiver<models::FeeEstimate>,
+    pub s_fee_estimate: models::FeeEstimate,
     pub s_xmr_address: String,
     pub x_xmr_sweep_res: reqres::XmrRpcSweepAllResponse,
 }
@@ -26,19 +55,53 @@ impl Default for WalletApp {
     fn default() -> Self {
         let (xmr_address_tx, xmr_address_rx) = std::sync::mpsc::channel();
         let (xmr_sweep_all_tx, xmr_sweep_all_rx) = std::sync::mpsc::channel();
+        let (fee_estimate_tx, fee_estimate_rx) = std::sync::mpsc::channel();
+        let (history_sync_tx, history_sync_rx) = std::sync::mpsc::channel();
+        let (accounts_tx, accounts_rx) = std::sync::mpsc::channel();
+        let (send_preview_tx, send_preview_rx) = std::sync::mpsc::channel();
+        let (send_confirm_tx, send_confirm_rx) = std::sync::mpsc::channel();
         let contents = std::fs::read("./assets/qr.png").unwrap_or(Vec::new());
         WalletApp {
+            accounts: Vec::new(),
+            accounts_rx,
+            accounts_tx,
             init: false,
+            history: Vec::new(),
+            history_filter: monero::TransferFilter::all(),
+            history_subaddress: String::new(),
+            history_sync_rx,
+            history_sync_tx,
             is_loading: false,
+            is_syncing_history: false,
             is_qr_set: false,
             is_showing_qr: false,
             is_showing_sweep_result: false,
+            is_previewing_send: false,
+            is_sending: false,
+            is_showing_send_confirmation: false,
+            payments: Vec::new(),
+            payments_export: String::new(),
             qr: egui_extras::RetainedImage::from_image_bytes("qr.png", &contents).unwrap(),
+            send_account: 0,
+            send_address: String::new(),
+            send_amount: String::new(),
+            send_password: String::new(),
+            send_priority: monero::FeePriority::Normal,
+            send_status: String::new(),
+            send_subaddress: String::new(),
+            send_confirm_rx,
+            send_confirm_tx,
+            send_preview_rx,
+            send_preview_tx,
+            s_send_preview: Default::default(),
             sweep_address: String::new(),
             xmr_address_rx,
             xmr_address_tx,
             xmr_sweep_all_rx,
             xmr_sweep_all_tx,
+            fee_estimate_rx,
+            fee_estimate_tx,
+            s_fee_estimate: Default::default(),
             s_xmr_address: String::new(),
             x_xmr_sweep_res: Default::default(),
         }
@@ -50,12 +113,53 @@ impl eframe::App for WalletApp {
         if let Ok(a) = self.xmr_address_rx.try_recv() {
             self.s_xmr_address = a.result.address;
         }
+        if let Ok(synced) = self.history_sync_rx.try_recv() {
+            if !synced {
+                log::error!("failed to sync wallet history");
+            }
+            self.history = history::find_all(&self.history_filter).unwrap_or_default();
+            self.is_syncing_history = false;
+        }
+        if let Ok(fee_estimate) = self.fee_estimate_rx.try_recv() {
+            self.s_fee_estimate = fee_estimate;
+        }
+        if let Ok(accounts) = self.accounts_rx.try_recv() {
+            self.accounts = accounts;
+        }
+        if let Ok(preview) = self.send_preview_rx.try_recv() {
+            match preview {
+                Ok(p) => {
+                    self.send_status = String::new();
+                    self.s_send_preview = p;
+                    self.is_showing_send_confirmation = true;
+                }
+                Err(e) => self.send_status = format!("failed to preview send: {:?}", e),
+            }
+            self.is_previewing_send = false;
+        }
+        if let Ok(sent) = self.send_confirm_rx.try_recv() {
+            match sent {
+                Ok(hash) => {
+                    self.send_status = format!("sent: {}", hash);
+                    self.send_address = String::new();
+                    self.send_amount = String::new();
+                    self.is_showing_send_confirmation = false;
+                    self.s_send_preview = Default::default();
+                }
+                Err(e) => self.send_status = format!("failed to send: {:?}", e),
+            }
+            self.is_sending = false;
+        }
         if let Ok(sweep) = self.xmr_sweep_all_rx.try_recv() {
             self.x_xmr_sweep_res = sweep;
             self.is_loading = false;
         }
         if !self.init {
             send_address_req(self.xmr_address_tx.clone(), ctx.clone());
+            send_fee_estimate_req(self.fee_estimate_tx.clone(), ctx.clone());
+            send_accounts_req(self.accounts_tx.clone(), ctx.clone());
+            self.payments = ledger::find_all().unwrap_or_default();
+            self.history = history::find_all(&self.history_filter).unwrap_or_default();
             self.init = true;
         }
         // Sweep Result
@@ -77,6 +181,51 @@ impl eframe::App for WalletApp {
                 }
             });
 
+        // Send Confirmation
+        //-----------------------------------------------------------------------------------
+        let mut is_showing_send_confirmation = self.is_showing_send_confirmation;
+        egui::Window::new("confirm send")
+            .open(&mut is_showing_send_confirmation)
+            .title_bar(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.heading("Confirm Send");
+                let p = &self.s_send_preview;
+                ui.label(format!("to: {}", p.address));
+                ui.label(format!("amount: {} piconero(s)", p.amount));
+                ui.label(format!("fee: {} piconero(s)", p.fee));
+                ui.label(format!("total: {} piconero(s)", p.amount + p.fee));
+                ui.label(format!("from account: {}", p.account_index));
+                ui.horizontal(|ui| {
+                    let password_label = ui.label("wallet password: \t");
+                    ui.add(egui::TextEdit::singleline(&mut self.send_password).password(true))
+                        .labelled_by(password_label.id);
+                });
+                if self.is_sending {
+                    ui.add(egui::Spinner::new());
+                    ui.label("sending...");
+                }
+                ui.label(&self.send_status);
+                ui.horizontal(|ui| {
+                    if !self.is_sending && ui.button("Confirm").clicked() {
+                        self.is_sending = true;
+                        send_confirm_req(
+                            self.send_confirm_tx.clone(),
+                            ctx.clone(),
+                            self.s_send_preview.clone(),
+                            self.send_password.clone(),
+                        );
+                        self.send_password = String::new();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.send_password = String::new();
+                        self.send_status = String::new();
+                        self.s_send_preview = Default::default();
+                        self.is_showing_send_confirmation = false;
+                    }
+                });
+            });
+
         // QR
         //-----------------------------------------------------------------------------------
         let mut is_showing_qr = self.is_showing_qr;
@@ -111,6 +260,10 @@ impl eframe::App for WalletApp {
             });
 
         egui::CentralPanel::default().show(ctx, |ui| {
+            let is_read_only = utils::is_read_only();
+            if is_read_only {
+                ui.label("view-only wallet, sending is disabled");
+            }
             ui.heading("Address");
             ui.label(
                 "____________________________________________________________________________\n",
@@ -121,6 +274,74 @@ impl eframe::App for WalletApp {
                 }
             });
             ui.label("\n\n");
+            ui.heading("Send");
+            ui.label(
+                "____________________________________________________________________________\n",
+            );
+            ui.horizontal(|ui| {
+                let address_label = ui.label("send to: \t");
+                ui.text_edit_singleline(&mut self.send_address)
+                    .labelled_by(address_label.id);
+            });
+            ui.horizontal(|ui| {
+                let amount_label = ui.label("amount: \t");
+                ui.text_edit_singleline(&mut self.send_amount)
+                    .labelled_by(amount_label.id)
+                    .on_hover_text("piconero(s)");
+            });
+            ui.horizontal(|ui| {
+                ui.label("priority: \t");
+                for (i, priority) in monero::FeePriority::ALL.iter().enumerate() {
+                    let fee = self.s_fee_estimate.fees.get(i).copied().unwrap_or(0);
+                    ui.radio_value(&mut self.send_priority, *priority, priority.label())
+                        .on_hover_text(format!("estimated fee: {} piconero(s)", fee));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("account: \t");
+                for a in &self.accounts {
+                    ui.radio_value(
+                        &mut self.send_account,
+                        a.account_index,
+                        format!("{} {}", a.account_index, a.label),
+                    )
+                    .on_hover_text(format!("{} unlocked piconero(s)", a.unlocked_balance));
+                }
+            });
+            ui.horizontal(|ui| {
+                let subaddress_label = ui.label("subaddress indices: ");
+                ui.text_edit_singleline(&mut self.send_subaddress)
+                    .labelled_by(subaddress_label.id)
+                    .on_hover_text("comma separated, empty for any");
+            });
+            ui.horizontal(|ui| {
+                if self.is_previewing_send {
+                    ui.add(egui::Spinner::new());
+                } else if ui
+                    .add_enabled(!is_read_only, egui::Button::new("Preview"))
+                    .clicked()
+                {
+                    match self.send_amount.trim().parse::<u128>() {
+                        Ok(amount) => {
+                            self.is_previewing_send = true;
+                            send_preview_req(
+                                self.send_preview_tx.clone(),
+                                ctx.clone(),
+                                self.send_address.trim().to_string(),
+                                amount,
+                                self.send_priority,
+                                self.send_account,
+                                send::parse_indices(&self.send_subaddress),
+                            );
+                        }
+                        Err(_) => self.send_status = String::from("invalid amount"),
+                    }
+                }
+                if !self.is_showing_send_confirmation {
+                    ui.label(&self.send_status);
+                }
+            });
+            ui.label("\n\n");
             ui.heading("Sweep Wallet");
             ui.label(
                 "____________________________________________________________________________\n",
@@ -129,7 +350,10 @@ impl eframe::App for WalletApp {
                 let sweep_label = ui.label("send to: \t");
                 ui.text_edit_singleline(&mut self.sweep_address)
                     .labelled_by(sweep_label.id);
-                if ui.button("Sweep").clicked() {
+                if ui
+                    .add_enabled(!is_read_only, egui::Button::new("Sweep"))
+                    .clicked()
+                {
                     send_sweep_all_req(
                         self.xmr_sweep_all_tx.clone(),
                         ctx.clone(),
@@ -140,6 +364,195 @@ impl eframe::App for WalletApp {
                     self.is_loading = true;
                 }
             });
+            for (priority, fee) in monero::FeePriority::ALL.iter().zip(&self.s_fee_estimate.fees) {
+                ui.label(format!(
+                    "estimated {} fee: {} piconero(s)",
+                    priority.label(),
+                    fee
+                ));
+            }
+            ui.label("\n\n");
+            ui.heading("History");
+            ui.label(
+                "____________________________________________________________________________\n",
+            );
+            ui.horizontal(|ui| {
+                if self.is_syncing_history {
+                    ui.add(egui::Spinner::new());
+                } else if ui.button("Sync").clicked() {
+                    self.is_syncing_history = true;
+                    send_history_sync_req(self.history_sync_tx.clone(), ctx.clone());
+                }
+                let filter = &mut self.history_filter;
+                let mut is_changed = ui.checkbox(&mut filter.is_in, "in").changed();
+                is_changed |= ui.checkbox(&mut filter.is_out, "out").changed();
+                is_changed |= ui.checkbox(&mut filter.is_pending, "pending").changed();
+                is_changed |= ui.checkbox(&mut filter.is_pool, "pool").changed();
+                let subaddress_label = ui.label("subaddress indices: ");
+                is_changed |= ui
+                    .text_edit_singleline(&mut self.history_subaddress)
+                    .labelled_by(subaddress_label.id)
+                    .on_hover_text("comma separated, empty for all")
+                    .changed();
+                if is_changed {
+                    self.history_filter.subaddr_indices =
+                        send::parse_indices(&self.history_subaddress);
+                    self.history = history::find_all(&self.history_filter).unwrap_or_default();
+                }
+            });
+            ui.push_id("history", |ui| {
+                use egui_extras::{
+                    Column,
+                    TableBuilder,
+                };
+                let table = TableBuilder::new(ui)
+                    .striped(true)
+                    .resizable(true)
+                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::initial(100.0).at_least(40.0).clip(true))
+                    .column(Column::initial(100.0).at_least(40.0).clip(true))
+                    .column(Column::auto())
+                    .column(Column::initial(100.0).at_least(40.0).clip(true))
+                    .column(Column::remainder())
+                    .min_scrolled_height(0.0)
+                    .max_scroll_height(200.0);
+                table
+                    .header(20.0, |mut header| {
+                        for title in [
+                            "Date", "Type", "Amount", "Fee", "Confs", "Purpose", "Hash",
+                        ] {
+                            header.col(|ui| {
+                                ui.strong(title);
+                            });
+                        }
+                    })
+                    .body(|mut body| {
+                        for t in &self.history {
+                            body.row(20.0, |mut row| {
+                                row.col(|ui| {
+                                    let date = if t.timestamp == 0 {
+                                        String::from("-")
+                                    } else {
+                                        chrono::DateTime::from_timestamp(t.timestamp as i64, 0)
+                                            .unwrap_or_default()
+                                            .to_string()
+                                    };
+                                    ui.label(date);
+                                });
+                                row.col(|ui| {
+                                    ui.label(format!(
+                                        "{} ({}/{})",
+                                        t.direction, t.account_index, t.subaddr_index
+                                    ));
+                                });
+                                row.col(|ui| {
+                                    ui.label(format!("{}", t.amount));
+                                });
+                                row.col(|ui| {
+                                    ui.label(format!("{}", t.fee));
+                                });
+                                row.col(|ui| {
+                                    ui.label(format!("{}", t.confirmations));
+                                });
+                                row.col(|ui| {
+                                    ui.label(&t.purpose).on_hover_text(&t.reference);
+                                });
+                                row.col(|ui| {
+                                    ui.label(&t.txid);
+                                });
+                            });
+                        }
+                    });
+            });
+            ui.label("\n\n");
+            ui.heading("Payments");
+            ui.label(
+                "____________________________________________________________________________\n",
+            );
+            ui.horizontal(|ui| {
+                if ui.button("Refresh").clicked() {
+                    self.payments = ledger::find_all().unwrap_or_default();
+                }
+                if ui.button("Export CSV").clicked() {
+                    self.payments_export = match ledger::export_csv() {
+                        Ok(path) => format!("exported to: {}", path),
+                        Err(_) => String::from("failed to export payments"),
+                    };
+                }
+                ui.label(&self.payments_export);
+            });
+            use egui_extras::{
+                Column,
+                TableBuilder,
+            };
+            let table = TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(Column::auto())
+                .column(Column::initial(100.0).at_least(40.0).clip(true))
+                .column(Column::initial(100.0).at_least(40.0).clip(true))
+                .column(Column::initial(100.0).at_least(40.0).clip(true))
+                .column(Column::initial(100.0).at_least(40.0).clip(true))
+                .column(Column::remainder())
+                .min_scrolled_height(0.0);
+
+            table
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("Date");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Contact");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Amount");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Fee");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Hash");
+                    });
+                    header.col(|ui| {
+                        ui.strong("JWP Expires");
+                    });
+                })
+                .body(|mut body| {
+                    for p in self.payments.iter().rev() {
+                        let row_height = 20.0;
+                        body.row(row_height, |mut row| {
+                            row.col(|ui| {
+                                let h = chrono::DateTime::from_timestamp(p.created, 0)
+                                    .unwrap_or_default()
+                                    .to_string();
+                                ui.label(h);
+                            });
+                            row.col(|ui| {
+                                ui.label(format!("{}", p.contact));
+                            });
+                            row.col(|ui| {
+                                ui.label(format!("{}", p.amount));
+                            });
+                            row.col(|ui| {
+                                ui.label(format!("{}", p.fee));
+                            });
+                            row.col(|ui| {
+                                ui.label(format!("{}", p.hash));
+                            });
+                            row.col(|ui| {
+                                let expire = if p.expire == 0 {
+                                    String::from("-")
+                                } else {
+                                    format!("block {}", p.expire)
+                                };
+                                ui.label(expire);
+                            });
+                        });
+                    }
+                });
         });
     }
 }
@@ -149,14 +562,101 @@ fn send_address_req(tx: Sender<reqres::XmrRpcAddressResponse>, ctx: egui::Contex
         let wallet_name = String::from(neveko_core::APP_NAME);
         let wallet_password =
             std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-        monero::open_wallet(&wallet_name, &wallet_password).await;
-        let address: reqres::XmrRpcAddressResponse = monero::get_address().await;
-        monero::close_wallet(&wallet_name, &wallet_password).await;
+        let address: reqres::XmrRpcAddressResponse =
+            match monero::open_wallet(&wallet_name, &wallet_password).await {
+                Ok(wallet) => monero::get_address(&wallet).await.unwrap_or_default(),
+                Err(_) => Default::default(),
+            };
         let _ = tx.send(address);
         ctx.request_repaint();
     });
 }
 
+fn send_history_sync_req(tx: Sender<bool>, ctx: egui::Context) {
+    tokio::spawn(async move {
+        let wallet_name = String::from(neveko_core::APP_NAME);
+        let wallet_password =
+            std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+        let is_synced = match monero::open_wallet(&wallet_name, &wallet_password).await {
+            Ok(wallet) => history::sync(&wallet).await.is_ok(),
+            Err(_) => false,
+        };
+        let _ = tx.send(is_synced);
+        ctx.request_repaint();
+    });
+}
+
+fn send_fee_estimate_req(tx: Sender<models::FeeEstimate>, ctx: egui::Context) {
+    tokio::spawn(async move {
+        let fee_estimate = utils::get_fee_estimate().await.unwrap_or_default();
+        let _ = tx.send(fee_estimate);
+        ctx.request_repaint();
+    });
+}
+
+fn send_accounts_req(tx: Sender<Vec<reqres::SubaddressAccount>>, ctx: egui::Context) {
+    tokio::spawn(async move {
+        let wallet_name = String::from(neveko_core::APP_NAME);
+        let wallet_password =
+            std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+        let accounts = match monero::open_wallet(&wallet_name, &wallet_password).await {
+            Ok(wallet) => monero::get_accounts(&wallet)
+                .await
+                .unwrap_or_default()
+                .result
+                .subaddress_accounts,
+            Err(_) => Vec::new(),
+        };
+        let _ = tx.send(accounts);
+        ctx.request_repaint();
+    });
+}
+
+fn send_preview_req(
+    tx: Sender<Result<models::SendPreview, send::SendError>>,
+    ctx: egui::Context,
+    address: String,
+    amount: u128,
+    priority: monero::FeePriority,
+    account_index: u32,
+    subaddr_indices: Vec<u32>,
+) {
+    tokio::spawn(async move {
+        let wallet_name = String::from(neveko_core::APP_NAME);
+        let wallet_password =
+            std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
+        let preview = match monero::open_wallet(&wallet_name, &wallet_password).await {
+            Ok(wallet) => {
+                send::preview(
+                    &wallet,
+                    &address,
+                    amount,
+                    priority,
+                    account_index,
+                    subaddr_indices,
+                )
+                .await
+            }
+            Err(_) => Err(send::SendError::Wallet),
+        };
+        let _ = tx.send(preview);
+        ctx.request_repaint();
+    });
+}
+
+fn send_confirm_req(
+    tx: Sender<Result<String, send::SendError>>,
+    ctx: egui::Context,
+    preview: models::SendPreview,
+    password: String,
+) {
+    tokio::spawn(async move {
+        let sent = send::confirm(&preview, &password).await;
+        let _ = tx.send(sent);
+        ctx.request_repaint();
+    });
+}
+
 fn send_sweep_all_req(
     tx: Sender<reqres::XmrRpcSweepAllResponse>,
     ctx: egui::Context,
@@ -166,9 +666,17 @@ fn send_sweep_all_req(
         let wallet_name = String::from(neveko_core::APP_NAME);
         let wallet_password =
             std::env::var(neveko_core::MONERO_WALLET_PASSWORD).unwrap_or(String::from("password"));
-        monero::open_wallet(&wallet_name, &wallet_password).await;
-        let result: reqres::XmrRpcSweepAllResponse = monero::sweep_all(address).await;
-        monero::close_wallet(&wallet_name, &wallet_password).await;
+        let result: reqres::XmrRpcSweepAllResponse =
+            match monero::open_wallet(&wallet_name, &wallet_password).await {
+                Ok(wallet) => monero::sweep_accounts(&wallet, String::from(&address))
+                    .await
+                    .unwrap_or_default(),
+                Err(_) => Default::default(),
+            };
+        for hash in result.result.tx_hash_list.iter().flatten() {
+            history::annotate(hash, history::Purpose::Sweep, &address)
+                .unwrap_or_else(|_| log::error!("failed to annotate sweep"));
+        }
         let _ = tx.send(result);
         ctx.request_repaint();
     });
@@ -6,11 +6,28 @@ use sha2::{
     Sha512,
 };
 
+/// How the app wallet is set up on first run
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum WalletSetup {
+    Create,
+    Seed,
+    /// From the address and secret keys, view-only without a spend key
+    Keys,
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct LoginApp {
     pub credential: String,
     pub is_cred_generated: bool,
     pub is_not_showing_password: bool,
+    pub wallet_setup: WalletSetup,
+    pub restore_address: String,
+    pub restore_height: String,
+    pub restore_seed: String,
+    pub restore_spend_key: String,
+    pub restore_view_key: String,
+    pub restore_status: String,
 }
 
 impl Default for LoginApp {
@@ -22,7 +39,67 @@ impl Default for LoginApp {
             credential,
             is_cred_generated,
             is_not_showing_password,
+            wallet_setup: WalletSetup::Create,
+            restore_address: String::new(),
+            restore_height: String::new(),
+            restore_seed: String::new(),
+            restore_spend_key: String::new(),
+            restore_view_key: String::new(),
+            restore_status: String::new(),
+        }
+    }
+}
+
+impl LoginApp {
+    /// Pass restore secrets to core through an owner-only file, core removes
+    ///
+    /// it once the app wallet is set up.
+    fn set_wallet_restore(&mut self) -> bool {
+        utils::clear_wallet_restore();
+        if self.wallet_setup == WalletSetup::Create {
+            return true;
+        }
+        let height = self.restore_height.trim();
+        if !height.is_empty() && height.parse::<u64>().is_err() {
+            self.restore_status = String::from("invalid restore height");
+            return false;
+        }
+        let restore = match self.wallet_setup {
+            WalletSetup::Seed => {
+                let seed = self.restore_seed.split_whitespace().collect::<Vec<&str>>().join(" ");
+                if seed.is_empty() {
+                    self.restore_status = String::from("enter the mnemonic seed");
+                    return false;
+                }
+                utils::WalletRestore {
+                    seed,
+                    ..Default::default()
+                }
+            }
+            _ => {
+                if self.restore_address.trim().is_empty()
+                    || self.restore_view_key.trim().is_empty()
+                {
+                    self.restore_status = String::from("enter the address and secret view key");
+                    return false;
+                }
+                utils::WalletRestore {
+                    address: self.restore_address.trim().to_string(),
+                    view_key: self.restore_view_key.trim().to_string(),
+                    spend_key: self.restore_spend_key.trim().to_string(),
+                    ..Default::default()
+                }
+            }
+        };
+        let restore = utils::WalletRestore {
+            restore_height: height.parse::<u64>().unwrap_or(0),
+            ..restore
+        };
+        if utils::set_wallet_restore(&restore).is_err() {
+            self.restore_status = String::from("failed to save the wallet restore file");
+            return false;
         }
+        true
     }
 }
 
@@ -47,7 +124,44 @@ impl eframe::App for LoginApp {
                     self.is_not_showing_password = !self.is_not_showing_password;
                 }
             });
-            if ui.button("Login").clicked() {
+            ui.label("\n");
+            ui.horizontal(|ui| {
+                ui.label("wallet: \t");
+                ui.radio_value(&mut self.wallet_setup, WalletSetup::Create, "create new");
+                ui.radio_value(&mut self.wallet_setup, WalletSetup::Seed, "restore from seed");
+                ui.radio_value(&mut self.wallet_setup, WalletSetup::Keys, "restore from keys");
+            });
+            if self.wallet_setup == WalletSetup::Seed {
+                ui.horizontal(|ui| {
+                    ui.label("seed: \t");
+                    ui.add(egui::TextEdit::multiline(&mut self.restore_seed).password(true));
+                });
+            }
+            if self.wallet_setup == WalletSetup::Keys {
+                ui.horizontal(|ui| {
+                    ui.label("address: \t");
+                    ui.text_edit_singleline(&mut self.restore_address);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("view key: \t");
+                    ui.add(egui::TextEdit::singleline(&mut self.restore_view_key).password(true));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("spend key: \t");
+                    ui.add(egui::TextEdit::singleline(&mut self.restore_spend_key).password(true))
+                        .on_hover_text("leave empty for a view-only audit wallet");
+                });
+            }
+            if self.wallet_setup != WalletSetup::Create {
+                ui.horizontal(|ui| {
+                    ui.label("restore height: \t");
+                    ui.text_edit_singleline(&mut self.restore_height)
+                        .on_hover_text("block height the wallet was created at, empty to scan all");
+                });
+                ui.label("the wallet is restored when core is started from the home screen");
+            }
+            ui.label(&self.restore_status);
+            if ui.button("Login").clicked() && self.set_wallet_restore() {
                 // temporarily set the password to user environment and clear with screenlock
                 // we set it here for the initial launch of neveko
                 std::env::set_var(neveko_core::MONERO_WALLET_PASSWORD, self.credential.clone());
@@ -60,6 +174,9 @@ impl eframe::App for LoginApp {
                 db::write_chunks(&db.env, &db.handle, k.as_bytes(), &v)
                     .unwrap_or_else(|_| log::error!("failed to set credential"));
                 self.credential = String::new();
+                self.restore_seed = String::new();
+                self.restore_spend_key = String::new();
+                self.restore_view_key = String::new();
             }
         });
     }
@@ -236,7 +236,7 @@ impl eframe::App for WrapApp {
         )
         .unwrap_or_else(|_| log::error!("failed to write i2p status."));
         if is_bg != String::from("1") {
-            utils::kill_child_processes(false);
+            utils::kill_child_processes();
         }
     }
 }
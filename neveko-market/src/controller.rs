@@ -17,7 +17,7 @@ use neveko_core::*;
 #[post("/create", data = "<req_product>")]
 pub async fn create_product(
     req_product: Json<models::Product>,
-    _token: auth::BearerToken,
+    _token: auth::MarketAdminToken,
 ) -> Custom<Json<models::Product>> {
     let m_product = product::create(req_product);
     Custom(Status::Created, Json(m_product.unwrap_or_default()))
@@ -25,7 +25,10 @@ pub async fn create_product(
 
 /// Get a product by passing id
 #[get("/<pid>")]
-pub async fn get_product(pid: String, _token: auth::BearerToken) -> Custom<Json<models::Product>> {
+pub async fn get_product(
+    pid: String,
+    _token: auth::MarketAdminToken,
+) -> Custom<Json<models::Product>> {
     let m_product = product::find(&pid);
     Custom(Status::Ok, Json(m_product.unwrap_or_default()))
 }
@@ -34,7 +37,7 @@ pub async fn get_product(pid: String, _token: auth::BearerToken) -> Custom<Json<
 #[patch("/update", data = "<product>")]
 pub async fn update_product(
     product: Json<models::Product>,
-    _token: auth::BearerToken,
+    _token: auth::MarketAdminToken,
 ) -> Custom<Json<models::Product>> {
     let m_product = product::modify(product);
     Custom(Status::Ok, Json(m_product.unwrap_or_default()))
@@ -42,21 +45,24 @@ pub async fn update_product(
 
 /// Return all products
 #[get("/")]
-pub async fn get_products(_token: auth::BearerToken) -> Custom<Json<Vec<models::Product>>> {
+pub async fn get_products(_token: auth::MarketAdminToken) -> Custom<Json<Vec<models::Product>>> {
     let m_products = product::find_all();
     Custom(Status::Ok, Json(m_products.unwrap_or_default()))
 }
 
 /// Get a order by passing id
 #[get("/<orid>")]
-pub async fn get_order(orid: String, _token: auth::BearerToken) -> Custom<Json<models::Order>> {
+pub async fn get_order(
+    orid: String,
+    _token: auth::MarketAdminToken,
+) -> Custom<Json<models::Order>> {
     let m_order = order::find(&orid);
     Custom(Status::Ok, Json(m_order.unwrap_or_default()))
 }
 
 /// Get all orders
 #[get("/")]
-pub async fn get_orders(_token: auth::BearerToken) -> Custom<Json<Vec<models::Order>>> {
+pub async fn get_orders(_token: auth::MarketAdminToken) -> Custom<Json<Vec<models::Order>>> {
     let m_orders = order::find_all();
     Custom(Status::Ok, Json(m_orders.unwrap_or_default()))
 }
@@ -65,7 +71,7 @@ pub async fn get_orders(_token: auth::BearerToken) -> Custom<Json<Vec<models::Or
 #[patch("/update", data = "<order>")]
 pub async fn update_order(
     order: Json<models::Order>,
-    _token: auth::BearerToken,
+    _token: auth::MarketAdminToken,
 ) -> Custom<Json<models::Order>> {
     let m_order = order::modify(order);
     Custom(Status::Ok, Json(m_order.unwrap_or_default()))
@@ -75,7 +81,7 @@ pub async fn update_order(
 #[post("/create", data = "<dispute>")]
 pub async fn create_dispute(
     dispute: Json<models::Dispute>,
-    _token: auth::BearerToken,
+    _token: auth::MarketAdminToken,
 ) -> Custom<Json<models::Dispute>> {
     let m_dispute = dispute::create(dispute);
     Custom(Status::Ok, Json(m_dispute.unwrap_or_default()))
@@ -83,7 +89,10 @@ pub async fn create_dispute(
 
 /// Fetch a dispute
 #[get("/<did>")]
-pub async fn get_dispute(_token: auth::BearerToken, did: String) -> Custom<Json<models::Dispute>> {
+pub async fn get_dispute(
+    _token: auth::MarketAdminToken,
+    did: String,
+) -> Custom<Json<models::Dispute>> {
     let m_dispute = dispute::find(&did);
     Custom(Status::Ok, Json(m_dispute.unwrap_or_default()))
 }
@@ -92,7 +101,7 @@ pub async fn get_dispute(_token: auth::BearerToken, did: String) -> Custom<Json<
 #[post("/sign/submit", data = "<r_data>")]
 pub async fn sign_and_submit_multisig(
     r_data: Json<reqres::SignAndSubmitRequest>,
-    _token: auth::BearerToken,
+    _token: auth::WalletToken,
 ) -> Custom<Json<reqres::SignAndSubmitRequest>> {
     let result: reqres::XmrRpcSubmitMultisigResponse =
         order::sign_and_submit_multisig(&r_data.orid, &r_data.txset).await;
@@ -111,7 +120,7 @@ pub async fn sign_and_submit_multisig(
 pub async fn upload_delivery_info(
     orid: String,
     r_data: Json<reqres::FinalizeOrderResponse>,
-    _token: auth::BearerToken,
+    _token: auth::MarketAdminToken,
 ) -> Custom<Json<reqres::FinalizeOrderResponse>> {
     let upload = order::upload_delivery_info(&orid, &r_data.delivery_info).await;
     let u_upload = upload.unwrap_or_default();
@@ -124,7 +133,7 @@ pub async fn upload_delivery_info(
 /// toggle vendor mode
 #[get("/")]
 pub async fn toggle_vendor_mode(
-    _token: auth::BearerToken,
+    _token: auth::MarketAdminWriteToken,
 ) -> Custom<Json<reqres::VendorModeResponse>> {
     let mode = utils::toggle_vendor_enabled().unwrap_or_default();
     Custom(Status::Ok, Json(reqres::VendorModeResponse { mode }))
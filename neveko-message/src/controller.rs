@@ -23,6 +23,7 @@ pub async fn send_message(
     m_req: Json<Message>,
     r_type: String,
     token: proof::PaymentProof,
+    _m_token: auth::MessagesToken,
 ) -> Custom<Json<Message>> {
     let m_type: message::MessageType = if r_type == "multisig" {
         message::MessageType::Multisig
@@ -35,14 +36,17 @@ pub async fn send_message(
 
 /// Return all messages
 #[get("/")]
-pub async fn get_messages(_token: auth::BearerToken) -> Custom<Json<Vec<Message>>> {
+pub async fn get_messages(_token: auth::MessagesToken) -> Custom<Json<Vec<Message>>> {
     let messages = message::find_all();
     Custom(Status::Ok, Json(messages.unwrap_or_default()))
 }
 
 /// Delete a message by mid
 #[delete("/<mid>")]
-pub async fn remove_message(mid: String, _token: auth::BearerToken) -> Custom<Json<Vec<Message>>> {
+pub async fn remove_message(
+    mid: String,
+    _token: auth::MessagesToken,
+) -> Custom<Json<Vec<Message>>> {
     let _ = message::delete(&mid);
     let messages = message::find_all();
     Custom(Status::Ok, Json(messages.unwrap_or_default()))
@@ -52,7 +56,7 @@ pub async fn remove_message(mid: String, _token: auth::BearerToken) -> Custom<Js
 #[get("/<mid>")]
 pub async fn decipher(
     mid: String,
-    _token: auth::BearerToken,
+    _token: auth::MessagesToken,
 ) -> Custom<Json<reqres::DecipheredMessageBody>> {
     let d_message = message::decipher_body(mid).await;
     Custom(Status::Ok, Json(d_message.unwrap_or_default()))
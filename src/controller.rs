@@ -21,7 +21,10 @@ use neveko_core::*;
 /// This also functions as a health check
 #[get("/version")]
 pub async fn get_version(_jwp: proof::PaymentProof) -> Custom<Json<reqres::XmrRpcVersionResponse>> {
-    Custom(Status::Ok, Json(monero::get_version().await))
+    match monero::get_version().await {
+        Ok(v) => Custom(Status::Ok, Json(v)),
+        Err(_) => Custom(Status::ServiceUnavailable, Json(Default::default())),
+    }
 }
 
 /// If i2p not in the state of rejecting tunnels this will return `open: true`
@@ -53,7 +56,7 @@ pub async fn share_contact_info() -> Custom<Json<models::Contact>> {
 /// Protected: true
 #[post("/", data = "<message>")]
 pub async fn rx_message(
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MessageProof,
     message: Json<models::Message>,
 ) -> Custom<Json<models::Message>> {
     let _ = message::rx(message).await;
@@ -64,26 +67,80 @@ pub async fn rx_message(
 ///
 /// Protected: false
 #[get("/")]
-pub async fn gen_invoice() -> Custom<Json<reqres::Invoice>> {
-    let invoice = proof::create_invoice().await;
-    Custom(Status::Ok, Json(invoice))
+pub async fn gen_invoice(peer: peer::Peer) -> Custom<Json<reqres::Invoice>> {
+    let invoice = proof::create_invoice(&peer).await;
+    match invoice {
+        Ok(i) => Custom(Status::Ok, Json(i)),
+        Err(proof::InvoiceError::RateLimited) => {
+            Custom(Status::TooManyRequests, Json(Default::default()))
+        }
+        Err(proof::InvoiceError::Unavailable) => {
+            Custom(Status::ServiceUnavailable, Json(Default::default()))
+        }
+    }
 }
 
-/// jwp generation
+/// proof-of-work challenge generation
+///
+/// Protected: false
+#[get("/")]
+pub async fn gen_pow_challenge(peer: peer::Peer) -> Custom<Json<reqres::PowChallenge>> {
+    match pow::issue(&peer) {
+        Ok(c) => Custom(Status::Ok, Json(c)),
+        Err(pow::PowError::RateLimited) => {
+            Custom(Status::TooManyRequests, Json(Default::default()))
+        }
+    }
+}
+
+/// jwp generation, the request must be signed with `peer::get_auth_headers`
 ///
 /// Protected: false
 #[post("/", data = "<proof>")]
-pub async fn gen_jwp(proof: Json<proof::TxProof>) -> Custom<Json<reqres::Jwp>> {
-    let jwp = proof::create_jwp(&proof).await;
+pub async fn gen_jwp(
+    peer: peer::Peer,
+    proof: Json<proof::TxProof>,
+) -> Custom<Json<reqres::Jwp>> {
+    let jwp = proof::create_jwp(&peer, &proof).await;
     Custom(Status::Ok, Json(reqres::Jwp { jwp }))
 }
 
+/// Top up prepaid credit with a transaction proof
+///
+/// Protected: false
+#[post("/", data = "<proof>")]
+pub async fn top_up_credit(
+    peer: peer::Peer,
+    proof: Json<proof::TxProof>,
+) -> Custom<Json<reqres::Credit>> {
+    let credit = credit::top_up(&peer, &proof).await;
+    match credit {
+        Ok(c) => Custom(Status::Ok, Json(c)),
+        Err(_) => Custom(Status::PaymentRequired, Json(Default::default())),
+    }
+}
+
+/// Prepaid credit balance and usage history of the requester
+///
+/// Protected: true
+#[get("/")]
+pub async fn get_credit(
+    _credit: proof::CreditToken,
+    peer: peer::Peer,
+) -> Custom<Json<models::CreditAccount>> {
+    let account = credit::find(&peer.destination);
+    Custom(Status::Ok, Json(account.unwrap_or_default()))
+}
+
 // NEVEKO Market APIs
 //-----------------------------------------------
 
 /// Get a product by passing id
 #[get("/<pid>")]
-pub async fn get_product(pid: String, _jwp: proof::PaymentProof) -> Custom<Json<models::Product>> {
+pub async fn get_product(
+    pid: String,
+    _jwp: proof::MarketReadProof,
+) -> Custom<Json<models::Product>> {
     let m_product = product::find(&pid);
     Custom(Status::Ok, Json(m_product.unwrap_or_default()))
 }
@@ -92,7 +149,7 @@ pub async fn get_product(pid: String, _jwp: proof::PaymentProof) -> Custom<Json<
 ///
 /// Protected: true
 #[get("/products")]
-pub async fn get_products(_jwp: proof::PaymentProof) -> Custom<Json<Vec<models::Product>>> {
+pub async fn get_products(_jwp: proof::MarketReadProof) -> Custom<Json<Vec<models::Product>>> {
     let m_products: Vec<models::Product> = product::find_all().unwrap_or_default();
     Custom(Status::Ok, Json(m_products))
 }
@@ -103,24 +160,47 @@ pub async fn get_products(_jwp: proof::PaymentProof) -> Custom<Json<Vec<models::
 #[post("/order/create", data = "<r_order>")]
 pub async fn create_order(
     r_order: Json<reqres::OrderRequest>,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<models::Order>> {
     let m_order = order::create(r_order).await;
     Custom(Status::Created, Json(m_order.unwrap_or_default()))
 }
 
+/// Issue a single use challenge for order retrieval or cancellation
+///
+/// to the customer of the order. `action` is either `retrieve` or `cancel`.
+///
+/// Protected: true
+#[get("/order/challenge/<orid>/<action>")]
+pub async fn get_order_challenge(
+    orid: String,
+    action: String,
+    _jwp: proof::MarketOrderProof,
+    peer: peer::Peer,
+) -> Custom<Json<models::OrderChallenge>> {
+    let m_action = match order::ChallengeAction::from_value(&action) {
+        Some(a) => a,
+        None => return Custom(Status::BadRequest, Json(Default::default())),
+    };
+    match order::issue_challenge(&peer, &orid, &m_action) {
+        Ok(c) => Custom(Status::Created, Json(c)),
+        Err(_) => Custom(Status::BadRequest, Json(Default::default())),
+    }
+}
+
 /// Customer order retreival. Must send `signature`
 ///
-/// which is the order id signed by the NEVEKO wallet.
+/// which is the retrieval challenge signed by the NEVEKO wallet.
 ///
 /// Protected: true
-#[get("/order/retrieve/<orid>/<signature>")]
+#[get("/order/retrieve/<orid>/<nonce>/<signature>")]
 pub async fn retrieve_order(
     orid: String,
+    nonce: String,
     signature: String,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<models::Order>> {
-    let r_m_order = order::secure_retrieval(&orid, &signature).await;
+    let r_m_order = order::secure_retrieval(&orid, &nonce, &signature).await;
     let m_order = r_m_order.unwrap_or_default();
     if m_order.cid.is_empty() {
         return Custom(Status::BadRequest, Json(Default::default()));
@@ -134,7 +214,7 @@ pub async fn retrieve_order(
 #[post("/", data = "<r_info>")]
 pub async fn get_multisig_info(
     r_info: Json<reqres::MultisigInfoRequest>,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<models::Order>> {
     let info: Vec<String> = r_info.info.to_vec();
     if r_info.msig_type == *message::PREPARE_MSIG {
@@ -161,7 +241,7 @@ pub async fn get_multisig_info(
 /// Protected: true
 #[post("/", data = "<message>")]
 pub async fn rx_multisig_message(
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
     message: Json<models::Message>,
 ) -> Custom<Json<models::Message>> {
     let _ = message::rx_multisig(message).await;
@@ -182,7 +262,7 @@ pub async fn rx_multisig_message(
 #[post("/ship/<orid>")]
 pub async fn request_shipment(
     orid: String,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<reqres::FinalizeOrderResponse>> {
     let r_finalize = order::validate_order_for_ship(&orid).await;
     let finalize = r_finalize.unwrap_or_default();
@@ -205,7 +285,7 @@ pub async fn request_shipment(
 pub async fn trigger_nasr(
     orid: String,
     vendor: String,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<models::Order>> {
     let r_order = order::d_trigger_ship_request(&vendor, &orid).await;
     let order = r_order.unwrap_or_default();
@@ -217,16 +297,17 @@ pub async fn trigger_nasr(
 
 /// Customer cancel order logic. Must send `signature`
 ///
-/// which is the order id signed by the NEVEKO wallet.
+/// which is the cancel challenge signed by the NEVEKO wallet.
 ///
 /// Protected: true
-#[post("/order/cancel/<orid>/<signature>")]
+#[post("/order/cancel/<orid>/<nonce>/<signature>")]
 pub async fn cancel_order(
     orid: String,
+    nonce: String,
     signature: String,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<models::Order>> {
-    let r_m_order = order::cancel_order(&orid, &signature).await;
+    let r_m_order = order::cancel_order(&orid, &nonce, &signature).await;
     let m_order = r_m_order.unwrap_or_default();
     if m_order.cid.is_empty() {
         return Custom(Status::BadRequest, Json(Default::default()));
@@ -242,7 +323,7 @@ pub async fn cancel_order(
 #[post("/order/finalize/<orid>")]
 pub async fn finalize_order(
     orid: String,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<reqres::FinalizeOrderResponse>> {
     let r_finalize = order::finalize_order(&orid).await;
     let finalize = r_finalize.unwrap_or_default();
@@ -256,7 +337,7 @@ pub async fn finalize_order(
 #[post("/dispute/create", data = "<dispute>")]
 pub async fn create_dispute(
     dispute: Json<models::Dispute>,
-    _jwp: proof::PaymentProof,
+    _jwp: proof::MarketOrderProof,
 ) -> Custom<Json<models::Dispute>> {
     let r_m_dispute = dispute::create(dispute);
     let m_dispute = r_m_dispute.unwrap_or_default();
@@ -3,9 +3,12 @@ extern crate rocket;
 
 use neveko::*;
 use neveko_core::*;
-use rocket::data::{
-    Limits,
-    ToByteUnit,
+use rocket::{
+    data::{
+        Limits,
+        ToByteUnit,
+    },
+    fairing::AdHoc,
 };
 
 // The only changes below here should be mounting new controller methods
@@ -19,8 +22,17 @@ async fn rocket() -> _ {
         ..rocket::Config::debug_default()
     };
     env_logger::init();
-    let _ = utils::start_up().await;
+    if utils::start_up().await.is_err() {
+        // the cause is logged by start_up
+        let _ = rocket::tokio::task::spawn_blocking(utils::kill_child_processes).await;
+        std::process::exit(1);
+    }
     rocket::custom(&config)
+        .attach(AdHoc::on_shutdown("Stop child processes", |_| {
+            Box::pin(async {
+                let _ = rocket::tokio::task::spawn_blocking(utils::kill_child_processes).await;
+            })
+        }))
         .register(
             "/",
             catchers![
@@ -31,12 +43,17 @@ async fn rocket() -> _ {
         )
         .mount("/multisig/info", routes![controller::get_multisig_info])
         .mount("/invoice", routes![controller::gen_invoice])
+        .mount("/pow", routes![controller::gen_pow_challenge])
         .mount("/message/rx", routes![controller::rx_message])
         .mount(
             "/message/rx/multisig",
             routes![controller::rx_multisig_message],
         )
         .mount("/prove", routes![controller::gen_jwp])
+        .mount(
+            "/credit",
+            routes![controller::get_credit, controller::top_up_credit],
+        )
         .mount("/share", routes![controller::share_contact_info])
         .mount("/i2p", routes![controller::get_i2p_status])
         .mount("/xmr/rpc", routes![controller::get_version])
@@ -48,7 +65,9 @@ async fn rocket() -> _ {
                 controller::get_product,
                 controller::get_products,
                 controller::request_shipment,
+                controller::get_order_challenge,
                 controller::retrieve_order,
+                controller::cancel_order,
                 controller::trigger_nasr,
                 controller::finalize_order,
             ],